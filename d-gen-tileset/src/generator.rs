@@ -1,7 +1,7 @@
 use image::{Rgba, RgbaImage};
 
 use crate::layout;
-use droneforge_core::{AIR, BEDROCK, BlockId, DIRT, IRON, STONE};
+use droneforge_core::{BlockId, BlockRegistry};
 
 #[derive(Clone, Copy)]
 struct Color {
@@ -31,14 +31,8 @@ fn color_to_rgba(color: Color) -> Rgba<u8> {
 }
 
 fn palette_color(block: BlockId) -> Color {
-    match block {
-        AIR => color_from_rgba(0, 0, 0, 0),
-        DIRT => color_from_rgba(143, 99, 63, 255),
-        STONE => color_from_rgba(120, 120, 120, 255),
-        IRON => color_from_rgba(194, 133, 74, 255),
-        BEDROCK => color_from_rgba(45, 45, 45, 255),
-        _ => color_from_rgba(255, 0, 255, 255),
-    }
+    let [r, g, b, a] = BlockRegistry::global().color(block);
+    color_from_rgba(r, g, b, a)
 }
 
 fn apply_saturation_and_brightness(color: Color, saturation: f32, brightness: f32) -> Color {
@@ -233,14 +227,14 @@ pub fn build_tileset_image() -> RgbaImage {
     let mut atlas = RgbaImage::from_pixel(width_px, height_px, Rgba([0, 0, 0, 0]));
     let wall_masks = 0u8..(layout::WALL_MASK_VARIANTS as u8);
 
-    for &block in layout::SOLID_BLOCKS.iter() {
+    for &block in layout::TILED_BLOCKS.iter() {
         let base_color = palette_color(block);
         let pos = layout::floor_tile_position(block)
             .expect("floor tile position should exist for solid block");
         fill_block(&mut atlas, pos.tile_x, pos.tile_y, base_color);
     }
 
-    for &block in layout::SOLID_BLOCKS.iter() {
+    for &block in layout::TILED_BLOCKS.iter() {
         let source_color = palette_color(block);
         let base_color = wall_base_tint(source_color);
         let edge_color = wall_edge_tint(source_color);
@@ -9,7 +9,11 @@ pub const MASK_EAST: u8 = 2;
 pub const MASK_SOUTH: u8 = 4;
 pub const MASK_WEST: u8 = 8;
 
-pub const SOLID_BLOCKS: [BlockId; 4] = [DIRT, STONE, IRON, BEDROCK];
+/// Blocks with floor and wall tiles in the atlas, in atlas order. The atlas is baked, so this
+/// stays fixed; it must list the same blocks as [`BlockRegistry::tiled_blocks`].
+///
+/// [`BlockRegistry::tiled_blocks`]: droneforge_core::BlockRegistry::tiled_blocks
+pub const TILED_BLOCKS: [BlockId; 4] = [DIRT, STONE, IRON, BEDROCK];
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct TilePosition {
@@ -18,14 +22,14 @@ pub struct TilePosition {
 }
 
 pub fn solid_block_index(block: BlockId) -> Option<u32> {
-    SOLID_BLOCKS
+    TILED_BLOCKS
         .iter()
         .position(|&candidate| candidate == block)
         .map(|idx| idx as u32)
 }
 
 pub const fn solid_block_count() -> u32 {
-    SOLID_BLOCKS.len() as u32
+    TILED_BLOCKS.len() as u32
 }
 
 pub const fn floor_tile_count() -> u32 {
@@ -38,7 +42,7 @@ pub const fn total_tile_count() -> u32 {
 
 pub const fn tile_grid_size() -> (u32, u32) {
     let columns = TILESET_COLUMNS;
-    let rows = (total_tile_count() + columns - 1) / columns;
+    let rows = total_tile_count().div_ceil(columns);
     (columns, rows)
 }
 
@@ -78,3 +82,14 @@ pub fn tile_pixel_position(tile_index: u32) -> (u32, u32) {
     let pos = tile_position_for_index(tile_index);
     (pos.tile_x * BLOCK_PIXEL_SIZE, pos.tile_y * BLOCK_PIXEL_SIZE)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use droneforge_core::BlockRegistry;
+
+    #[test]
+    fn tiled_blocks_match_the_registry() {
+        assert_eq!(BlockRegistry::global().tiled_blocks(), TILED_BLOCKS);
+    }
+}
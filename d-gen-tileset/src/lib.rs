@@ -2,3 +2,6 @@ pub mod layout;
 
 #[cfg(feature = "generator")]
 pub mod generator;
+
+#[cfg(feature = "generator")]
+pub mod map_image;
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use droneforge_core::{
+    AIR, BlockId, BlockRegistry, ImportedMap, MapImportError, MapImportErrorReason, WorldCoord,
+};
+
+/// Maps a pixel to the block whose registry colour it matches. Fully transparent pixels are air.
+pub fn block_for_color(pixel: Rgba<u8>) -> Option<BlockId> {
+    if pixel[3] == 0 {
+        return Some(AIR);
+    }
+    BlockRegistry::global()
+        .definitions()
+        .find(|definition| definition.color == pixel.0)
+        .map(|definition| definition.id)
+}
+
+/// Builds a map from one image per level: pixel `(x, y)` of the image for level `z` becomes
+/// the block at `(x, y, z)`.
+pub fn import_level_images(
+    levels: impl IntoIterator<Item = (i32, RgbaImage)>,
+) -> Result<ImportedMap, MapImportError> {
+    let mut map = ImportedMap::new();
+    let mut seen_levels = HashSet::new();
+
+    for (z, image) in levels {
+        if !seen_levels.insert(z) {
+            return Err(MapImportError::new(
+                MapImportErrorReason::DuplicateLayer(z),
+                0,
+                0,
+            ));
+        }
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let block = block_for_color(*pixel).ok_or_else(|| {
+                MapImportError::new(
+                    MapImportErrorReason::UnknownColor(pixel.0),
+                    y as usize + 1,
+                    x as usize + 1,
+                )
+            })?;
+            map.set_block(WorldCoord::new(x as i32, y as i32, z), block);
+        }
+    }
+
+    Ok(map)
+}
+
+/// Reads one PNG per level, as described in [`import_level_images`].
+pub fn import_level_pngs(levels: &[(i32, &Path)]) -> Result<ImportedMap, MapImportError> {
+    let images = levels
+        .iter()
+        .map(|&(z, path)| {
+            image::open(path)
+                .map(|image| (z, image.to_rgba8()))
+                .map_err(|err| {
+                    let detail = format!("{}: {err}", path.display());
+                    MapImportError::new(MapImportErrorReason::Image(detail), 0, 0)
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    import_level_images(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use droneforge_core::worldgen::WorldGenerator;
+    use droneforge_core::{BEDROCK, CORE, DIRT, IRON, STONE};
+
+    fn level_image(rows: &[&[BlockId]]) -> RgbaImage {
+        let registry = BlockRegistry::global();
+        RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            match rows[y as usize][x as usize] {
+                AIR => Rgba([0, 0, 0, 0]),
+                block => Rgba(registry.color(block)),
+            }
+        })
+    }
+
+    #[test]
+    fn level_images_round_trip_through_block_colours() {
+        let rows: [&[BlockId]; 2] = [&[STONE, DIRT, AIR], &[CORE, IRON, STONE]];
+        let map = import_level_images([(-1, level_image(&rows))]).unwrap();
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &block) in row.iter().enumerate() {
+                assert_eq!(map.block_at(WorldCoord::new(x as i32, y as i32, -1)), block);
+            }
+        }
+        assert_eq!(map.block_at(WorldCoord::new(0, 0, 0)), AIR);
+
+        let path = std::env::temp_dir().join(format!("map-image-{}.png", std::process::id()));
+        level_image(&rows).save(&path).unwrap();
+        let from_png = import_level_pngs(&[(-1, path.as_path())]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_png.unwrap().to_chunk_blocks(), map.to_chunk_blocks());
+    }
+
+    #[test]
+    fn reports_unknown_colours_with_position() {
+        let mut image = level_image(&[&[STONE, STONE, STONE], &[STONE, STONE, STONE]]);
+        image.put_pixel(2, 1, Rgba([1, 2, 3, 255]));
+
+        let err = import_level_images([(0, image)]).unwrap_err();
+
+        assert_eq!(
+            err.reason,
+            MapImportErrorReason::UnknownColor([1, 2, 3, 255])
+        );
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(block_for_color(Rgba([9, 9, 9, 0])), Some(AIR));
+    }
+
+    #[test]
+    fn block_colours_match_the_published_level_format() {
+        assert_eq!(block_for_color(Rgba([143, 99, 63, 255])), Some(DIRT));
+        assert_eq!(block_for_color(Rgba([120, 120, 120, 255])), Some(STONE));
+        assert_eq!(block_for_color(Rgba([194, 133, 74, 255])), Some(IRON));
+        assert_eq!(block_for_color(Rgba([45, 45, 45, 255])), Some(BEDROCK));
+        assert_eq!(block_for_color(Rgba([90, 200, 250, 255])), Some(CORE));
+    }
+}
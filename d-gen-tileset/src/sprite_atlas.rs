@@ -325,10 +325,10 @@ fn sample_color(spec: &DroneSpec, svg_x: f32, svg_y: f32) -> Option<Rgba<u8>> {
     let dy = svg_y - spec.circle.cy;
     let dist = (dx * dx + dy * dy).sqrt();
 
-    if let Some(fill) = spec.circle.fill {
-        if dist <= spec.circle.r + SAMPLE_EPSILON {
-            color = Some(fill);
-        }
+    if let Some(fill) = spec.circle.fill
+        && dist <= spec.circle.r + SAMPLE_EPSILON
+    {
+        color = Some(fill);
     }
 
     if let Some(stroke) = spec.circle.stroke {
@@ -338,10 +338,10 @@ fn sample_color(spec: &DroneSpec, svg_x: f32, svg_y: f32) -> Option<Rgba<u8>> {
         }
     }
 
-    if let Some(line_color) = spec.line.stroke {
-        if point_on_line(&spec.line, svg_x, svg_y) {
-            color = Some(line_color);
-        }
+    if let Some(line_color) = spec.line.stroke
+        && point_on_line(&spec.line, svg_x, svg_y)
+    {
+        color = Some(line_color);
     }
 
     color
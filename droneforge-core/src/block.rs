@@ -1,4 +1,8 @@
+use crate::item::ItemId;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
 
 pub type BlockId = u16;
 
@@ -8,6 +12,8 @@ pub const STONE: BlockId = 2;
 pub const IRON: BlockId = 3;
 pub const BEDROCK: BlockId = 4;
 pub const CORE: BlockId = 5;
+/// Open floor marked out for a warrior to stand on.
+pub const WARRIOR_SPOT: BlockId = 6;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Block {
@@ -25,7 +31,174 @@ impl Block {
 }
 
 pub fn is_placable_block(block: BlockId) -> bool {
-    block == CORE
+    BlockRegistry::global().is_placeable(block)
+}
+
+/// Everything the game needs to know about one kind of block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockDefinition {
+    pub id: BlockId,
+    pub name: String,
+    /// Solid blocks stop movement and line of sight.
+    pub solid: bool,
+    pub diggable: bool,
+    pub dig_seconds: f32,
+    /// What a drone collects when it digs the block out, if anything.
+    pub drop: Option<ItemId>,
+    pub placeable: bool,
+    pub color: [u8; 4],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockRegistryErrorReason {
+    Json(String),
+    DuplicateId(BlockId),
+    MissingAir,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRegistryError {
+    pub reason: BlockRegistryErrorReason,
+}
+
+impl BlockRegistryError {
+    pub fn new(reason: BlockRegistryErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            BlockRegistryErrorReason::Json(_) => "block definitions are not valid",
+            BlockRegistryErrorReason::DuplicateId(_) => "block id is defined twice",
+            BlockRegistryErrorReason::MissingAir => "block definitions need a non-solid air block",
+        }
+    }
+}
+
+impl fmt::Display for BlockRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            BlockRegistryErrorReason::Json(detail) => write!(f, "{} ({})", self.message(), detail),
+            BlockRegistryErrorReason::DuplicateId(id) => write!(f, "{} ({})", self.message(), id),
+            BlockRegistryErrorReason::MissingAir => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for BlockRegistryError {}
+
+const BUILTIN_BLOCKS: &str = include_str!("../assets/blocks.json");
+
+static GLOBAL_REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+/// Block properties, indexed by [`BlockId`]. Ids without a definition behave like an
+/// unbreakable wall.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockRegistry {
+    definitions: Vec<Option<BlockDefinition>>,
+}
+
+impl BlockRegistry {
+    pub fn from_definitions(
+        definitions: impl IntoIterator<Item = BlockDefinition>,
+    ) -> Result<Self, BlockRegistryError> {
+        let mut slots: Vec<Option<BlockDefinition>> = Vec::new();
+        for definition in definitions {
+            let index = definition.id as usize;
+            if slots.len() <= index {
+                slots.resize(index + 1, None);
+            }
+            if slots[index].is_some() {
+                return Err(BlockRegistryError::new(
+                    BlockRegistryErrorReason::DuplicateId(definition.id),
+                ));
+            }
+            slots[index] = Some(definition);
+        }
+
+        let registry = Self { definitions: slots };
+        if registry.get(AIR).is_none_or(|air| air.solid) {
+            return Err(BlockRegistryError::new(
+                BlockRegistryErrorReason::MissingAir,
+            ));
+        }
+        Ok(registry)
+    }
+
+    /// Parses a JSON array of [`BlockDefinition`]s.
+    pub fn from_json(source: &str) -> Result<Self, BlockRegistryError> {
+        let definitions: Vec<BlockDefinition> = serde_json::from_str(source).map_err(|err| {
+            BlockRegistryError::new(BlockRegistryErrorReason::Json(err.to_string()))
+        })?;
+        Self::from_definitions(definitions)
+    }
+
+    /// The blocks shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_BLOCKS).expect("built-in block definitions must be valid")
+    }
+
+    /// The registry every block query goes through. It is the built-in one unless
+    /// [`BlockRegistry::install`] ran first.
+    pub fn global() -> &'static BlockRegistry {
+        GLOBAL_REGISTRY.get_or_init(Self::builtin)
+    }
+
+    /// Makes `self` the global registry. Fails, handing the registry back, once the global
+    /// registry has been used or installed.
+    pub fn install(self) -> Result<(), BlockRegistry> {
+        GLOBAL_REGISTRY.set(self)
+    }
+
+    pub fn get(&self, block: BlockId) -> Option<&BlockDefinition> {
+        self.definitions.get(block as usize)?.as_ref()
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = &BlockDefinition> {
+        self.definitions.iter().flatten()
+    }
+
+    pub fn name(&self, block: BlockId) -> &str {
+        self.get(block)
+            .map_or("Unknown", |definition| &definition.name)
+    }
+
+    pub fn is_solid(&self, block: BlockId) -> bool {
+        self.get(block).is_none_or(|definition| definition.solid)
+    }
+
+    pub fn is_diggable(&self, block: BlockId) -> bool {
+        self.get(block)
+            .is_some_and(|definition| definition.diggable)
+    }
+
+    pub fn dig_seconds(&self, block: BlockId) -> f32 {
+        self.get(block)
+            .map_or(0.0, |definition| definition.dig_seconds)
+    }
+
+    pub fn drop(&self, block: BlockId) -> Option<ItemId> {
+        self.get(block).and_then(|definition| definition.drop)
+    }
+
+    pub fn is_placeable(&self, block: BlockId) -> bool {
+        self.get(block)
+            .is_some_and(|definition| definition.placeable)
+    }
+
+    /// RGBA colour; unknown blocks are magenta so they stand out.
+    pub fn color(&self, block: BlockId) -> [u8; 4] {
+        self.get(block)
+            .map_or([255, 0, 255, 255], |definition| definition.color)
+    }
+
+    /// Solid blocks that are drawn from the terrain tileset rather than as sprites.
+    pub fn tiled_blocks(&self) -> Vec<BlockId> {
+        self.definitions()
+            .filter(|definition| definition.solid && !definition.placeable)
+            .map(|definition| definition.id)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +224,45 @@ mod tests {
         assert_eq!(restored, original);
     }
 
+    #[test]
+    fn builtin_registry_describes_every_block() {
+        let registry = BlockRegistry::builtin();
+
+        assert!(!registry.is_solid(AIR));
+        assert!(registry.is_solid(STONE));
+        assert!(registry.is_diggable(IRON));
+        assert!(!registry.is_diggable(BEDROCK));
+        assert_eq!(registry.drop(DIRT), None);
+        assert_eq!(registry.drop(STONE), Some(crate::item::ITEM_STONE));
+        assert_eq!(registry.name(CORE), "Core");
+        assert_eq!(registry.tiled_blocks(), vec![DIRT, STONE, IRON, BEDROCK]);
+    }
+
+    #[test]
+    fn unknown_blocks_are_undiggable_walls() {
+        let registry = BlockRegistry::builtin();
+
+        assert!(registry.is_solid(999));
+        assert!(!registry.is_diggable(999));
+        assert!(!registry.is_placeable(999));
+        assert_eq!(registry.color(999), [255, 0, 255, 255]);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_missing_air() {
+        let duplicate = r#"[
+            { "id": 0, "name": "Air", "solid": false, "diggable": false, "dig_seconds": 0.0,
+              "drop": null, "placeable": false, "color": [0, 0, 0, 0] },
+            { "id": 0, "name": "Void", "solid": false, "diggable": false, "dig_seconds": 0.0,
+              "drop": null, "placeable": false, "color": [0, 0, 0, 0] }
+        ]"#;
+        let err = BlockRegistry::from_json(duplicate).unwrap_err();
+        assert_eq!(err.reason, BlockRegistryErrorReason::DuplicateId(0));
+
+        let err = BlockRegistry::from_json("[]").unwrap_err();
+        assert_eq!(err.reason, BlockRegistryErrorReason::MissingAir);
+    }
+
     #[test]
     fn placable_block_flags_core_only() {
         assert!(is_placable_block(CORE));
@@ -33,9 +33,11 @@ pub struct Chunk {
     blocks: Vec<BlockId>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChunkError {
     InvalidBlockCount(usize),
+    /// A packed index that points past the end of the chunk's palette.
+    InvalidPaletteIndex(usize),
     OutOfBounds,
 }
 
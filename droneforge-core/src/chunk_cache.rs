@@ -2,9 +2,13 @@ use crate::block::{AIR, BlockId};
 use crate::chunk::{CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH, Chunk, ChunkBlocks, ChunkError};
 use crate::coordinates::{ChunkPosition, LocalBlockCoord, WorldCoord};
 use crate::linecast::first_solid_supercover;
-use crate::worldgen::{DeterministicMap, HORIZONTAL_LIMIT, VERTICAL_LIMIT};
-use std::collections::HashMap;
+use crate::pathfind::{PathError, find_path};
+use crate::worldgen::{HORIZONTAL_LIMIT, VERTICAL_LIMIT, WorldGenerator};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::ops::RangeInclusive;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct CachedChunk {
@@ -13,6 +17,16 @@ pub struct CachedChunk {
     blocks: Vec<u64>,
     bits_per_index: u8,
     changed: bool,
+    dirty: bool,
+}
+
+/// A chunk in the same palette-packed form the cache keeps in memory, used for delta saves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedChunk {
+    pub position: ChunkPosition,
+    pub palette: Vec<BlockId>,
+    pub bits_per_index: u8,
+    pub blocks: Vec<u64>,
 }
 
 impl CachedChunk {
@@ -31,6 +45,7 @@ impl CachedChunk {
             blocks,
             bits_per_index,
             changed,
+            dirty: changed,
         }
     }
 
@@ -48,12 +63,148 @@ impl CachedChunk {
         self.palette
             .get(palette_index as usize)
             .copied()
-            .ok_or(ChunkError::InvalidBlockCount(palette_index as usize))
+            .ok_or(ChunkError::InvalidPaletteIndex(palette_index as usize))
+    }
+
+    /// Writes one block without unpacking the chunk. A block new to the chunk is appended to
+    /// the palette, and the indices are re-encoded only when the palette outgrows
+    /// `bits_per_index`.
+    pub fn set_block(&mut self, coord: LocalBlockCoord, block: BlockId) -> Result<(), ChunkError> {
+        let index = Chunk::block_index(coord)?;
+        let palette_index = match self.palette.iter().position(|&entry| entry == block) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(block);
+                let bits_per_index = bits_required(self.palette.len());
+                if bits_per_index != self.bits_per_index {
+                    self.repack(bits_per_index);
+                }
+                self.palette.len() - 1
+            }
+        };
+
+        if self.bits_per_index > 0 {
+            write_index(
+                &mut self.blocks,
+                index,
+                self.bits_per_index,
+                palette_index as u64,
+            );
+        }
+        self.changed = true;
+        self.dirty = true;
+        Ok(())
     }
 
     pub fn changed(&self) -> bool {
         self.changed
     }
+
+    /// World tiles in this chunk holding `block`, in z, y, x order. Chunks whose palette lacks
+    /// the block are not scanned.
+    pub fn tiles_holding(&self, block: BlockId) -> Vec<WorldCoord> {
+        if !self.palette.contains(&block) {
+            return Vec::new();
+        }
+        let origin = WorldCoord::new(
+            self.position.x * CHUNK_WIDTH as i32,
+            self.position.y * CHUNK_DEPTH as i32,
+            self.position.z * CHUNK_HEIGHT as i32,
+        );
+        let mut tiles = Vec::new();
+        for z in 0..CHUNK_HEIGHT {
+            for y in 0..CHUNK_DEPTH {
+                for x in 0..CHUNK_WIDTH {
+                    if self.get_block(LocalBlockCoord::new(x, y, z)) == Ok(block) {
+                        tiles.push(WorldCoord::new(
+                            origin.x + x as i32,
+                            origin.y + y as i32,
+                            origin.z + z as i32,
+                        ));
+                    }
+                }
+            }
+        }
+        tiles
+    }
+
+    /// Whether the chunk has edits that have not been taken by
+    /// [`ChunkCache::take_dirty_chunks`] yet.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn to_packed(&self) -> PackedChunk {
+        PackedChunk {
+            position: self.position,
+            palette: self.palette.clone(),
+            bits_per_index: self.bits_per_index,
+            blocks: self.blocks.clone(),
+        }
+    }
+
+    /// Rebuilds a saved delta. The chunk counts as changed but not dirty, since it already
+    /// matches what is stored.
+    pub fn from_packed(data: PackedChunk) -> Result<Self, ChunkError> {
+        let block_count = CHUNK_WIDTH * CHUNK_DEPTH * CHUNK_HEIGHT;
+        let expected_words = (block_count * data.bits_per_index as usize).div_ceil(64);
+        if data.palette.is_empty()
+            || data.bits_per_index != bits_required(data.palette.len())
+            || data.blocks.len() != expected_words
+        {
+            return Err(ChunkError::InvalidBlockCount(data.blocks.len()));
+        }
+        if data.bits_per_index > 0
+            && let Some(palette_index) = (0..block_count)
+                .map(|index| unpack_index(&data.blocks, index, data.bits_per_index) as usize)
+                .find(|&palette_index| palette_index >= data.palette.len())
+        {
+            return Err(ChunkError::InvalidPaletteIndex(palette_index));
+        }
+
+        Ok(Self {
+            position: data.position,
+            palette: data.palette,
+            blocks: data.blocks,
+            bits_per_index: data.bits_per_index,
+            changed: true,
+            dirty: false,
+        })
+    }
+
+    /// Unpacks the chunk into the flat block list used by saves.
+    pub fn to_block_save(&self) -> ChunkBlocks {
+        ChunkBlocks {
+            position: self.position,
+            blocks: chunkblocks_from_cached(self),
+            changed: self.changed,
+        }
+    }
+
+    fn repack(&mut self, bits_per_index: u8) {
+        let block_count = CHUNK_WIDTH * CHUNK_DEPTH * CHUNK_HEIGHT;
+        let mut blocks = vec![0u64; (block_count * bits_per_index as usize).div_ceil(64)];
+        if self.bits_per_index > 0 {
+            for index in 0..block_count {
+                let value = unpack_index(&self.blocks, index, self.bits_per_index);
+                write_index(&mut blocks, index, bits_per_index, value);
+            }
+        }
+        self.blocks = blocks;
+        self.bits_per_index = bits_per_index;
+    }
+
+    pub fn from_block_save(data: &ChunkBlocks) -> Result<Self, ChunkError> {
+        let mut chunk = Chunk::new(data.position, AIR);
+        chunk.apply_block_save(data)?;
+        Ok(Self::from_chunk_with_changed(&chunk, data.changed))
+    }
+}
+
+/// Where a streaming cache finds changed chunks again after evicting them. Chunks handed out
+/// by [`ChunkCache::take_dirty_chunks`] must be served back from here.
+pub trait DeltaSource: fmt::Debug + Send + Sync {
+    fn delta(&self, position: ChunkPosition) -> Option<PackedChunk>;
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +212,15 @@ pub struct ChunkCache {
     chunks: HashMap<ChunkPosition, CachedChunk>,
     reusable_chunk: Chunk,
     last_save_ms: Option<f64>,
+    clock: Option<fn() -> f64>,
+    generator: Option<Arc<dyn WorldGenerator>>,
+    delta_source: Option<Arc<dyn DeltaSource>>,
+    chunk_budget: Option<usize>,
+    use_clock: u64,
+    last_used: HashMap<ChunkPosition, u64>,
+    use_order: BTreeMap<u64, ChunkPosition>,
+    /// Chunks inserted or written since the last [`ChunkCache::take_fresh_chunks`].
+    fresh: HashSet<ChunkPosition>,
 }
 
 impl ChunkCache {
@@ -73,10 +233,67 @@ impl ChunkCache {
             chunks: HashMap::with_capacity(capacity),
             reusable_chunk: Chunk::new(ChunkPosition::new(0, 0, 0), AIR),
             last_save_ms: None,
+            clock: None,
+            generator: None,
+            delta_source: None,
+            chunk_budget: None,
+            use_clock: 0,
+            last_used: HashMap::new(),
+            use_order: BTreeMap::new(),
+            fresh: HashSet::new(),
         }
     }
 
-    pub fn from_generator_with_limits(generator: &DeterministicMap) -> Self {
+    /// An empty cache that answers reads for chunks it has not loaded from `generator`, and
+    /// generates a chunk the first time it is written to.
+    pub fn with_generator(generator: impl WorldGenerator + 'static) -> Self {
+        let mut cache = Self::new();
+        cache.set_generator(Some(Arc::new(generator)));
+        cache
+    }
+
+    /// A cache that generates chunks as they are touched and keeps at most `chunk_budget`
+    /// of them, evicting the least recently used first. Changed chunks stay loaded until
+    /// `take_dirty_chunks` has handed them off and a [`DeltaSource`] can bring them back.
+    pub fn streaming(generator: impl WorldGenerator + 'static, chunk_budget: usize) -> Self {
+        let mut cache = Self::with_generator(generator);
+        cache.set_chunk_budget(Some(chunk_budget));
+        cache
+    }
+
+    pub fn set_chunk_budget(&mut self, chunk_budget: Option<usize>) {
+        self.chunk_budget = chunk_budget.map(|budget| budget.max(1));
+        self.evict_over_budget(None);
+    }
+
+    pub fn chunk_budget(&self) -> Option<usize> {
+        self.chunk_budget
+    }
+
+    pub fn set_generator(&mut self, generator: Option<Arc<dyn WorldGenerator>>) {
+        self.generator = generator;
+    }
+
+    pub fn generator(&self) -> Option<&Arc<dyn WorldGenerator>> {
+        self.generator.as_ref()
+    }
+
+    /// Lets changed chunks that were already handed off be evicted, reloading them from
+    /// `source` when they are touched again.
+    pub fn set_delta_source(&mut self, source: Option<Arc<dyn DeltaSource>>) {
+        self.delta_source = source;
+        let positions: Vec<ChunkPosition> = self.chunks.keys().copied().collect();
+        for position in positions {
+            if self.can_evict(position) {
+                self.mark_used(position);
+            } else {
+                self.pin(position);
+            }
+        }
+        self.evict_over_budget(None);
+    }
+
+    pub fn from_generator_with_limits(generator: &dyn WorldGenerator) -> Self {
         let total_chunks = Self::chunk_count_for_limits(HORIZONTAL_LIMIT, VERTICAL_LIMIT);
         let mut cache = Self::with_capacity(total_chunks);
         cache.populate_within_limits(generator, HORIZONTAL_LIMIT, VERTICAL_LIMIT);
@@ -84,7 +301,7 @@ impl ChunkCache {
     }
 
     pub fn from_generator_with_limits_with_progress(
-        generator: &DeterministicMap,
+        generator: &dyn WorldGenerator,
         progress: &mut dyn FnMut(usize, usize),
     ) -> Self {
         let total_chunks = Self::chunk_count_for_limits(HORIZONTAL_LIMIT, VERTICAL_LIMIT);
@@ -100,7 +317,7 @@ impl ChunkCache {
 
     pub fn populate_within_limits(
         &mut self,
-        generator: &DeterministicMap,
+        generator: &dyn WorldGenerator,
         horizontal_limit: i32,
         vertical_limit: i32,
     ) {
@@ -114,48 +331,111 @@ impl ChunkCache {
 
     pub fn populate_within_limits_with_progress(
         &mut self,
-        generator: &DeterministicMap,
+        generator: &dyn WorldGenerator,
         horizontal_limit: i32,
         vertical_limit: i32,
-        mut progress: Option<&mut dyn FnMut(usize, usize)>,
+        progress: Option<&mut dyn FnMut(usize, usize)>,
     ) {
         let (chunk_xs, chunk_ys, chunk_zs) =
             Self::chunk_ranges_for_limits(horizontal_limit, vertical_limit);
-        let total_chunks = chunk_xs.len() * chunk_ys.len() * chunk_zs.len();
-        self.chunks
-            .reserve(total_chunks.saturating_sub(self.chunks.len()));
-        let mut loaded = 0usize;
-        let notify_every = 1000usize;
-        let mut last_notified = 0usize;
-
+        let mut positions = Vec::with_capacity(chunk_xs.len() * chunk_ys.len() * chunk_zs.len());
         for &chunk_x in &chunk_xs {
             for &chunk_y in &chunk_ys {
                 for &chunk_z in &chunk_zs {
-                    let position = ChunkPosition::new(chunk_x, chunk_y, chunk_z);
-                    self.populate_chunk_at(generator, position);
-                    loaded += 1;
-
-                    if let Some(callback) = progress.as_mut() {
-                        if loaded % notify_every == 0 || loaded == total_chunks {
-                            callback(loaded, total_chunks);
-                            last_notified = loaded;
-                        }
-                    }
+                    positions.push(ChunkPosition::new(chunk_x, chunk_y, chunk_z));
                 }
             }
         }
+        self.populate_chunks_with_progress(generator, &positions, progress);
+    }
 
-        if let Some(callback) = progress.as_mut() {
-            if loaded > 0 && loaded != last_notified {
+    pub fn populate_chunks(&mut self, generator: &dyn WorldGenerator, positions: &[ChunkPosition]) {
+        self.populate_chunks_with_progress(generator, positions, None);
+    }
+
+    /// Generates every chunk in `positions`. Native builds spread the work over worker
+    /// threads for the length of the call, then insert the chunks in position order; the
+    /// blocks are identical to calling `populate_chunk_at` for each position in turn. Use
+    /// [`ChunkWorkers`] to generate in the background instead.
+    pub fn populate_chunks_with_progress(
+        &mut self,
+        generator: &dyn WorldGenerator,
+        positions: &[ChunkPosition],
+        mut progress: Option<&mut dyn FnMut(usize, usize)>,
+    ) {
+        let total_chunks = positions.len();
+        self.chunks
+            .reserve(total_chunks.saturating_sub(self.chunks.len()));
+        let mut loaded = 0usize;
+        let notify_every = 1000usize;
+        let mut on_loaded = || {
+            loaded += 1;
+            if let Some(callback) = progress.as_mut()
+                && (loaded.is_multiple_of(notify_every) || loaded == total_chunks)
+            {
                 callback(loaded, total_chunks);
             }
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut generated = Vec::with_capacity(total_chunks);
+            generate_on_workers(generator, positions, |cached| {
+                generated.push(cached);
+                on_loaded();
+            });
+            sort_by_position(&mut generated);
+            for cached in generated {
+                self.insert_chunk(cached);
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        for &position in positions {
+            generator.populate_chunk(&mut self.reusable_chunk, position);
+            let cached = CachedChunk::from_chunk(&self.reusable_chunk);
+            self.insert_chunk(cached);
+            on_loaded();
         }
     }
 
-    pub fn populate_chunk_at(&mut self, generator: &DeterministicMap, position: ChunkPosition) {
+    pub fn populate_chunk_at(&mut self, generator: &dyn WorldGenerator, position: ChunkPosition) {
         generator.populate_chunk(&mut self.reusable_chunk, position);
         let cached = CachedChunk::from_chunk(&self.reusable_chunk);
-        self.chunks.insert(position, cached);
+        self.insert_chunk(cached);
+    }
+
+    /// Returns the chunk at `position`, generating it first if it is not loaded, and marks it
+    /// as recently used.
+    pub fn touch_chunk(&mut self, position: ChunkPosition) -> Option<&CachedChunk> {
+        if self.chunks.contains_key(&position) {
+            self.mark_used(position);
+        } else {
+            self.load_missing(position).ok()?;
+        }
+        self.chunks.get(&position)
+    }
+
+    /// Brings an unloaded chunk in from the delta source, or else from the generator. Leaves
+    /// the cache untouched when neither knows the chunk.
+    fn load_missing(&mut self, position: ChunkPosition) -> Result<(), ChunkError> {
+        if let Some(delta) = self
+            .delta_source
+            .as_ref()
+            .and_then(|source| source.delta(position))
+        {
+            self.insert_chunk(CachedChunk::from_packed(delta)?);
+        } else if let Some(generator) = self.generator.clone() {
+            self.populate_chunk_at(generator.as_ref(), position);
+        }
+        Ok(())
+    }
+
+    /// Like `block_at_world`, but keeps the chunk loaded for later lookups.
+    pub fn load_block_at(&mut self, coord: WorldCoord) -> Option<BlockId> {
+        let (chunk_pos, local) = chunk_and_local_for_world_coord(coord);
+        self.touch_chunk(chunk_pos)
+            .and_then(|chunk| chunk.get_block(local).ok())
     }
 
     pub fn chunk_ranges_for_limits(
@@ -171,10 +451,78 @@ impl ChunkCache {
         x_range.count() * y_range.count() * z_range.count()
     }
 
+    pub fn insert_chunk(&mut self, chunk: CachedChunk) {
+        let position = chunk.position;
+        self.chunks.insert(position, chunk);
+        self.fresh.insert(position);
+        if self.can_evict(position) {
+            self.mark_used(position);
+            self.evict_over_budget(Some(position));
+        } else {
+            self.pin(position);
+        }
+    }
+
     pub fn chunk(&self, position: &ChunkPosition) -> Option<&CachedChunk> {
         self.chunks.get(position)
     }
 
+    /// Chunks edited through `set_block` since they were generated or loaded.
+    pub fn changed_chunks(&self) -> impl Iterator<Item = &CachedChunk> {
+        self.chunks.values().filter(|chunk| chunk.changed)
+    }
+
+    pub fn dirty_chunks(&self) -> impl Iterator<Item = &CachedChunk> {
+        self.chunks.values().filter(|chunk| chunk.dirty)
+    }
+
+    /// Packs every dirty chunk for a delta save and clears its dirty flag. Chunks come out
+    /// ordered by position. With a [`DeltaSource`] set, the handed-off chunks may be evicted
+    /// from then on.
+    pub fn take_dirty_chunks(&mut self) -> Vec<PackedChunk> {
+        let mut deltas: Vec<PackedChunk> = self
+            .chunks
+            .values_mut()
+            .filter(|chunk| chunk.dirty)
+            .map(|chunk| {
+                chunk.dirty = false;
+                chunk.to_packed()
+            })
+            .collect();
+        deltas.sort_by_key(|chunk| position_key(chunk.position));
+        for delta in &deltas {
+            if self.can_evict(delta.position) {
+                self.mark_used(delta.position);
+            }
+        }
+        self.evict_over_budget(None);
+        deltas
+    }
+
+    /// Positions of the chunks inserted or written since the last call, in position order, so
+    /// callers can index blocks that arrived without going through them.
+    pub fn take_fresh_chunks(&mut self) -> Vec<ChunkPosition> {
+        let mut fresh: Vec<ChunkPosition> = self.fresh.drain().collect();
+        fresh.sort_by_key(|&position| position_key(position));
+        fresh
+    }
+
+    /// Overlays saved deltas on terrain already populated from a [`WorldGenerator`]; each
+    /// delta replaces the generated chunk at its position.
+    pub fn apply_deltas(
+        &mut self,
+        deltas: impl IntoIterator<Item = PackedChunk>,
+    ) -> Result<(), ChunkError> {
+        for delta in deltas {
+            self.insert_chunk(CachedChunk::from_packed(delta)?);
+        }
+        Ok(())
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &ChunkPosition> {
+        self.chunks.keys()
+    }
+
     pub fn has_chunk(&self, position: &ChunkPosition) -> bool {
         self.chunks.contains_key(position)
     }
@@ -183,30 +531,105 @@ impl ChunkCache {
         self.chunks.keys().any(|pos| pos.z == chunk_z)
     }
 
+    /// The block at `coord`, falling back to the delta source and then the generator for
+    /// chunks that are not loaded. `None` means the chunk is neither loaded nor generatable.
+    /// Use `load_block_at` to keep the chunk around for later lookups.
     pub fn block_at_world(&self, coord: WorldCoord) -> Option<BlockId> {
         let (chunk_pos, local) = chunk_and_local_for_world_coord(coord);
-        self.chunk(&chunk_pos)
-            .and_then(|chunk| chunk.get_block(local).ok())
+        if let Some(chunk) = self.chunk(&chunk_pos) {
+            return chunk.get_block(local).ok();
+        }
+        match self
+            .delta_source
+            .as_ref()
+            .and_then(|source| source.delta(chunk_pos))
+        {
+            Some(delta) => CachedChunk::from_packed(delta).ok()?.get_block(local).ok(),
+            None => self
+                .generator
+                .as_ref()
+                .map(|generator| generator.block_at(coord)),
+        }
     }
 
+    /// Writes `block` at `coord`, generating the chunk first if it is not loaded yet.
     pub fn set_block(&mut self, coord: WorldCoord, block: BlockId) -> Result<(), ChunkError> {
+        let Some(clock) = self.clock else {
+            return self.write_block(coord, block);
+        };
+        let started = clock();
+        self.write_block(coord, block)?;
+        self.record_save_time_ms((clock() - started) * 1000.0);
+        Ok(())
+    }
+
+    fn write_block(&mut self, coord: WorldCoord, block: BlockId) -> Result<(), ChunkError> {
         let (chunk_pos, local) = chunk_and_local_for_world_coord(coord);
-        let Some(existing) = self.chunks.get(&chunk_pos) else {
+        if !self.chunks.contains_key(&chunk_pos) {
+            self.load_missing(chunk_pos)?;
+        }
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
             return Err(ChunkError::OutOfBounds);
         };
-
-        let mut chunk = chunk_from_cached(existing)?;
         chunk.set_block(local, block)?;
-
-        let updated = CachedChunk::from_chunk_with_changed(&chunk, true);
-        self.chunks.insert(chunk_pos, updated);
+        self.pin(chunk_pos);
+        self.fresh.insert(chunk_pos);
         Ok(())
     }
 
+    /// Clean chunks can always be regenerated; changed ones only once they were handed off
+    /// and the delta source can serve them again.
+    fn can_evict(&self, position: ChunkPosition) -> bool {
+        self.chunks
+            .get(&position)
+            .is_some_and(|chunk| !chunk.dirty && (!chunk.changed || self.delta_source.is_some()))
+    }
+
+    fn mark_used(&mut self, position: ChunkPosition) {
+        if !self.can_evict(position) {
+            return;
+        }
+        self.use_clock += 1;
+        if let Some(previous) = self.last_used.insert(position, self.use_clock) {
+            self.use_order.remove(&previous);
+        }
+        self.use_order.insert(self.use_clock, position);
+    }
+
+    /// Takes a changed chunk out of the eviction order.
+    fn pin(&mut self, position: ChunkPosition) {
+        if let Some(previous) = self.last_used.remove(&position) {
+            self.use_order.remove(&previous);
+        }
+    }
+
+    fn evict_over_budget(&mut self, keep: Option<ChunkPosition>) {
+        let Some(budget) = self.chunk_budget else {
+            return;
+        };
+        while self.chunks.len() > budget {
+            let Some((&stamp, &position)) = self.use_order.first_key_value() else {
+                return;
+            };
+            if Some(position) == keep {
+                return;
+            }
+            self.use_order.remove(&stamp);
+            self.last_used.remove(&position);
+            self.chunks.remove(&position);
+        }
+    }
+
     pub fn last_save_ms(&self) -> Option<f64> {
         self.last_save_ms
     }
 
+    /// Times every successful [`Self::set_block`] with `clock`, which reads seconds, so
+    /// [`Self::last_save_ms`] reports the cost of the write alone.
+    pub fn set_clock(&mut self, clock: fn() -> f64) {
+        self.clock = Some(clock);
+    }
+
     pub fn record_save_time_ms(&mut self, ms: f64) {
         if ms.is_finite() && ms >= 0.0 {
             self.last_save_ms = Some(ms);
@@ -220,9 +643,23 @@ impl ChunkCache {
         first_solid_supercover(|coord| self.block_at_world(coord), start, end)
     }
 
+    /// Plans a route between two tiles, loading every chunk the search looks at; see
+    /// [`crate::pathfind::find_path`].
+    pub fn find_path(
+        &mut self,
+        start: WorldCoord,
+        goal: WorldCoord,
+    ) -> Result<Vec<WorldCoord>, PathError> {
+        find_path(|coord| self.load_block_at(coord), start, goal)
+    }
+
     pub fn len(&self) -> usize {
         self.chunks.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
 }
 
 impl Default for ChunkCache {
@@ -231,16 +668,137 @@ impl Default for ChunkCache {
     }
 }
 
+/// A pool of generation threads that lives until it is dropped. Positions passed to
+/// [`ChunkWorkers::request`] are generated in the background and collected a batch at a time
+/// with [`ChunkWorkers::insert_finished`], so a frame never waits on the generator.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct ChunkWorkers {
+    requests: std::sync::mpsc::Sender<ChunkPosition>,
+    finished: std::sync::mpsc::Receiver<CachedChunk>,
+    in_flight: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ChunkWorkers {
+    /// Starts one worker per available core. The workers exit once the pool is dropped.
+    pub fn new(generator: Arc<dyn WorldGenerator>) -> Self {
+        use std::sync::{Mutex, mpsc};
+
+        let workers = std::thread::available_parallelism().map_or(1, |count| count.get());
+        let (requests, queue) = mpsc::channel::<ChunkPosition>();
+        let queue = Arc::new(Mutex::new(queue));
+        let (sender, finished) = mpsc::channel();
+        for _ in 0..workers {
+            let (generator, queue, sender) = (generator.clone(), queue.clone(), sender.clone());
+            std::thread::spawn(move || {
+                let mut chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+                while let Ok(Ok(position)) = queue.lock().map(|queue| queue.recv()) {
+                    generator.populate_chunk(&mut chunk, position);
+                    if sender.send(CachedChunk::from_chunk(&chunk)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        Self {
+            requests,
+            finished,
+            in_flight: 0,
+        }
+    }
+
+    /// Queues `position` for generation; requests are picked up in the order they are made.
+    pub fn request(&mut self, position: ChunkPosition) {
+        if self.requests.send(position).is_ok() {
+            self.in_flight += 1;
+        }
+    }
+
+    /// Requested chunks that have not been collected yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Inserts at most `budget` finished chunks into `cache` without blocking, in position
+    /// order so the cache's eviction order does not depend on which worker finished first.
+    /// Positions the cache loaded meanwhile keep their blocks. Returns every position
+    /// collected.
+    pub fn insert_finished(&mut self, cache: &mut ChunkCache, budget: usize) -> Vec<ChunkPosition> {
+        let mut finished: Vec<CachedChunk> = self.finished.try_iter().take(budget).collect();
+        self.in_flight -= finished.len();
+        sort_by_position(&mut finished);
+        finished
+            .into_iter()
+            .map(|cached| {
+                let position = cached.position;
+                if !cache.has_chunk(&position) {
+                    cache.insert_chunk(cached);
+                }
+                position
+            })
+            .collect()
+    }
+}
+
+fn position_key(position: ChunkPosition) -> (i32, i32, i32) {
+    (position.x, position.y, position.z)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn sort_by_position(chunks: &mut [CachedChunk]) {
+    chunks.sort_by_key(|chunk| position_key(chunk.position));
+}
+
+/// Generates `positions` on one scoped worker per available core, handing each finished chunk
+/// to `on_chunk` on the calling thread.
+#[cfg(not(target_arch = "wasm32"))]
+fn generate_on_workers(
+    generator: &dyn WorldGenerator,
+    positions: &[ChunkPosition],
+    mut on_chunk: impl FnMut(CachedChunk),
+) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |count| count.get())
+        .min(positions.len());
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                let mut chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+                while let Some(&position) = positions.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    generator.populate_chunk(&mut chunk, position);
+                    if sender.send(CachedChunk::from_chunk(&chunk)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for cached in receiver {
+            on_chunk(cached);
+        }
+    });
+}
+
 fn build_palette(blocks: &[BlockId]) -> (Vec<BlockId>, HashMap<BlockId, usize>) {
     let mut palette = Vec::new();
     let mut indices = HashMap::new();
 
     for &block in blocks {
-        if !indices.contains_key(&block) {
-            let idx = palette.len();
+        indices.entry(block).or_insert_with(|| {
             palette.push(block);
-            indices.insert(block, idx);
-        }
+            palette.len() - 1
+        });
     }
 
     (palette, indices)
@@ -264,7 +822,7 @@ fn pack_blocks(
     }
 
     let total_bits = blocks.len() * bits_per_index as usize;
-    let u64_len = (total_bits + 63) / 64;
+    let u64_len = total_bits.div_ceil(64);
     let mut packed = vec![0u64; u64_len];
     let mask: u64 = (1u64 << bits_per_index) - 1;
 
@@ -306,7 +864,26 @@ fn unpack_index(packed: &[u64], block_index: usize, bits_per_index: u8) -> u64 {
     value
 }
 
-fn chunk_and_local_for_world_coord(coord: WorldCoord) -> (ChunkPosition, LocalBlockCoord) {
+fn write_index(packed: &mut [u64], block_index: usize, bits_per_index: u8, value: u64) {
+    let offset = block_index * bits_per_index as usize;
+    let word_index = offset / 64;
+    let bit_in_word = offset % 64;
+    let mask = (1u64 << bits_per_index) - 1;
+
+    packed[word_index] &= !(mask << bit_in_word);
+    packed[word_index] |= (value & mask) << bit_in_word;
+    let bits_written = bit_in_word + bits_per_index as usize;
+    if bits_written > 64 {
+        let spill_bits = bits_written - 64;
+        let shift = bits_per_index as usize - spill_bits;
+        packed[word_index + 1] &= !((1u64 << spill_bits) - 1);
+        packed[word_index + 1] |= (value & mask) >> shift;
+    }
+}
+
+pub(crate) fn chunk_and_local_for_world_coord(
+    coord: WorldCoord,
+) -> (ChunkPosition, LocalBlockCoord) {
     let chunk_x = div_floor(coord.x, CHUNK_WIDTH as i32);
     let chunk_y = div_floor(coord.y, CHUNK_DEPTH as i32);
     let chunk_z = div_floor(coord.z, CHUNK_HEIGHT as i32);
@@ -335,18 +912,6 @@ fn chunkblocks_from_cached(cached: &CachedChunk) -> Vec<BlockId> {
     blocks
 }
 
-fn chunk_from_cached(cached: &CachedChunk) -> Result<Chunk, ChunkError> {
-    let mut chunk = Chunk::new(cached.position, AIR);
-    let blocks = chunkblocks_from_cached(cached);
-    let blocks_struct = ChunkBlocks {
-        position: cached.position,
-        blocks,
-        changed: cached.changed,
-    };
-    chunk.apply_block_save(&blocks_struct)?;
-    Ok(chunk)
-}
-
 fn chunk_range_bounds(
     horizontal_limit: i32,
     vertical_limit: i32,
@@ -381,7 +946,8 @@ fn div_floor(a: i32, b: i32) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::{AIR, BEDROCK, DIRT, IRON, STONE};
+    use crate::block::{AIR, BEDROCK, CORE, DIRT, IRON, STONE};
+    use crate::worldgen::{DeterministicMap, FlatGenerator};
 
     fn coord_for_index(index: usize) -> LocalBlockCoord {
         let z = index / (CHUNK_WIDTH * CHUNK_DEPTH);
@@ -495,6 +1061,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clock_times_each_block_write() {
+        // Every reading is two milliseconds after the last.
+        fn ticking_clock() -> f64 {
+            static READINGS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+            READINGS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) as f64 * 0.002
+        }
+        let mut cache = ChunkCache::with_generator(FlatGenerator::new(-1, STONE));
+        cache.set_clock(ticking_clock);
+
+        cache.set_block(WorldCoord::new(0, 0, -1), AIR).unwrap();
+
+        let elapsed = cache.last_save_ms().unwrap();
+        assert!((elapsed - 2.0).abs() < 1e-9);
+    }
+
     #[test]
     fn palette_builds_unique_ids_in_order() {
         let blocks = vec![AIR, DIRT, AIR, STONE, DIRT];
@@ -590,4 +1172,382 @@ mod tests {
 
         assert_eq!(cache.first_solid_on_line(start, end), None);
     }
+
+    #[test]
+    fn finds_path_around_blocking_wall() {
+        let wall = [
+            WorldCoord::new(2, -1, 0),
+            WorldCoord::new(2, 0, 0),
+            WorldCoord::new(2, 1, 0),
+        ];
+        let mut cache = cache_with_solid_blocks(&wall);
+
+        let start = WorldCoord::new(0, 0, 0);
+        let end = WorldCoord::new(4, 0, 0);
+
+        assert_eq!(cache.first_solid_on_line(start, end), Some(wall[1]));
+        let path = cache.find_path(start, end).unwrap();
+        assert_eq!(path.last(), Some(&end));
+        assert!(path.iter().all(|tile| !wall.contains(tile)));
+    }
+
+    #[test]
+    fn dirty_deltas_overlay_generated_terrain() {
+        let generator = DeterministicMap::new(11);
+        let mut cache = ChunkCache::new();
+        cache.populate_within_limits(&generator, CHUNK_WIDTH as i32, CHUNK_HEIGHT as i32);
+        let dug = WorldCoord::new(5, 5, -2);
+        cache.set_block(dug, AIR).unwrap();
+        cache.set_block(WorldCoord::new(6, 5, -2), AIR).unwrap();
+
+        let deltas = cache.take_dirty_chunks();
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].position, ChunkPosition::new(0, 0, -1));
+        assert!(cache.take_dirty_chunks().is_empty());
+        assert_eq!(cache.changed_chunks().count(), 1);
+
+        let mut restored = ChunkCache::new();
+        restored.populate_within_limits(&generator, CHUNK_WIDTH as i32, CHUNK_HEIGHT as i32);
+        restored.apply_deltas(deltas).unwrap();
+
+        assert_eq!(restored.block_at_world(dug), Some(AIR));
+        assert_eq!(
+            restored.block_at_world(WorldCoord::new(7, 5, -2)),
+            cache.block_at_world(WorldCoord::new(7, 5, -2))
+        );
+        let chunk = restored.chunk(&ChunkPosition::new(0, 0, -1)).unwrap();
+        assert!(chunk.changed() && !chunk.is_dirty());
+    }
+
+    #[test]
+    fn generator_fills_misses_and_backs_first_write() {
+        let generator = DeterministicMap::new(5);
+        let mut cache = ChunkCache::with_generator(generator.clone());
+        let coord = WorldCoord::new(40, -3, -6);
+
+        assert_eq!(cache.block_at_world(coord), Some(generator.block_at(coord)));
+        assert!(cache.is_empty());
+
+        cache.set_block(coord, AIR).unwrap();
+        let neighbour = WorldCoord::new(41, -3, -6);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.block_at_world(coord), Some(AIR));
+        assert_eq!(
+            cache.block_at_world(neighbour),
+            Some(generator.block_at(neighbour))
+        );
+        assert_eq!(cache.dirty_chunks().count(), 1);
+    }
+
+    #[test]
+    fn in_place_writes_grow_palette_and_keep_other_blocks() {
+        let generator = DeterministicMap::new(9);
+        let position = ChunkPosition::new(0, 0, -1);
+        let mut chunk = generator.chunk_for_position(position);
+        let mut cached = CachedChunk::from_chunk(&chunk);
+        let bits_before = cached.bits_per_index;
+
+        // Enough distinct ids to force at least one re-encode at a wider width.
+        for (i, block) in (100..140).enumerate() {
+            let coord = coord_for_index(i * 97 % 4096);
+            chunk.set_block(coord, block).unwrap();
+            cached.set_block(coord, block).unwrap();
+        }
+
+        assert!(cached.bits_per_index > bits_before);
+        assert!(cached.changed() && cached.is_dirty());
+        for index in 0..chunk.blocks().len() {
+            let coord = coord_for_index(index);
+            assert_eq!(cached.get_block(coord), chunk.get_block(coord));
+        }
+    }
+
+    fn rebuild_with_block(
+        cached: &CachedChunk,
+        coord: LocalBlockCoord,
+        block: BlockId,
+    ) -> CachedChunk {
+        let data = cached.to_block_save();
+        let mut chunk = Chunk::new(cached.position, AIR);
+        chunk.apply_block_save(&data).unwrap();
+        chunk.set_block(coord, block).unwrap();
+        CachedChunk::from_chunk_with_changed(&chunk, true)
+    }
+
+    fn scattered_writes() -> (CachedChunk, Vec<(LocalBlockCoord, BlockId)>) {
+        let generator = DeterministicMap::new(9);
+        let original =
+            CachedChunk::from_chunk(&generator.chunk_for_position(ChunkPosition::new(0, 0, -1)));
+        let writes = (0..200)
+            .map(|i| (coord_for_index(i * 31 % 4096), [AIR, CORE, STONE][i % 3]))
+            .collect();
+        (original, writes)
+    }
+
+    #[test]
+    fn in_place_writes_repack_only_when_the_palette_outgrows_its_bits() {
+        let (original, writes) = scattered_writes();
+
+        let mut rebuilt = original.clone();
+        for &(coord, block) in &writes {
+            rebuilt = rebuild_with_block(&rebuilt, coord, block);
+        }
+
+        // The old path re-encodes all 4096 indices on every write; the in-place path only
+        // does so when a new palette entry needs another bit.
+        let mut in_place = original.clone();
+        let mut repacks = 0;
+        for &(coord, block) in &writes {
+            let bits_before = in_place.bits_per_index;
+            in_place.set_block(coord, block).unwrap();
+            if in_place.bits_per_index != bits_before {
+                repacks += 1;
+            }
+        }
+
+        assert_eq!(
+            in_place.to_block_save().blocks,
+            rebuilt.to_block_save().blocks
+        );
+        assert!(repacks <= 1);
+    }
+
+    #[test]
+    #[ignore = "wall-clock comparison; run with --ignored on an idle machine"]
+    fn in_place_writes_beat_full_repacking() {
+        let (original, writes) = scattered_writes();
+
+        let started = std::time::Instant::now();
+        let mut rebuilt = original.clone();
+        for &(coord, block) in &writes {
+            rebuilt = rebuild_with_block(&rebuilt, coord, block);
+        }
+        let rebuild_time = started.elapsed();
+
+        let started = std::time::Instant::now();
+        let mut in_place = original.clone();
+        for &(coord, block) in &writes {
+            in_place.set_block(coord, block).unwrap();
+        }
+        let in_place_time = started.elapsed();
+
+        assert!(in_place_time < rebuild_time);
+    }
+
+    #[test]
+    fn streaming_cache_generates_on_touch() {
+        let generator = DeterministicMap::new(4);
+        let mut cache = ChunkCache::streaming(generator.clone(), 8);
+        let coord = WorldCoord::new(-700, 300, -20);
+
+        assert_eq!(cache.load_block_at(coord), Some(generator.block_at(coord)));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.touch_chunk(ChunkPosition::new(3, 3, -2)).is_some());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn streaming_cache_evicts_least_recently_used_clean_chunks() {
+        let generator = DeterministicMap::new(4);
+        let mut cache = ChunkCache::streaming(generator.clone(), 2);
+        let edited = ChunkPosition::new(0, 0, -1);
+        let (a, b, c) = (
+            ChunkPosition::new(1, 0, -1),
+            ChunkPosition::new(2, 0, -1),
+            ChunkPosition::new(3, 0, -1),
+        );
+
+        cache.set_block(WorldCoord::new(1, 1, -2), AIR).unwrap();
+        cache.touch_chunk(a);
+        cache.touch_chunk(b);
+        assert!(!cache.has_chunk(&a));
+
+        cache.touch_chunk(b);
+        cache.touch_chunk(c);
+        assert!(!cache.has_chunk(&b));
+        assert!(cache.has_chunk(&c));
+        assert!(cache.has_chunk(&edited));
+        assert_eq!(cache.block_at_world(WorldCoord::new(1, 1, -2)), Some(AIR));
+
+        let coord = WorldCoord::new(40, 5, -3);
+        assert_eq!(cache.load_block_at(coord), Some(generator.block_at(coord)));
+    }
+
+    #[derive(Debug, Default)]
+    struct SavedDeltas(std::sync::Mutex<HashMap<ChunkPosition, PackedChunk>>);
+
+    impl DeltaSource for SavedDeltas {
+        fn delta(&self, position: ChunkPosition) -> Option<PackedChunk> {
+            self.0.lock().unwrap().get(&position).cloned()
+        }
+    }
+
+    #[test]
+    fn handed_off_chunks_are_evicted_and_reloaded_from_the_delta_source() {
+        let mut cache = ChunkCache::streaming(FlatGenerator::new(-1, STONE), 2);
+        let dug = WorldCoord::new(1, 1, -1);
+        let edited = ChunkPosition::new(0, 0, -1);
+        cache.set_block(dug, AIR).unwrap();
+        cache.touch_chunk(ChunkPosition::new(1, 0, -1));
+        cache.touch_chunk(ChunkPosition::new(2, 0, -1));
+        assert!(cache.has_chunk(&edited));
+
+        let saved = Arc::new(SavedDeltas::default());
+        cache.set_delta_source(Some(saved.clone()));
+        for delta in cache.take_dirty_chunks() {
+            saved.0.lock().unwrap().insert(delta.position, delta);
+        }
+        cache.touch_chunk(ChunkPosition::new(3, 0, -1));
+        cache.touch_chunk(ChunkPosition::new(4, 0, -1));
+        assert!(!cache.has_chunk(&edited));
+        assert_eq!(cache.len(), 2);
+
+        assert_eq!(cache.block_at_world(dug), Some(AIR));
+        assert_eq!(cache.load_block_at(dug), Some(AIR));
+        let reloaded = cache.chunk(&edited).unwrap();
+        assert!(reloaded.changed() && !reloaded.is_dirty());
+    }
+
+    #[test]
+    fn pathfinding_loads_the_chunks_it_searches() {
+        let mut cache = ChunkCache::streaming(FlatGenerator::new(-1, STONE), 64);
+        let goal = WorldCoord::new(40, 3, 0);
+
+        let path = cache.find_path(WorldCoord::new(0, 0, 0), goal).unwrap();
+
+        assert_eq!(path.last(), Some(&goal));
+        assert!(cache.has_chunk(&ChunkPosition::new(0, 0, 0)));
+        assert!(cache.has_chunk(&chunk_and_local_for_world_coord(goal).0));
+    }
+
+    #[test]
+    fn parallel_generation_matches_single_threaded() {
+        let generator = DeterministicMap::new(21);
+        let (xs, ys, zs) = ChunkCache::chunk_ranges_for_limits(64, 8);
+        let positions: Vec<ChunkPosition> = xs
+            .iter()
+            .flat_map(|&x| ys.iter().map(move |&y| (x, y)))
+            .flat_map(|(x, y)| zs.iter().map(move |&z| ChunkPosition::new(x, y, z)))
+            .collect();
+
+        let mut parallel = ChunkCache::new();
+        let mut reports = Vec::new();
+        parallel.populate_chunks_with_progress(
+            &generator,
+            &positions,
+            Some(&mut |loaded, total| reports.push((loaded, total))),
+        );
+
+        assert_eq!(parallel.len(), positions.len());
+        assert_eq!(reports.last(), Some(&(positions.len(), positions.len())));
+        let mut serial = ChunkCache::new();
+        for &position in &positions {
+            serial.populate_chunk_at(&generator, position);
+            assert_eq!(
+                parallel.chunk(&position).unwrap().to_packed(),
+                serial.chunk(&position).unwrap().to_packed()
+            );
+        }
+    }
+
+    #[test]
+    fn worker_pool_inserts_finished_chunks_in_budgeted_sorted_batches() {
+        let generator = DeterministicMap::new(21);
+        let positions: Vec<ChunkPosition> = (0..4)
+            .rev()
+            .flat_map(|x| (-1..=1).map(move |z| ChunkPosition::new(x, 0, z)))
+            .collect();
+        let mut workers = ChunkWorkers::new(Arc::new(generator.clone()));
+        let mut cache = ChunkCache::with_generator(generator.clone());
+        let edited = WorldCoord::new(1, 1, 1);
+        cache.set_block(edited, CORE).unwrap();
+        for &position in &positions {
+            workers.request(position);
+        }
+
+        let mut collected = Vec::new();
+        while workers.in_flight() > 0 {
+            let batch = workers.insert_finished(&mut cache, 5);
+            assert!(batch.len() <= 5);
+            assert!(batch.is_sorted_by_key(|&position| position_key(position)));
+            collected.extend(batch);
+            std::thread::yield_now();
+        }
+
+        assert_eq!(collected.len(), positions.len());
+        assert_eq!(cache.block_at_world(edited), Some(CORE));
+        let mut serial = ChunkCache::new();
+        for &position in &positions {
+            serial.populate_chunk_at(&generator, position);
+            if position != ChunkPosition::new(0, 0, 0) {
+                assert_eq!(
+                    cache.chunk(&position).unwrap().to_packed(),
+                    serial.chunk(&position).unwrap().to_packed()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn populates_from_any_world_generator() {
+        #[derive(Debug)]
+        struct Checkerboard;
+
+        impl WorldGenerator for Checkerboard {
+            fn block_at(&self, coord: WorldCoord) -> BlockId {
+                if (coord.x + coord.y).rem_euclid(2) == 0 {
+                    STONE
+                } else {
+                    AIR
+                }
+            }
+        }
+
+        let positions = [ChunkPosition::new(0, 0, 0), ChunkPosition::new(-1, 2, -3)];
+        let mut cache = ChunkCache::new();
+        cache.populate_chunks(&Checkerboard, &positions);
+        assert_eq!(
+            cache.block_at_world(WorldCoord::new(-4, 70, -9)),
+            Some(STONE)
+        );
+        assert_eq!(cache.block_at_world(WorldCoord::new(-3, 70, -9)), Some(AIR));
+
+        let mut flat = ChunkCache::streaming(FlatGenerator::new(-1, DIRT), 4);
+        assert_eq!(flat.load_block_at(WorldCoord::new(500, 9, -1)), Some(DIRT));
+        assert_eq!(flat.load_block_at(WorldCoord::new(500, 9, 0)), Some(AIR));
+        flat.set_block(WorldCoord::new(3, 3, -1), CORE).unwrap();
+        assert_eq!(flat.block_at_world(WorldCoord::new(3, 3, -1)), Some(CORE));
+        assert_eq!(
+            flat.block_at_world(WorldCoord::new(-90, 3, -40)),
+            Some(DIRT)
+        );
+    }
+
+    #[test]
+    fn rejects_packed_chunk_with_wrong_length() {
+        let chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+        let mut packed = CachedChunk::from_chunk(&chunk).to_packed();
+        packed.palette.push(STONE);
+
+        assert!(CachedChunk::from_packed(packed).is_err());
+    }
+
+    #[test]
+    fn rejects_packed_chunk_indexing_past_its_palette() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+        chunk
+            .set_block(LocalBlockCoord::new(1, 0, 0), STONE)
+            .unwrap();
+        chunk
+            .set_block(LocalBlockCoord::new(2, 0, 0), DIRT)
+            .unwrap();
+        let mut packed = CachedChunk::from_chunk(&chunk).to_packed();
+        assert_eq!((packed.palette.len(), packed.bits_per_index), (3, 2));
+        packed.blocks[0] |= 0b11;
+
+        assert_eq!(
+            CachedChunk::from_packed(packed).unwrap_err(),
+            ChunkError::InvalidPaletteIndex(3)
+        );
+    }
 }
@@ -0,0 +1,342 @@
+use crate::coordinates::WorldCoord;
+use crate::drone::DroneId;
+use crate::enemy::EnemyId;
+use crate::inventory::InventorySlots;
+use crate::item::ItemId;
+use crate::recipe::ItemStack;
+use crate::warrior::WarriorId;
+use serde::{Deserialize, Serialize};
+
+/// How something fights. Range and cooldown are whole tiles and ticks and damage is whole
+/// hit points, so a fight resolves the same way on every target the game is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attack {
+    pub damage: i32,
+    /// Reach in tiles along either axis, on the attacker's own level.
+    pub range: i32,
+    pub cooldown_ticks: u64,
+}
+
+impl Attack {
+    pub const fn new(damage: i32, range: i32, cooldown_ticks: u64) -> Self {
+        Self {
+            damage,
+            range,
+            cooldown_ticks,
+        }
+    }
+
+    pub fn reaches(&self, from: WorldCoord, target: WorldCoord) -> bool {
+        from.z == target.z
+            && (from.x - target.x).abs() <= self.range
+            && (from.y - target.y).abs() <= self.range
+    }
+}
+
+/// Damage left after `armour` soaks its share. A hit always lands for at least one point.
+pub fn mitigated_damage(damage: i32, armour: i32) -> i32 {
+    if damage <= 0 {
+        return 0;
+    }
+    (damage - armour.max(0)).max(1)
+}
+
+/// The tick from which an attacker may strike again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cooldown {
+    ready_tick: u64,
+}
+
+impl Cooldown {
+    pub fn is_ready(&self, tick: u64) -> bool {
+        tick >= self.ready_tick
+    }
+
+    pub fn start(&mut self, tick: u64, attack: &Attack) {
+        self.ready_tick = tick + attack.cooldown_ticks;
+    }
+}
+
+/// Anything that deals or takes damage in a fight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Combatant {
+    Drone(DroneId),
+    Enemy(EnemyId),
+    Warrior(WarriorId),
+    /// The core standing on this tile.
+    Core(WorldCoord),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CombatEvent {
+    Hit {
+        tick: u64,
+        attacker: Combatant,
+        target: Combatant,
+        damage: i32,
+        health: i32,
+    },
+    Died {
+        tick: u64,
+        combatant: Combatant,
+        tile: WorldCoord,
+    },
+}
+
+/// Items a dead drone left on the ground, waiting for another drone to pick them up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemDrop {
+    pub tile: WorldCoord,
+    pub items: Vec<ItemStack>,
+}
+
+impl ItemDrop {
+    /// Everything held in `slots`, one stack per item in first-seen order.
+    pub fn from_inventory(tile: WorldCoord, slots: &InventorySlots) -> Self {
+        let mut drop = Self {
+            tile,
+            items: Vec::new(),
+        };
+        for slot in slots.iter().filter(|slot| slot.count > 0) {
+            if let Some(item) = slot.item {
+                drop.add(item, slot.count);
+            }
+        }
+        drop
+    }
+
+    /// Adds `count` of `item` to the stack already holding it, or to a new one at the end.
+    pub fn add(&mut self, item: ItemId, count: u32) {
+        match self.items.iter_mut().find(|stack| stack.item == item) {
+            Some(stack) => stack.count += count,
+            None => self.items.push(ItemStack { item, count }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.iter().all(|stack| stack.count == 0)
+    }
+}
+
+/// The receiving end of a strike.
+pub(crate) struct Target<'a> {
+    pub combatant: Combatant,
+    pub armour: i32,
+    pub health: &'a mut i32,
+}
+
+/// Lands `attack` on `target`, starts the attacker's cooldown and logs the hit. Returns
+/// whether the target died.
+pub(crate) fn strike(
+    tick: u64,
+    attacker: Combatant,
+    attack: &Attack,
+    cooldown: &mut Cooldown,
+    target: Target<'_>,
+    log: &mut Vec<CombatEvent>,
+) -> bool {
+    let damage = mitigated_damage(attack.damage, target.armour);
+    *target.health = (*target.health - damage).max(0);
+    cooldown.start(tick, attack);
+    log.push(CombatEvent::Hit {
+        tick,
+        attacker,
+        target: target.combatant,
+        damage,
+        health: *target.health,
+    });
+    *target.health == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drone::DronePose;
+    use crate::inventory::add_items;
+    use crate::item::{ITEM_IRON_ORE, ITEM_STONE};
+    use crate::test_support::arena;
+    use crate::wave::{EnemyKind, WaveSchedule, Waves};
+    use crate::{CORE, TileBox3};
+
+    #[test]
+    fn armour_soaks_damage_but_never_all_of_it() {
+        assert_eq!(mitigated_damage(5, 2), 3);
+        assert_eq!(mitigated_damage(1, 4), 1);
+        assert_eq!(mitigated_damage(3, -1), 3);
+        assert_eq!(mitigated_damage(0, 0), 0);
+    }
+
+    #[test]
+    fn strikes_wait_for_the_cooldown() {
+        let attack = Attack::new(4, 1, 30);
+        let mut cooldown = Cooldown::default();
+        let mut health = 10;
+        let mut log = Vec::new();
+
+        assert!(cooldown.is_ready(5));
+        let target = Target {
+            combatant: Combatant::Enemy(3),
+            armour: 1,
+            health: &mut health,
+        };
+        let killed = strike(
+            5,
+            Combatant::Warrior(0),
+            &attack,
+            &mut cooldown,
+            target,
+            &mut log,
+        );
+
+        assert!(!killed);
+        assert_eq!(health, 7);
+        assert!(!cooldown.is_ready(34));
+        assert!(cooldown.is_ready(35));
+        assert!(attack.reaches(WorldCoord::new(0, 0, 0), WorldCoord::new(1, -1, 0)));
+        assert!(!attack.reaches(WorldCoord::new(0, 0, 0), WorldCoord::new(1, 0, 1)));
+    }
+
+    #[test]
+    fn dead_drones_leave_their_inventory_behind() {
+        let mut world = arena();
+        world.add_drone(DronePose::new([4.0, 4.0, 0.0], [1.0, 0.0], "d1", 1, 10));
+        world.add_drone(DronePose::new([9.0, 4.0, 0.0], [1.0, 0.0], "d2", 10, 10));
+        let slots = world.inventory_mut(0).unwrap();
+        add_items(slots, ITEM_STONE, 3);
+        add_items(slots, ITEM_IRON_ORE, 2);
+        world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(5, 4, 0));
+
+        world.step();
+
+        assert_eq!(world.drones().len(), 1);
+        assert_eq!(world.drones()[0].name, "d2");
+        let tile = WorldCoord::new(4, 4, 0);
+        assert_eq!(
+            world.drops(),
+            &[ItemDrop {
+                tile,
+                items: vec![
+                    ItemStack {
+                        item: ITEM_STONE,
+                        count: 3,
+                    },
+                    ItemStack {
+                        item: ITEM_IRON_ORE,
+                        count: 2,
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            world.drain_combat_events(),
+            vec![
+                CombatEvent::Hit {
+                    tick: 1,
+                    attacker: Combatant::Enemy(0),
+                    target: Combatant::Drone(0),
+                    damage: 1,
+                    health: 0,
+                },
+                CombatEvent::Died {
+                    tick: 1,
+                    combatant: Combatant::Drone(0),
+                    tile,
+                },
+            ]
+        );
+
+        world.drones_mut()[0].position = [5.0, 5.0, 0.0];
+        assert_eq!(world.pick_up_drops(0), Ok(5));
+        assert!(world.drops().is_empty());
+    }
+
+    #[test]
+    fn drone_armour_soaks_enemy_blows() {
+        let mut world = arena();
+        world.add_drone(DronePose::new([4.0, 4.0, 0.0], [1.0, 0.0], "d1", 10, 10).with_armour(2));
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(5, 4, 0));
+
+        world.step();
+
+        assert_eq!(
+            world.drain_combat_events(),
+            vec![CombatEvent::Hit {
+                tick: 1,
+                attacker: Combatant::Enemy(0),
+                target: Combatant::Drone(0),
+                damage: 1,
+                health: 9,
+            }]
+        );
+        assert_eq!(world.drones()[0].health, 9);
+    }
+
+    #[test]
+    fn drones_keep_their_id_after_others_die() {
+        let mut world = arena();
+        for (index, x) in [2.0, 4.0, 6.0].into_iter().enumerate() {
+            world.add_drone(DronePose::new(
+                [x, 4.0, 0.0],
+                [1.0, 0.0],
+                format!("d{index}"),
+                10,
+                10,
+            ));
+        }
+
+        world.drones_mut()[0].health = 0;
+        world.step();
+        world.drones_mut()[0].health = 0;
+        world.step();
+
+        let deaths: Vec<Combatant> = world
+            .drain_combat_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                CombatEvent::Died { combatant, .. } => Some(combatant),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deaths, vec![Combatant::Drone(0), Combatant::Drone(1)]);
+        assert_eq!(world.drone_index(2), Some(0));
+        assert_eq!(world.drone_index(1), None);
+    }
+
+    #[test]
+    fn a_wave_fight_replays_identically() {
+        let schedule = WaveSchedule::from_json(
+            r#"[{
+                "delay_seconds": 0.5,
+                "jitter_seconds": 0.5,
+                "groups": [{ "enemy": "crawler", "count": 4 }, { "enemy": "brute", "count": 2 }]
+            }]"#,
+        )
+        .unwrap();
+        let edge = TileBox3::new(WorldCoord::new(2, 2, 0), WorldCoord::new(29, 29, 0)).unwrap();
+        let run = || {
+            let mut world = arena();
+            world.set_block(WorldCoord::new(16, 16, 0), CORE).unwrap();
+            for (index, x) in [13.0, 14.0, 18.0].into_iter().enumerate() {
+                world.add_drone(DronePose::new(
+                    [x, 16.0, 0.0],
+                    [1.0, 0.0],
+                    format!("d{index}"),
+                    10,
+                    10,
+                ));
+            }
+            world.set_waves(Waves::new(&schedule, 7, edge, world.tick));
+            for _ in 0..30 * crate::TICKS_PER_SECOND as u64 {
+                world.step();
+            }
+            (world.drain_combat_events(), world.drones().to_vec())
+        };
+
+        let (log, drones) = run();
+        assert!(
+            log.iter()
+                .any(|event| matches!(event, CombatEvent::Died { .. }))
+        );
+        assert_eq!(run(), (log, drones));
+    }
+}
@@ -26,7 +26,7 @@ impl LocalBlockCoord {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct WorldCoord {
     pub x: i32,
     pub y: i32,
@@ -39,6 +39,39 @@ impl WorldCoord {
     }
 }
 
+/// Inclusive axis-aligned box of world tiles. `min` never exceeds `max` on any axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TileBox3 {
+    pub min: WorldCoord,
+    pub max: WorldCoord,
+}
+
+impl TileBox3 {
+    pub fn new(min: WorldCoord, max: WorldCoord) -> Option<Self> {
+        (min.x <= max.x && min.y <= max.y && min.z <= max.z).then_some(Self { min, max })
+    }
+
+    pub fn contains(&self, coord: WorldCoord) -> bool {
+        (self.min.x..=self.max.x).contains(&coord.x)
+            && (self.min.y..=self.max.y).contains(&coord.y)
+            && (self.min.z..=self.max.z).contains(&coord.z)
+    }
+
+    pub fn tile_count(&self) -> u64 {
+        let span = |min: i32, max: i32| (max as i64 - min as i64 + 1) as u64;
+        span(self.min.x, self.max.x) * span(self.min.y, self.max.y) * span(self.min.z, self.max.z)
+    }
+
+    /// Iterates tiles in x-fastest, then y, then z order.
+    pub fn tiles(&self) -> impl Iterator<Item = WorldCoord> + use<> {
+        let (min, max) = (self.min, self.max);
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y)
+                .flat_map(move |y| (min.x..=max.x).map(move |x| WorldCoord::new(x, y, z)))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,4 +105,25 @@ mod tests {
         assert!(world_coords.contains(&c));
         assert!(!world_coords.contains(&d));
     }
+
+    #[test]
+    fn tile_box_rejects_inverted_bounds() {
+        let min = WorldCoord::new(0, 0, 0);
+        let max = WorldCoord::new(2, 1, 0);
+        assert!(TileBox3::new(min, max).is_some());
+        assert!(TileBox3::new(max, min).is_none());
+        assert!(TileBox3::new(min, WorldCoord::new(2, 1, -1)).is_none());
+    }
+
+    #[test]
+    fn tile_box_iterates_every_tile_once() {
+        let tile_box = TileBox3::new(WorldCoord::new(-1, 0, 0), WorldCoord::new(1, 1, 1)).unwrap();
+        let tiles: Vec<WorldCoord> = tile_box.tiles().collect();
+
+        assert_eq!(tiles.len() as u64, tile_box.tile_count());
+        assert_eq!(tiles.first(), Some(&WorldCoord::new(-1, 0, 0)));
+        assert_eq!(tiles.last(), Some(&WorldCoord::new(1, 1, 1)));
+        assert!(tiles.iter().all(|tile| tile_box.contains(*tile)));
+        assert_eq!(tiles.iter().collect::<HashSet<_>>().len(), tiles.len());
+    }
 }
@@ -1,10 +1,23 @@
+use crate::coordinates::WorldCoord;
+
+/// Mining speed of a drone without upgrades; dig times are divided by it.
+pub const BASE_MINING_SPEED: f32 = 1.0;
+
+pub type DroneId = u32;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DronePose {
+    /// Handed out by the world when the drone joins it. Unlike the drone's index it stays the
+    /// same while other drones come and go.
+    pub id: DroneId,
     pub position: [f32; 3], // world-space tile coordinates, fractional allowed
     pub heading: [f32; 2],  // normalized direction; defaults handled by consumers
     pub name: String,
     pub health: i32,
     pub max_health: i32,
+    /// Soaks part of every hit; see `combat::mitigated_damage`.
+    pub armour: i32,
+    pub mining_speed: f32,
 }
 
 impl DronePose {
@@ -18,11 +31,41 @@ impl DronePose {
         let bounded_max = max_health.max(1);
         let clamped_health = health.clamp(0, bounded_max);
         Self {
+            id: 0,
             position,
             heading,
             name: name.into(),
             health: clamped_health,
             max_health: bounded_max,
+            armour: 0,
+            mining_speed: BASE_MINING_SPEED,
+        }
+    }
+
+    /// Sets the drone's mining speed, e.g. after fitting a better drill.
+    pub fn with_mining_speed(mut self, mining_speed: f32) -> Self {
+        self.mining_speed = mining_speed;
+        self
+    }
+
+    pub fn with_armour(mut self, armour: i32) -> Self {
+        self.armour = armour;
+        self
+    }
+
+    /// Points the heading along `(dx, dy)`; a zero vector keeps the previous heading.
+    pub fn face_towards(&mut self, dx: f32, dy: f32) {
+        let length = (dx * dx + dy * dy).sqrt();
+        if length > f32::EPSILON {
+            self.heading = [dx / length, dy / length];
         }
     }
+
+    pub fn tile(&self) -> WorldCoord {
+        WorldCoord::new(
+            self.position[0].floor() as i32,
+            self.position[1].floor() as i32,
+            self.position[2].floor() as i32,
+        )
+    }
 }
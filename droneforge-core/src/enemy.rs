@@ -0,0 +1,529 @@
+use crate::block::{AIR, BlockId, BlockRegistry};
+use crate::chunk_cache::ChunkCache;
+use crate::combat::{Attack, CombatEvent, Combatant, Cooldown, Target, strike};
+use crate::coordinates::WorldCoord;
+use crate::drone::DronePose;
+use crate::pathfind::find_tunnelling_path;
+use crate::structure::CoreStructure;
+use crate::warrior::Warrior;
+use crate::wave::EnemyKind;
+use crate::world::{SECONDS_PER_TICK, TICKS_PER_SECOND};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub type EnemyId = u32;
+
+/// Ticks an enemy follows a route before planning a fresh one, so it notices new walls and
+/// tunnels.
+pub const REPATH_INTERVAL_TICKS: u64 = TICKS_PER_SECOND as u64;
+
+/// Cost of one orthogonal step in the path search.
+const STEP_COST: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnemyStats {
+    pub max_health: i32,
+    pub armour: i32,
+    /// Tiles per second.
+    pub speed: f32,
+    pub attack: Attack,
+    /// Drones closer than this draw the enemy away from the core.
+    pub aggro_range: f32,
+    /// How fast the enemy breaks blocks, relative to a drone digging at base mining speed.
+    pub wall_breaking: f32,
+}
+
+impl EnemyKind {
+    pub fn stats(self) -> EnemyStats {
+        match self {
+            EnemyKind::Crawler => EnemyStats {
+                max_health: 6,
+                armour: 0,
+                speed: 2.0,
+                attack: Attack::new(1, 1, TICKS_PER_SECOND as u64),
+                aggro_range: 4.0,
+                wall_breaking: 0.5,
+            },
+            EnemyKind::Brute => EnemyStats {
+                max_health: 20,
+                armour: 1,
+                speed: 1.0,
+                attack: Attack::new(3, 1, 2 * TICKS_PER_SECOND as u64),
+                aggro_range: 3.0,
+                wall_breaking: 2.0,
+            },
+        }
+    }
+}
+
+/// A hostile unit. It heads for the nearest core on its level and turns on drones that come
+/// close. Any block a drone could dig is fair game when breaking it is quicker than walking
+/// around: untouched terrain as well as walls players built. Only undiggable blocks such as
+/// bedrock stop it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Enemy {
+    pub id: EnemyId,
+    pub kind: EnemyKind,
+    pub position: [f32; 3],
+    pub health: i32,
+    route: VecDeque<WorldCoord>,
+    goal: Option<WorldCoord>,
+    repath_tick: u64,
+    cooldown: Cooldown,
+    /// The block being broken and the seconds spent on it so far.
+    breaking: Option<(WorldCoord, f32)>,
+}
+
+impl Enemy {
+    pub fn new(id: EnemyId, kind: EnemyKind, tile: WorldCoord) -> Self {
+        Self {
+            id,
+            kind,
+            position: [tile.x as f32, tile.y as f32, tile.z as f32],
+            health: kind.stats().max_health,
+            route: VecDeque::new(),
+            goal: None,
+            repath_tick: 0,
+            cooldown: Cooldown::default(),
+            breaking: None,
+        }
+    }
+
+    pub fn stats(&self) -> EnemyStats {
+        self.kind.stats()
+    }
+
+    pub fn tile(&self) -> WorldCoord {
+        WorldCoord::new(
+            self.position[0].floor() as i32,
+            self.position[1].floor() as i32,
+            self.position[2].floor() as i32,
+        )
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    /// The tile the enemy is currently breaking into, if any.
+    pub fn breaking_tile(&self) -> Option<WorldCoord> {
+        self.breaking.map(|(tile, _)| tile)
+    }
+
+    /// The tiles the enemy still means to cross, next one first.
+    pub fn route(&self) -> impl Iterator<Item = WorldCoord> + '_ {
+        self.route.iter().copied()
+    }
+
+    fn distance_to(&self, position: [f32; 3]) -> f32 {
+        let dx = position[0] - self.position[0];
+        let dy = position[1] - self.position[1];
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnemyEvent {
+    Spawned {
+        enemy: EnemyId,
+        kind: EnemyKind,
+        tile: WorldCoord,
+    },
+    AttackedCore {
+        enemy: EnemyId,
+        tile: WorldCoord,
+        damage: i32,
+    },
+    BrokeBlock {
+        enemy: EnemyId,
+        tile: WorldCoord,
+        block: BlockId,
+    },
+}
+
+/// Runs one tick of every living enemy, in order, so the same world always plays out the
+/// same way. Every blow, whether it lands on a drone, a warrior or a core, goes to the
+/// combat log.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn advance_enemies(
+    tick: u64,
+    enemies: &mut [Enemy],
+    drones: &mut [DronePose],
+    warriors: &mut [Warrior],
+    chunk_cache: &mut ChunkCache,
+    cores: &mut [CoreStructure],
+    events: &mut Vec<EnemyEvent>,
+    combat: &mut Vec<CombatEvent>,
+) {
+    for enemy in enemies.iter_mut().filter(|enemy| enemy.is_alive()) {
+        if !attack_defender_in_reach(tick, enemy, drones, warriors, combat) {
+            advance_enemy(tick, enemy, drones, chunk_cache, cores, events, combat);
+        }
+    }
+}
+
+/// Strikes the closest drone or warrior in reach if the cooldown allows; drones come first
+/// on ties. Returns whether anything was in reach, which keeps the enemy standing its ground
+/// either way.
+fn attack_defender_in_reach(
+    tick: u64,
+    enemy: &mut Enemy,
+    drones: &mut [DronePose],
+    warriors: &mut [Warrior],
+    combat: &mut Vec<CombatEvent>,
+) -> bool {
+    let attack = enemy.stats().attack;
+    let tile = enemy.tile();
+    let distance = |target: WorldCoord| {
+        let (dx, dy) = (target.x - tile.x, target.y - tile.y);
+        dx * dx + dy * dy
+    };
+    let nearest_drone = drones
+        .iter()
+        .enumerate()
+        .filter(|(_, drone)| drone.health > 0 && attack.reaches(tile, drone.tile()))
+        .map(|(index, drone)| (distance(drone.tile()), index))
+        .min();
+    let nearest_warrior = warriors
+        .iter()
+        .enumerate()
+        .filter(|(_, warrior)| warrior.is_alive() && attack.reaches(tile, warrior.tile))
+        .map(|(index, warrior)| (distance(warrior.tile), warrior.id, index))
+        .min();
+    let struck_warrior = nearest_warrior.filter(|&(distance, ..)| {
+        nearest_drone.is_none_or(|(drone_distance, _)| distance < drone_distance)
+    });
+
+    let target = match (struck_warrior, nearest_drone) {
+        (Some((_, _, warrior_index)), _) => {
+            let warrior = &mut warriors[warrior_index];
+            Target {
+                combatant: Combatant::Warrior(warrior.id),
+                armour: 0,
+                health: &mut warrior.health,
+            }
+        }
+        (None, Some((_, drone_index))) => {
+            let drone = &mut drones[drone_index];
+            Target {
+                combatant: Combatant::Drone(drone.id),
+                armour: drone.armour,
+                health: &mut drone.health,
+            }
+        }
+        (None, None) => return false,
+    };
+    if enemy.cooldown.is_ready(tick) {
+        strike(
+            tick,
+            Combatant::Enemy(enemy.id),
+            &attack,
+            &mut enemy.cooldown,
+            target,
+            combat,
+        );
+    }
+    true
+}
+
+fn advance_enemy(
+    tick: u64,
+    enemy: &mut Enemy,
+    drones: &[DronePose],
+    chunk_cache: &mut ChunkCache,
+    cores: &mut [CoreStructure],
+    events: &mut Vec<EnemyEvent>,
+    combat: &mut Vec<CombatEvent>,
+) {
+    let stats = enemy.stats();
+
+    let tile = enemy.tile();
+    let chased_drone = nearest_drone(enemy, drones, stats.aggro_range);
+    let goal = match chased_drone {
+        Some(drone_index) => Some(drones[drone_index].tile()),
+        None => nearest_core(tile, cores),
+    };
+    let Some(goal) = goal else {
+        enemy.route.clear();
+        enemy.goal = None;
+        return;
+    };
+
+    if chased_drone.is_none() && within_reach(tile, goal) {
+        let core = cores.iter_mut().find(|core| core.tile == goal);
+        if let Some(core) = core.filter(|_| enemy.cooldown.is_ready(tick)) {
+            let target = Target {
+                combatant: Combatant::Core(goal),
+                armour: 0,
+                health: &mut core.hp,
+            };
+            strike(
+                tick,
+                Combatant::Enemy(enemy.id),
+                &stats.attack,
+                &mut enemy.cooldown,
+                target,
+                combat,
+            );
+            events.push(EnemyEvent::AttackedCore {
+                enemy: enemy.id,
+                tile: goal,
+                damage: stats.attack.damage,
+            });
+        }
+        return;
+    }
+
+    if enemy.goal != Some(goal) || tick >= enemy.repath_tick {
+        enemy.route = plan_route(chunk_cache, &stats, tile, goal).into();
+        enemy.goal = Some(goal);
+        enemy.repath_tick = tick + REPATH_INTERVAL_TICKS;
+    }
+
+    let Some(&next) = enemy.route.front() else {
+        return;
+    };
+    if enemy.breaking.is_some_and(|(breaking, _)| breaking != next) {
+        enemy.breaking = None;
+    }
+
+    let registry = BlockRegistry::global();
+    match chunk_cache.block_at_world(next) {
+        Some(block) if registry.is_solid(block) => {
+            if !registry.is_diggable(block) {
+                // Someone built something unbreakable on the route; plan again next tick.
+                enemy.route.clear();
+                enemy.repath_tick = tick + 1;
+                return;
+            }
+            let (_, spent) = enemy.breaking.get_or_insert((next, 0.0));
+            *spent += SECONDS_PER_TICK * stats.wall_breaking;
+            if *spent + f32::EPSILON >= registry.dig_seconds(block) {
+                enemy.breaking = None;
+                if chunk_cache.set_block(next, AIR).is_ok() {
+                    events.push(EnemyEvent::BrokeBlock {
+                        enemy: enemy.id,
+                        tile: next,
+                        block,
+                    });
+                }
+            }
+        }
+        Some(_) => {
+            if step_towards(&mut enemy.position, next, stats.speed * SECONDS_PER_TICK) {
+                enemy.route.pop_front();
+            }
+        }
+        None => {
+            enemy.route.clear();
+            enemy.repath_tick = tick + 1;
+        }
+    }
+}
+
+/// The closest living drone on the enemy's level within `range`, lowest index first on ties.
+fn nearest_drone(enemy: &Enemy, drones: &[DronePose], range: f32) -> Option<usize> {
+    let level = enemy.tile().z;
+    drones
+        .iter()
+        .enumerate()
+        .filter(|(_, drone)| drone.health > 0 && drone.tile().z == level)
+        .map(|(index, drone)| (index, enemy.distance_to(drone.position)))
+        .filter(|(_, distance)| *distance <= range)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
+
+/// The closest core on `tile`'s level, with ties broken by coordinate.
+fn nearest_core(tile: WorldCoord, cores: &[CoreStructure]) -> Option<WorldCoord> {
+    cores
+        .iter()
+        .map(|core| core.tile)
+        .filter(|core| core.z == tile.z)
+        .min_by_key(|core| {
+            let dx = i64::from(core.x - tile.x);
+            let dy = i64::from(core.y - tile.y);
+            (dx * dx + dy * dy, core.x, core.y)
+        })
+}
+
+pub(crate) fn within_reach(tile: WorldCoord, target: WorldCoord) -> bool {
+    tile.z == target.z && (tile.x - target.x).abs() <= 1 && (tile.y - target.y).abs() <= 1
+}
+
+/// Tiles from `start` towards `goal`; empty when there is no way through. Breaking a block
+/// is priced as the distance the enemy could have walked in the time it takes.
+fn plan_route(
+    chunk_cache: &mut ChunkCache,
+    stats: &EnemyStats,
+    start: WorldCoord,
+    goal: WorldCoord,
+) -> Vec<WorldCoord> {
+    let registry = BlockRegistry::global();
+    let breaking_speed = stats.wall_breaking.max(f32::EPSILON);
+    let extra_cost = |coord: WorldCoord| {
+        let block = chunk_cache.load_block_at(coord)?;
+        if !registry.is_solid(block) {
+            return Some(0);
+        }
+        if !registry.is_diggable(block) {
+            return None;
+        }
+        let seconds = registry.dig_seconds(block) / breaking_speed;
+        Some((seconds * stats.speed * STEP_COST).ceil() as u32)
+    };
+    find_tunnelling_path(extra_cost, start, goal).unwrap_or_default()
+}
+
+/// Moves `position` up to `distance` towards `tile` and reports whether it got there.
+fn step_towards(position: &mut [f32; 3], tile: WorldCoord, distance: f32) -> bool {
+    let target = [tile.x as f32, tile.y as f32, tile.z as f32];
+    let offset = [
+        target[0] - position[0],
+        target[1] - position[1],
+        target[2] - position[2],
+    ];
+    let remaining = (offset[0] * offset[0] + offset[1] * offset[1] + offset[2] * offset[2]).sqrt();
+    if remaining <= distance {
+        *position = target;
+        return true;
+    }
+    for (axis, delta) in position.iter_mut().zip(offset) {
+        *axis += delta / remaining * distance;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{BEDROCK, CORE, DIRT, STONE};
+    use crate::coordinates::TileBox3;
+    use crate::test_support::arena;
+    use crate::wave::{WaveSchedule, Waves};
+    use crate::world::World;
+    use crate::worldgen::FlatGenerator;
+
+    fn step_seconds(world: &mut World, seconds: u64) {
+        for _ in 0..seconds * TICKS_PER_SECOND as u64 {
+            world.step();
+        }
+    }
+
+    #[test]
+    fn heads_for_the_nearest_core_and_attacks_it() {
+        let mut world = arena();
+        let (near, far) = (WorldCoord::new(8, 10, 0), WorldCoord::new(25, 10, 0));
+        world.set_block(far, CORE).unwrap();
+        world.set_block(near, CORE).unwrap();
+        world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(1, 10, 0));
+
+        step_seconds(&mut world, 5);
+
+        assert_eq!(world.enemies()[0].tile(), WorldCoord::new(7, 10, 0));
+        assert!(world.drain_enemy_events().iter().any(|event| matches!(
+            event,
+            EnemyEvent::AttackedCore { tile, damage: 1, .. } if *tile == near
+        )));
+    }
+
+    #[test]
+    fn breaks_through_walls_around_the_core() {
+        let mut world = arena();
+        let core = WorldCoord::new(15, 15, 0);
+        world.set_block(core, CORE).unwrap();
+        let ring = TileBox3::new(WorldCoord::new(14, 14, 0), WorldCoord::new(16, 16, 0)).unwrap();
+        for tile in ring.tiles().filter(|&tile| tile != core) {
+            world.set_block(tile, STONE).unwrap();
+        }
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(5, 15, 0));
+
+        step_seconds(&mut world, 20);
+
+        let events = world.drain_enemy_events();
+        let broken = events.iter().position(|event| {
+            matches!(event, EnemyEvent::BrokeBlock { tile, block, .. }
+                if *tile == WorldCoord::new(14, 15, 0) && *block == STONE)
+        });
+        let attacked = events
+            .iter()
+            .position(|event| matches!(event, EnemyEvent::AttackedCore { .. }));
+        assert!(broken.is_some() && attacked.is_some());
+        assert!(broken < attacked);
+        assert_eq!(world.cores()[0].tile, core);
+    }
+
+    #[test]
+    fn tunnels_through_untouched_terrain_but_not_bedrock() {
+        let mut world = World::with_generator(FlatGenerator::new(0, DIRT));
+        let core = WorldCoord::new(4, 0, 0);
+        world.set_block(core, CORE).unwrap();
+        for y in -2..=2 {
+            world.set_block(WorldCoord::new(2, y, 0), BEDROCK).unwrap();
+        }
+        world.set_block(WorldCoord::new(0, 0, 0), AIR).unwrap();
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(0, 0, 0));
+
+        step_seconds(&mut world, 30);
+
+        let events = world.drain_enemy_events();
+        let broken: Vec<BlockId> = events
+            .iter()
+            .filter_map(|event| match event {
+                EnemyEvent::BrokeBlock { block, .. } => Some(*block),
+                _ => None,
+            })
+            .collect();
+        assert!(broken.len() >= 3);
+        assert!(broken.iter().all(|&block| block == DIRT));
+        assert!(
+            events.iter().any(
+                |event| matches!(event, EnemyEvent::AttackedCore { tile, .. } if *tile == core)
+            )
+        );
+    }
+
+    #[test]
+    fn turns_on_drones_that_come_close() {
+        let mut world = arena();
+        world.set_block(WorldCoord::new(30, 5, 0), CORE).unwrap();
+        world.add_drone(DronePose::new([6.0, 5.0, 0.0], [1.0, 0.0], "d1", 10, 10));
+        world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(3, 5, 0));
+
+        step_seconds(&mut world, 3);
+
+        assert!(world.drones()[0].health < 10);
+        assert!(
+            world
+                .drain_enemy_events()
+                .iter()
+                .all(|event| !matches!(event, EnemyEvent::AttackedCore { .. }))
+        );
+    }
+
+    #[test]
+    fn the_same_attack_plays_out_the_same_way() {
+        let schedule = WaveSchedule::from_json(
+            r#"[{
+                "delay_seconds": 1.0,
+                "jitter_seconds": 0.5,
+                "groups": [{ "enemy": "crawler", "count": 3 }, { "enemy": "brute", "count": 2 }]
+            }]"#,
+        )
+        .unwrap();
+        let edge = TileBox3::new(WorldCoord::new(0, 0, 0), WorldCoord::new(31, 31, 0)).unwrap();
+        let run = || {
+            let mut world = arena();
+            world.set_block(WorldCoord::new(16, 16, 0), CORE).unwrap();
+            world.set_block(WorldCoord::new(17, 16, 0), STONE).unwrap();
+            world.add_drone(DronePose::new([12.0, 16.0, 0.0], [1.0, 0.0], "d1", 10, 10));
+            world.set_waves(Waves::new(&schedule, 11, edge, world.tick));
+            step_seconds(&mut world, 20);
+            (world.drain_enemy_events(), world.enemies().to_vec())
+        };
+
+        let (events, enemies) = run();
+        assert_eq!(enemies.len(), 5);
+        assert_eq!(run(), (events, enemies));
+    }
+}
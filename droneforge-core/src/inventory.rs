@@ -1,95 +1,343 @@
-use crate::block::BlockId;
+use crate::item::{ItemId, ItemRegistry};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
 
 pub const INVENTORY_SLOTS: usize = 10;
-pub const MAX_INVENTORY_UNITS: u32 = 64;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InventorySlot {
-    pub block: Option<BlockId>,
+    pub item: Option<ItemId>,
     pub count: u32,
 }
 
 pub type InventorySlots = [InventorySlot; INVENTORY_SLOTS];
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryErrorReason {
+    UnknownInventory,
+    InvalidSlot,
+    InvalidCount,
+    SlotEmpty,
+    /// The two slots hold different items.
+    ItemMismatch,
+    NoSpace,
+    /// Inventories only trade between neighbouring tiles.
+    OutOfReach,
+    /// Items were to be handed from an inventory to itself.
+    SameInventory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryError {
+    pub reason: InventoryErrorReason,
+}
+
+impl InventoryError {
+    pub fn new(reason: InventoryErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            InventoryErrorReason::UnknownInventory => "inventory does not exist",
+            InventoryErrorReason::InvalidSlot => "invalid inventory slot",
+            InventoryErrorReason::InvalidCount => "cannot split off that many",
+            InventoryErrorReason::SlotEmpty => "inventory slot is empty",
+            InventoryErrorReason::ItemMismatch => "slots hold different items",
+            InventoryErrorReason::NoSpace => "inventory full",
+            InventoryErrorReason::OutOfReach => "too far away to hand items over",
+            InventoryErrorReason::SameInventory => "cannot hand items to the same inventory",
+        }
+    }
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for InventoryError {}
+
 pub fn empty_inventory() -> InventorySlots {
     [InventorySlot::default(); INVENTORY_SLOTS]
 }
 
-pub fn slot_block(slots: &InventorySlots, slot_index: usize) -> Option<(BlockId, u32)> {
+/// Units of `item` a single slot holds.
+pub fn stack_limit(item: ItemId) -> u32 {
+    ItemRegistry::global().stack_size(item)
+}
+
+pub fn slot_item(slots: &InventorySlots, slot_index: usize) -> Option<(ItemId, u32)> {
     slots
         .get(slot_index)
-        .and_then(|slot| slot.block.map(|block| (block, slot.count)))
+        .and_then(|slot| slot.item.map(|item| (item, slot.count)))
         .filter(|(_, count)| *count > 0)
 }
 
-pub fn remove_block_from_slot(slots: &mut InventorySlots, slot_index: usize) -> Option<BlockId> {
+pub fn remove_item_from_slot(slots: &mut InventorySlots, slot_index: usize) -> Option<ItemId> {
     let slot = slots.get_mut(slot_index)?;
     if slot.count == 0 {
-        slot.block = None;
+        slot.item = None;
         return None;
     }
 
     slot.count = slot.count.saturating_sub(1);
-    let block = slot.block;
+    let item = slot.item;
     if slot.count == 0 {
-        slot.block = None;
+        slot.item = None;
     }
-    block
+    item
 }
 
 pub fn inventory_unit_count(slots: &InventorySlots) -> u32 {
     slots.iter().map(|slot| slot.count).sum()
 }
 
-pub fn add_block_to_slots(slots: &mut InventorySlots, block: BlockId) -> bool {
-    if inventory_unit_count(slots) >= MAX_INVENTORY_UNITS {
-        return false;
+pub fn item_count(slots: &InventorySlots, item: ItemId) -> u32 {
+    slots
+        .iter()
+        .filter(|slot| slot.item == Some(item))
+        .map(|slot| slot.count)
+        .sum()
+}
+
+/// Units of `item` that still fit: the room left on its stacks plus every empty slot.
+pub fn space_for(slots: &InventorySlots, item: ItemId) -> u32 {
+    let limit = stack_limit(item);
+    slots
+        .iter()
+        .map(|slot| match slot.item {
+            None => limit,
+            Some(held) if held == item => limit.saturating_sub(slot.count),
+            Some(_) => 0,
+        })
+        .sum()
+}
+
+pub fn add_item_to_slots(slots: &mut InventorySlots, item: ItemId) -> bool {
+    add_items(slots, item, 1) == 0
+}
+
+/// Adds up to `count` of `item`, topping up existing stacks before opening empty slots.
+/// Returns how many did not fit.
+pub fn add_items(slots: &mut InventorySlots, item: ItemId, mut count: u32) -> u32 {
+    let limit = stack_limit(item);
+    for slot in slots.iter_mut().filter(|slot| slot.item == Some(item)) {
+        let added = limit.saturating_sub(slot.count).min(count);
+        slot.count += added;
+        count -= added;
     }
+    for slot in slots.iter_mut().filter(|slot| slot.item.is_none()) {
+        if count == 0 {
+            break;
+        }
+        let added = limit.min(count);
+        *slot = InventorySlot {
+            item: Some(item),
+            count: added,
+        };
+        count -= added;
+    }
+    count
+}
 
-    if let Some(slot) = slots.iter_mut().find(|slot| slot.block == Some(block)) {
-        slot.count = slot.count.saturating_add(1);
-        return true;
+/// Takes up to `count` of `item` out of `slots`, last slot first so earlier stacks stay put.
+/// Returns how many were missing.
+pub fn remove_items(slots: &mut InventorySlots, item: ItemId, mut count: u32) -> u32 {
+    for slot in slots.iter_mut().rev() {
+        if count == 0 {
+            break;
+        }
+        if slot.item != Some(item) {
+            continue;
+        }
+        let taken = slot.count.min(count);
+        slot.count -= taken;
+        count -= taken;
+        if slot.count == 0 {
+            slot.item = None;
+        }
     }
+    count
+}
 
-    if let Some(slot) = slots.iter_mut().find(|slot| slot.block.is_none()) {
-        slot.block = Some(block);
-        slot.count = 1;
-        return true;
+pub fn swap_slots(
+    slots: &mut InventorySlots,
+    first: usize,
+    second: usize,
+) -> Result<(), InventoryError> {
+    if first >= slots.len() || second >= slots.len() {
+        return Err(InventoryError::new(InventoryErrorReason::InvalidSlot));
     }
+    slots.swap(first, second);
+    Ok(())
+}
 
-    false
+/// Moves `count` units out of `slot_index` into the first empty slot and returns that slot.
+pub fn split_slot(
+    slots: &mut InventorySlots,
+    slot_index: usize,
+    count: u32,
+) -> Result<usize, InventoryError> {
+    let (item, held) = slot_item(slots, slot_index).ok_or_else(|| {
+        InventoryError::new(if slot_index < slots.len() {
+            InventoryErrorReason::SlotEmpty
+        } else {
+            InventoryErrorReason::InvalidSlot
+        })
+    })?;
+    if count == 0 || count >= held {
+        return Err(InventoryError::new(InventoryErrorReason::InvalidCount));
+    }
+    let target = slots
+        .iter()
+        .position(|slot| slot.item.is_none())
+        .ok_or_else(|| InventoryError::new(InventoryErrorReason::NoSpace))?;
+
+    slots[slot_index].count -= count;
+    slots[target] = InventorySlot {
+        item: Some(item),
+        count,
+    };
+    Ok(target)
+}
+
+/// Moves as much of slot `from` onto slot `into` as its stack allows and returns the units
+/// moved. `into` must be empty or hold the same item.
+pub fn merge_slots(
+    slots: &mut InventorySlots,
+    from: usize,
+    into: usize,
+) -> Result<u32, InventoryError> {
+    if from >= slots.len() || into >= slots.len() || from == into {
+        return Err(InventoryError::new(InventoryErrorReason::InvalidSlot));
+    }
+    let (item, held) = slot_item(slots, from)
+        .ok_or_else(|| InventoryError::new(InventoryErrorReason::SlotEmpty))?;
+    let target = slots[into];
+    if target.item.is_some_and(|other| other != item) {
+        return Err(InventoryError::new(InventoryErrorReason::ItemMismatch));
+    }
+
+    let moved = stack_limit(item).saturating_sub(target.count).min(held);
+    slots[into] = InventorySlot {
+        item: Some(item),
+        count: target.count + moved,
+    };
+    slots[from].count -= moved;
+    if slots[from].count == 0 {
+        slots[from].item = None;
+    }
+    Ok(moved)
+}
+
+/// Moves up to `count` of `item` from one inventory to another, limited by what `from`
+/// holds and what fits in `to`. Returns the units moved.
+pub fn transfer_items(
+    from: &mut InventorySlots,
+    to: &mut InventorySlots,
+    item: ItemId,
+    count: u32,
+) -> u32 {
+    let moved = count.min(item_count(from, item)).min(space_for(to, item));
+    remove_items(from, item, moved);
+    add_items(to, item, moved);
+    moved
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::item::{ITEM_CORE, ITEM_IRON_ORE, ITEM_STONE};
 
     #[test]
-    fn slot_block_reports_present_item() {
+    fn slot_item_reports_present_item() {
         let mut slots = empty_inventory();
         slots[2] = InventorySlot {
-            block: Some(7),
+            item: Some(7),
             count: 3,
         };
 
-        assert_eq!(slot_block(&slots, 2), Some((7, 3)));
-        assert_eq!(slot_block(&slots, 1), None);
+        assert_eq!(slot_item(&slots, 2), Some((7, 3)));
+        assert_eq!(slot_item(&slots, 1), None);
     }
 
     #[test]
-    fn remove_block_from_slot_decrements_and_clears() {
+    fn remove_item_from_slot_decrements_and_clears() {
         let mut slots = empty_inventory();
         slots[1] = InventorySlot {
-            block: Some(5),
+            item: Some(5),
             count: 2,
         };
 
-        assert_eq!(remove_block_from_slot(&mut slots, 1), Some(5));
-        assert_eq!(slot_block(&slots, 1), Some((5, 1)));
+        assert_eq!(remove_item_from_slot(&mut slots, 1), Some(5));
+        assert_eq!(slot_item(&slots, 1), Some((5, 1)));
 
-        assert_eq!(remove_block_from_slot(&mut slots, 1), Some(5));
-        assert_eq!(slot_block(&slots, 1), None);
-        assert!(slots[1].block.is_none());
+        assert_eq!(remove_item_from_slot(&mut slots, 1), Some(5));
+        assert_eq!(slot_item(&slots, 1), None);
+        assert!(slots[1].item.is_none());
         assert_eq!(slots[1].count, 0);
     }
+
+    #[test]
+    fn add_items_fills_stacks_up_to_their_limit() {
+        let mut slots = empty_inventory();
+        let limit = stack_limit(ITEM_IRON_ORE);
+
+        assert_eq!(add_items(&mut slots, ITEM_IRON_ORE, limit + 3), 0);
+        assert_eq!(slot_item(&slots, 0), Some((ITEM_IRON_ORE, limit)));
+        assert_eq!(slot_item(&slots, 1), Some((ITEM_IRON_ORE, 3)));
+
+        let leftover = add_items(&mut slots, ITEM_CORE, INVENTORY_SLOTS as u32);
+        assert_eq!(leftover, 2);
+        assert_eq!(item_count(&slots, ITEM_CORE), INVENTORY_SLOTS as u32 - 2);
+        assert_eq!(space_for(&slots, ITEM_IRON_ORE), limit - 3);
+    }
+
+    #[test]
+    fn remove_items_reports_what_was_missing() {
+        let mut slots = empty_inventory();
+        add_items(&mut slots, ITEM_STONE, 5);
+
+        assert_eq!(remove_items(&mut slots, ITEM_STONE, 8), 3);
+        assert_eq!(item_count(&slots, ITEM_STONE), 0);
+        assert!(slots[0].item.is_none());
+    }
+
+    #[test]
+    fn split_and_merge_round_trip() {
+        let mut slots = empty_inventory();
+        add_items(&mut slots, ITEM_STONE, 10);
+        add_items(&mut slots, ITEM_IRON_ORE, 1);
+
+        let split = split_slot(&mut slots, 0, 4).unwrap();
+        assert_eq!(split, 2);
+        assert_eq!(slot_item(&slots, 0), Some((ITEM_STONE, 6)));
+        assert_eq!(slot_item(&slots, 2), Some((ITEM_STONE, 4)));
+
+        let err = merge_slots(&mut slots, 1, 2).unwrap_err();
+        assert_eq!(err.reason, InventoryErrorReason::ItemMismatch);
+
+        assert_eq!(merge_slots(&mut slots, 2, 0), Ok(4));
+        assert_eq!(slot_item(&slots, 0), Some((ITEM_STONE, 10)));
+        assert_eq!(slot_item(&slots, 2), None);
+
+        swap_slots(&mut slots, 0, 1).unwrap();
+        assert_eq!(slot_item(&slots, 0), Some((ITEM_IRON_ORE, 1)));
+    }
+
+    #[test]
+    fn transfer_moves_only_what_fits() {
+        let mut from = empty_inventory();
+        add_items(&mut from, ITEM_STONE, 20);
+        let mut to = empty_inventory();
+        add_items(&mut to, ITEM_CORE, INVENTORY_SLOTS as u32 - 1);
+        add_items(&mut to, ITEM_STONE, stack_limit(ITEM_STONE) - 5);
+
+        assert_eq!(transfer_items(&mut from, &mut to, ITEM_STONE, 20), 5);
+        assert_eq!(item_count(&from, ITEM_STONE), 15);
+        assert_eq!(item_count(&to, ITEM_STONE), stack_limit(ITEM_STONE));
+    }
 }
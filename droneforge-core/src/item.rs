@@ -0,0 +1,239 @@
+use crate::block::{BlockId, BlockRegistry};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Anything a drone can carry. Ids are independent of [`BlockId`]s; blocks turn into items
+/// through their drop, and placeable items name the block they become.
+pub type ItemId = u16;
+
+pub const ITEM_STONE: ItemId = 1;
+pub const ITEM_IRON_ORE: ItemId = 2;
+pub const ITEM_IRON_PLATE: ItemId = 3;
+pub const ITEM_GEAR: ItemId = 4;
+pub const ITEM_CORE: ItemId = 5;
+
+/// How many units fit in one inventory slot when a definition does not say.
+pub const DEFAULT_STACK_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// Dug straight out of the ground.
+    Raw,
+    Refined,
+    Component,
+    Placeable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemDefinition {
+    pub id: ItemId,
+    pub name: String,
+    pub kind: ItemKind,
+    /// The block this item turns into when placed in the world.
+    pub places: Option<BlockId>,
+    /// Units one inventory slot holds.
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+}
+
+fn default_stack_size() -> u32 {
+    DEFAULT_STACK_SIZE
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemRegistryErrorReason {
+    Json(String),
+    DuplicateId(ItemId),
+    /// Item id 0 is reserved for "no item".
+    ReservedId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemRegistryError {
+    pub reason: ItemRegistryErrorReason,
+}
+
+impl ItemRegistryError {
+    pub fn new(reason: ItemRegistryErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            ItemRegistryErrorReason::Json(_) => "item definitions are not valid",
+            ItemRegistryErrorReason::DuplicateId(_) => "item id is defined twice",
+            ItemRegistryErrorReason::ReservedId => "item id 0 is reserved",
+        }
+    }
+}
+
+impl fmt::Display for ItemRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            ItemRegistryErrorReason::Json(detail) => write!(f, "{} ({})", self.message(), detail),
+            ItemRegistryErrorReason::DuplicateId(id) => write!(f, "{} ({})", self.message(), id),
+            ItemRegistryErrorReason::ReservedId => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for ItemRegistryError {}
+
+const BUILTIN_ITEMS: &str = include_str!("../assets/items.json");
+
+static GLOBAL_REGISTRY: OnceLock<ItemRegistry> = OnceLock::new();
+
+/// Item properties, indexed by [`ItemId`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemRegistry {
+    definitions: Vec<Option<ItemDefinition>>,
+}
+
+impl ItemRegistry {
+    pub fn from_definitions(
+        definitions: impl IntoIterator<Item = ItemDefinition>,
+    ) -> Result<Self, ItemRegistryError> {
+        let mut slots: Vec<Option<ItemDefinition>> = vec![None];
+        for definition in definitions {
+            if definition.id == 0 {
+                return Err(ItemRegistryError::new(ItemRegistryErrorReason::ReservedId));
+            }
+            let index = definition.id as usize;
+            if slots.len() <= index {
+                slots.resize(index + 1, None);
+            }
+            if slots[index].is_some() {
+                return Err(ItemRegistryError::new(
+                    ItemRegistryErrorReason::DuplicateId(definition.id),
+                ));
+            }
+            slots[index] = Some(definition);
+        }
+        Ok(Self { definitions: slots })
+    }
+
+    /// Parses a JSON array of [`ItemDefinition`]s.
+    pub fn from_json(source: &str) -> Result<Self, ItemRegistryError> {
+        let definitions: Vec<ItemDefinition> = serde_json::from_str(source).map_err(|err| {
+            ItemRegistryError::new(ItemRegistryErrorReason::Json(err.to_string()))
+        })?;
+        Self::from_definitions(definitions)
+    }
+
+    /// The items shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_ITEMS).expect("built-in item definitions must be valid")
+    }
+
+    /// The registry every item query goes through. It is the built-in one unless
+    /// [`ItemRegistry::install`] ran first.
+    pub fn global() -> &'static ItemRegistry {
+        GLOBAL_REGISTRY.get_or_init(Self::builtin)
+    }
+
+    /// Makes `self` the global registry. Fails, handing the registry back, once the global
+    /// registry has been used or installed.
+    pub fn install(self) -> Result<(), ItemRegistry> {
+        GLOBAL_REGISTRY.set(self)
+    }
+
+    pub fn get(&self, item: ItemId) -> Option<&ItemDefinition> {
+        self.definitions.get(item as usize)?.as_ref()
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = &ItemDefinition> {
+        self.definitions.iter().flatten()
+    }
+
+    pub fn name(&self, item: ItemId) -> &str {
+        self.get(item)
+            .map_or("Unknown", |definition| &definition.name)
+    }
+
+    pub fn kind(&self, item: ItemId) -> Option<ItemKind> {
+        self.get(item).map(|definition| definition.kind)
+    }
+
+    /// Units of `item` one inventory slot holds; never less than one.
+    pub fn stack_size(&self, item: ItemId) -> u32 {
+        self.get(item)
+            .map_or(DEFAULT_STACK_SIZE, |definition| definition.stack_size)
+            .max(1)
+    }
+
+    /// The block `item` becomes when placed, if it can be placed at all.
+    pub fn placed_block(&self, item: ItemId) -> Option<BlockId> {
+        self.get(item).and_then(|definition| definition.places)
+    }
+
+    /// A block to draw as the item's icon: the block it places, or else the block it drops
+    /// from.
+    pub fn icon_block(&self, item: ItemId) -> Option<BlockId> {
+        self.placed_block(item).or_else(|| {
+            BlockRegistry::global()
+                .definitions()
+                .find(|block| block.drop == Some(item))
+                .map(|block| block.id)
+        })
+    }
+}
+
+/// The item a drone collects for digging out `block`, if any.
+pub fn item_for_block(block: BlockId) -> Option<ItemId> {
+    BlockRegistry::global().drop(block)
+}
+
+/// The block `item` places, if it is placeable.
+pub fn block_for_item(item: ItemId) -> Option<BlockId> {
+    ItemRegistry::global().placed_block(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{CORE, DIRT, IRON, STONE};
+
+    #[test]
+    fn maps_drops_to_items_and_placeables_back_to_blocks() {
+        assert_eq!(item_for_block(STONE), Some(ITEM_STONE));
+        assert_eq!(item_for_block(IRON), Some(ITEM_IRON_ORE));
+        assert_eq!(item_for_block(DIRT), None);
+        assert_eq!(item_for_block(CORE), Some(ITEM_CORE));
+
+        assert_eq!(block_for_item(ITEM_CORE), Some(CORE));
+        assert_eq!(block_for_item(ITEM_STONE), None);
+        assert_eq!(block_for_item(ITEM_GEAR), None);
+    }
+
+    #[test]
+    fn builtin_registry_covers_every_kind() {
+        let registry = ItemRegistry::builtin();
+
+        assert_eq!(registry.kind(ITEM_IRON_ORE), Some(ItemKind::Raw));
+        assert_eq!(registry.kind(ITEM_IRON_PLATE), Some(ItemKind::Refined));
+        assert_eq!(registry.kind(ITEM_GEAR), Some(ItemKind::Component));
+        assert_eq!(registry.kind(ITEM_CORE), Some(ItemKind::Placeable));
+        assert_eq!(registry.name(ITEM_IRON_PLATE), "Iron plate");
+        assert_eq!(registry.icon_block(ITEM_IRON_ORE), Some(IRON));
+        assert_eq!(registry.icon_block(ITEM_GEAR), None);
+        assert_eq!(registry.stack_size(ITEM_CORE), 1);
+        assert_eq!(registry.stack_size(999), DEFAULT_STACK_SIZE);
+    }
+
+    #[test]
+    fn rejects_duplicate_and_reserved_ids() {
+        let duplicate = r#"[
+            { "id": 1, "name": "Stone", "kind": "raw", "places": null },
+            { "id": 1, "name": "Rock", "kind": "raw", "places": null }
+        ]"#;
+        let err = ItemRegistry::from_json(duplicate).unwrap_err();
+        assert_eq!(err.reason, ItemRegistryErrorReason::DuplicateId(1));
+
+        let reserved = r#"[{ "id": 0, "name": "Nothing", "kind": "raw", "places": null }]"#;
+        let err = ItemRegistry::from_json(reserved).unwrap_err();
+        assert_eq!(err.reason, ItemRegistryErrorReason::ReservedId);
+    }
+}
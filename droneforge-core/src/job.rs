@@ -0,0 +1,413 @@
+use crate::block::BlockRegistry;
+use crate::chunk_cache::ChunkCache;
+use crate::coordinates::{TileBox3, WorldCoord};
+use crate::order::{
+    OrderErrorReason, OrderEvent, OrderOutcome, collects_block, validate_dig_target,
+};
+use serde::{Deserialize, Serialize};
+
+/// How many frontier tiles a drone tries to route to before giving up for this tick.
+pub const MAX_ROUTE_ATTEMPTS: usize = 8;
+
+/// Tiles a drone may dig from: its eight neighbours on the level plus straight up and down.
+const STAND_OFFSETS: [(i32, i32, i32); 10] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (1, 1, 0),
+    (1, -1, 0),
+    (-1, 1, 0),
+    (-1, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct JobId(pub u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MineTileState {
+    Pending,
+    Working { drone_index: usize, percent: u32 },
+    Dug,
+    Failed(OrderErrorReason),
+}
+
+impl MineTileState {
+    pub fn is_worked_by(&self, drone_index: usize) -> bool {
+        matches!(self, MineTileState::Working { drone_index: worker, .. } if *worker == drone_index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MineTile {
+    pub coord: WorldCoord,
+    pub state: MineTileState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MineProgress {
+    pub total: usize,
+    pub pending: usize,
+    pub working: usize,
+    pub dug: usize,
+    pub failed: usize,
+}
+
+/// Where a drone should go and what it should dig next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MineAssignment {
+    pub tile: WorldCoord,
+    /// Waypoints to the tile the drone digs from; empty when it is already in place.
+    pub route: Vec<WorldCoord>,
+}
+
+/// Digs out every wall inside a box. Tiles are handed out one at a time from the frontier:
+/// the highest level first, then whichever reachable tile is closest to the asking drone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MineBoxJob {
+    pub id: JobId,
+    pub tile_box: TileBox3,
+    tiles: Vec<MineTile>,
+    dropped_blocks: u32,
+}
+
+impl MineBoxJob {
+    /// Lists the box's diggable tiles; open, undiggable and unloaded tiles are left out.
+    pub fn new(id: JobId, tile_box: TileBox3, chunk_cache: &ChunkCache) -> Self {
+        let tiles = tile_box
+            .tiles()
+            .filter(|&coord| {
+                chunk_cache.block_at_world(coord).is_some_and(|block| {
+                    let registry = BlockRegistry::global();
+                    registry.is_solid(block) && registry.is_diggable(block)
+                })
+            })
+            .map(|coord| MineTile {
+                coord,
+                state: MineTileState::Pending,
+            })
+            .collect();
+
+        Self {
+            id,
+            tile_box,
+            tiles,
+            dropped_blocks: 0,
+        }
+    }
+
+    pub fn tiles(&self) -> &[MineTile] {
+        &self.tiles
+    }
+
+    pub fn progress(&self) -> MineProgress {
+        let mut progress = MineProgress {
+            total: self.tiles.len(),
+            ..MineProgress::default()
+        };
+        for tile in &self.tiles {
+            match tile.state {
+                MineTileState::Pending => progress.pending += 1,
+                MineTileState::Working { .. } => progress.working += 1,
+                MineTileState::Dug => progress.dug += 1,
+                MineTileState::Failed(_) => progress.failed += 1,
+            }
+        }
+        progress
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tiles
+            .iter()
+            .all(|tile| matches!(tile.state, MineTileState::Dug | MineTileState::Failed(_)))
+    }
+
+    /// Collectable blocks that were dug but did not fit in the drone's inventory. Their items
+    /// are left on the ground where the block stood.
+    pub fn dropped_blocks(&self) -> u32 {
+        self.dropped_blocks
+    }
+
+    pub fn tile_assigned_to(&self, drone_index: usize) -> Option<WorldCoord> {
+        self.tiles
+            .iter()
+            .find(|tile| tile.state.is_worked_by(drone_index))
+            .map(|tile| tile.coord)
+    }
+
+    /// Picks the next tile for a drone standing on `drone_tile` and marks it as being worked.
+    /// Tiles rejected by `available`, such as ones reserved by other drones, are skipped.
+    pub fn next_assignment<F>(
+        &mut self,
+        chunk_cache: &mut ChunkCache,
+        drone_index: usize,
+        drone_tile: WorldCoord,
+        mut available: F,
+    ) -> Option<MineAssignment>
+    where
+        F: FnMut(WorldCoord) -> bool,
+    {
+        let mut candidates = Vec::new();
+        for (index, tile) in self.tiles.iter_mut().enumerate() {
+            if tile.state != MineTileState::Pending {
+                continue;
+            }
+            // Someone else may have cleared the tile since the job was listed.
+            if chunk_cache
+                .block_at_world(tile.coord)
+                .is_some_and(|block| !BlockRegistry::global().is_solid(block))
+            {
+                tile.state = MineTileState::Dug;
+                continue;
+            }
+            if available(tile.coord) && on_frontier(chunk_cache, tile.coord, drone_tile) {
+                candidates.push(index);
+            }
+        }
+
+        candidates.sort_by_key(|&index| {
+            let coord = self.tiles[index].coord;
+            (
+                std::cmp::Reverse(coord.z),
+                distance_squared(coord, drone_tile),
+                index,
+            )
+        });
+
+        for &index in candidates.iter().take(MAX_ROUTE_ATTEMPTS) {
+            let coord = self.tiles[index].coord;
+            if let Some(route) = route_to_stand(chunk_cache, drone_tile, coord) {
+                self.tiles[index].state = MineTileState::Working {
+                    drone_index,
+                    percent: 0,
+                };
+                return Some(MineAssignment { tile: coord, route });
+            }
+        }
+        None
+    }
+
+    /// Puts the drone's tile back in the pending pool, e.g. when it receives other orders.
+    pub fn release(&mut self, drone_index: usize) {
+        for tile in &mut self.tiles {
+            if tile.state.is_worked_by(drone_index) {
+                tile.state = MineTileState::Pending;
+            }
+        }
+    }
+
+    /// Renumbers workers after the drone at `removed` left the world; its own tiles must have
+    /// been released first.
+    pub(crate) fn forget_drone(&mut self, removed: usize) {
+        for tile in &mut self.tiles {
+            if let MineTileState::Working { drone_index, .. } = &mut tile.state
+                && *drone_index > removed
+            {
+                *drone_index -= 1;
+            }
+        }
+    }
+
+    /// Updates tile states from an order event of the drone at `drone_index`.
+    pub fn apply_event(&mut self, drone_index: usize, event: &OrderEvent) {
+        let Some(tile) = self
+            .tiles
+            .iter_mut()
+            .find(|tile| tile.state.is_worked_by(drone_index))
+        else {
+            return;
+        };
+
+        match event {
+            OrderEvent::Progressed { percent, .. } => {
+                tile.state = MineTileState::Working {
+                    drone_index,
+                    percent: *percent,
+                };
+            }
+            OrderEvent::Completed {
+                outcome:
+                    OrderOutcome::Dug {
+                        tile: dug,
+                        block,
+                        collected,
+                    },
+                ..
+            } if *dug == tile.coord => {
+                tile.state = MineTileState::Dug;
+                if collects_block(*block) && !collected {
+                    self.dropped_blocks += 1;
+                }
+            }
+            OrderEvent::Failed { target, error, .. } if *target == tile.coord => {
+                tile.state = match error.reason {
+                    OrderErrorReason::NothingToDig => MineTileState::Dug,
+                    ref reason => MineTileState::Failed(reason.clone()),
+                };
+            }
+            // The drone never reached its digging spot; let the tile be planned again.
+            OrderEvent::Failed { .. } => tile.state = MineTileState::Pending,
+            _ => {}
+        }
+    }
+}
+
+/// A tile is on the frontier once some tile it can be dug from is open (or is the drone).
+fn on_frontier(chunk_cache: &ChunkCache, tile: WorldCoord, drone_tile: WorldCoord) -> bool {
+    stand_tiles(tile).any(|stand| can_dig_from(chunk_cache, stand, tile, drone_tile))
+}
+
+fn route_to_stand(
+    chunk_cache: &mut ChunkCache,
+    drone_tile: WorldCoord,
+    tile: WorldCoord,
+) -> Option<Vec<WorldCoord>> {
+    let mut stands: Vec<WorldCoord> = stand_tiles(tile)
+        .filter(|&stand| can_dig_from(&*chunk_cache, stand, tile, drone_tile))
+        .collect();
+    stands.sort_by_key(|&stand| distance_squared(stand, drone_tile));
+
+    stands
+        .into_iter()
+        .find_map(|stand| chunk_cache.find_path(drone_tile, stand).ok())
+}
+
+fn can_dig_from(
+    chunk_cache: &ChunkCache,
+    stand: WorldCoord,
+    tile: WorldCoord,
+    drone_tile: WorldCoord,
+) -> bool {
+    let open = stand == drone_tile
+        || chunk_cache
+            .block_at_world(stand)
+            .is_some_and(|block| !BlockRegistry::global().is_solid(block));
+    open && validate_dig_target(chunk_cache, stand, tile).is_ok()
+}
+
+fn stand_tiles(tile: WorldCoord) -> impl Iterator<Item = WorldCoord> {
+    STAND_OFFSETS
+        .into_iter()
+        .map(move |(dx, dy, dz)| WorldCoord::new(tile.x + dx, tile.y + dy, tile.z + dz))
+}
+
+fn distance_squared(a: WorldCoord, b: WorldCoord) -> i64 {
+    let dx = (a.x - b.x) as i64;
+    let dy = (a.y - b.y) as i64;
+    let dz = (a.z - b.z) as i64;
+    dx * dx + dy * dy + dz * dz
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, BEDROCK, BlockId, STONE};
+    use crate::chunk::Chunk;
+    use crate::chunk_cache::CachedChunk;
+    use crate::coordinates::{ChunkPosition, LocalBlockCoord};
+    use crate::order::OrderOutcome;
+
+    fn cache_with_blocks(blocks: &[(WorldCoord, BlockId)]) -> ChunkCache {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+        for &(coord, block) in blocks {
+            let local = LocalBlockCoord::new(coord.x as usize, coord.y as usize, coord.z as usize);
+            chunk.set_block(local, block).unwrap();
+        }
+        let mut cache = ChunkCache::new();
+        cache.insert_chunk(CachedChunk::from_chunk(&chunk));
+        cache
+    }
+
+    fn tile_box(min: (i32, i32, i32), max: (i32, i32, i32)) -> TileBox3 {
+        TileBox3::new(
+            WorldCoord::new(min.0, min.1, min.2),
+            WorldCoord::new(max.0, max.1, max.2),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn lists_only_diggable_tiles() {
+        let cache = cache_with_blocks(&[
+            (WorldCoord::new(0, 0, 0), STONE),
+            (WorldCoord::new(1, 0, 0), BEDROCK),
+        ]);
+
+        let job = MineBoxJob::new(JobId(0), tile_box((0, 0, 0), (2, 0, 0)), &cache);
+
+        let coords: Vec<WorldCoord> = job.tiles().iter().map(|tile| tile.coord).collect();
+        assert_eq!(coords, vec![WorldCoord::new(0, 0, 0)]);
+        assert_eq!(job.progress().pending, 1);
+    }
+
+    #[test]
+    fn hands_out_upper_level_first_then_nearest() {
+        let mut cache = cache_with_blocks(&[
+            (WorldCoord::new(3, 0, 1), STONE),
+            (WorldCoord::new(4, 0, 0), STONE),
+            (WorldCoord::new(5, 0, 0), STONE),
+        ]);
+        let mut job = MineBoxJob::new(JobId(0), tile_box((3, 0, 0), (5, 0, 1)), &cache);
+        let drone_tile = WorldCoord::new(8, 0, 0);
+
+        let first = job
+            .next_assignment(&mut cache, 0, drone_tile, |_| true)
+            .unwrap();
+        assert_eq!(first.tile, WorldCoord::new(3, 0, 1));
+
+        let second = job
+            .next_assignment(&mut cache, 1, drone_tile, |_| true)
+            .unwrap();
+        assert_eq!(second.tile, WorldCoord::new(5, 0, 0));
+        assert_eq!(second.route, vec![WorldCoord::new(6, 0, 0)]);
+
+        assert_eq!(job.tile_assigned_to(1), Some(WorldCoord::new(5, 0, 0)));
+        assert_eq!(job.progress().working, 2);
+    }
+
+    #[test]
+    fn events_update_tile_states() {
+        let tile = WorldCoord::new(2, 0, 0);
+        let mut cache = cache_with_blocks(&[(tile, STONE)]);
+        let mut job = MineBoxJob::new(JobId(0), tile_box((2, 0, 0), (2, 0, 0)), &cache);
+
+        job.next_assignment(&mut cache, 0, WorldCoord::new(1, 0, 0), |_| true)
+            .unwrap();
+        job.apply_event(
+            0,
+            &OrderEvent::Progressed {
+                drone: 0,
+                percent: 40,
+            },
+        );
+        assert_eq!(
+            job.tiles()[0].state,
+            MineTileState::Working {
+                drone_index: 0,
+                percent: 40
+            }
+        );
+
+        job.release(0);
+        assert_eq!(job.tiles()[0].state, MineTileState::Pending);
+
+        job.next_assignment(&mut cache, 0, WorldCoord::new(1, 0, 0), |_| true)
+            .unwrap();
+        job.apply_event(
+            0,
+            &OrderEvent::Completed {
+                drone: 0,
+                outcome: OrderOutcome::Dug {
+                    tile,
+                    block: STONE,
+                    collected: false,
+                },
+            },
+        );
+        assert_eq!(job.tiles()[0].state, MineTileState::Dug);
+        assert_eq!(job.dropped_blocks(), 1);
+        assert!(job.is_finished());
+    }
+}
@@ -1,24 +1,87 @@
 pub mod block;
 pub mod chunk;
 pub mod chunk_cache;
+pub mod combat;
 pub mod coordinates;
 pub mod drone;
+pub mod enemy;
 pub mod inventory;
+pub mod item;
+pub mod job;
 pub mod linecast;
+pub mod map_import;
+pub mod order;
+pub mod pathfind;
+pub mod recipe;
+pub mod resources;
+pub mod save;
+pub mod scheduler;
+pub mod script;
 pub mod storage;
+pub mod structure;
+#[cfg(test)]
+mod test_support;
 pub mod tool;
+pub mod warrior;
+pub mod wave;
 pub mod world;
 pub mod worldgen;
 
-pub use block::{is_placable_block, AIR, BEDROCK, Block, BlockId, CORE, DIRT, IRON, STONE};
+pub use block::{
+    AIR, BEDROCK, Block, BlockDefinition, BlockId, BlockRegistry, BlockRegistryError,
+    BlockRegistryErrorReason, CORE, DIRT, IRON, STONE, WARRIOR_SPOT, is_placable_block,
+};
 pub use chunk::{CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH, Chunk, ChunkBlocks, ChunkError};
-pub use chunk_cache::{CachedChunk, ChunkCache};
-pub use coordinates::{ChunkPosition, LocalBlockCoord, WorldCoord};
-pub use drone::DronePose;
-pub use inventory::{INVENTORY_SLOTS, InventorySlot, InventorySlots, MAX_INVENTORY_UNITS};
+#[cfg(not(target_arch = "wasm32"))]
+pub use chunk_cache::ChunkWorkers;
+pub use chunk_cache::{CachedChunk, ChunkCache, PackedChunk};
+pub use combat::{Attack, CombatEvent, Combatant, Cooldown, ItemDrop, mitigated_damage};
+pub use coordinates::{ChunkPosition, LocalBlockCoord, TileBox3, WorldCoord};
+pub use drone::{BASE_MINING_SPEED, DroneId, DronePose};
+pub use enemy::{Enemy, EnemyEvent, EnemyId, EnemyStats, REPATH_INTERVAL_TICKS};
+pub use inventory::{
+    INVENTORY_SLOTS, InventoryError, InventoryErrorReason, InventorySlot, InventorySlots,
+};
+pub use item::{
+    DEFAULT_STACK_SIZE, ITEM_CORE, ITEM_GEAR, ITEM_IRON_ORE, ITEM_IRON_PLATE, ITEM_STONE,
+    ItemDefinition, ItemId, ItemKind, ItemRegistry, ItemRegistryError, ItemRegistryErrorReason,
+    block_for_item, item_for_block,
+};
+pub use job::{JobId, MineAssignment, MineBoxJob, MineProgress, MineTile, MineTileState};
+pub use map_import::{
+    ASCII_LEGEND, ImportedMap, MapImportError, MapImportErrorReason, block_for_symbol,
+};
+pub use order::{
+    CraftOrder, DigOrder, MoveOrder, Order, OrderError, OrderErrorReason, OrderEvent, OrderOutcome,
+    dig_duration,
+};
+pub use pathfind::{PathError, PathErrorReason};
+pub use recipe::{
+    CraftError, CraftErrorReason, ItemStack, RECIPE_CORE, RECIPE_GEAR, RECIPE_IRON_PLATE, Recipe,
+    RecipeId, RecipeRegistry, RecipeRegistryError, RecipeRegistryErrorReason,
+};
+pub use resources::ResourcePool;
+pub use save::{
+    DroneSave, OrderSave, SAVE_VERSION, SaveError, SaveErrorReason, SaveMigration, WorldSave,
+    migrate,
+};
+pub use scheduler::{DroneStatus, Scheduler};
+pub use script::{
+    HostContext, HostRegistry, Program, ScriptError, ScriptErrorReason, ScriptType, Value,
+    execute_program, parse_program, validate_program,
+};
 pub use storage::{LoadBlocksFn, SaveBlocksFn, StorageError};
+pub use structure::{CORE_MAX_HP, CoreStructure, GamePhase, StructureEvent};
 pub use tool::{
     PlacementError, PlacementErrorReason, PlacementOutcome, ToolController, ToolSelection,
 };
-pub use world::World;
-pub use worldgen::DeterministicMap;
+pub use warrior::{
+    WARRIOR_ATTACK, WARRIOR_COST_ITEM, WARRIOR_IRON_COST, Warrior, WarriorEvent, WarriorId,
+    WarriorPlacementError, WarriorPlacementErrorReason,
+};
+pub use wave::{
+    EnemyKind, EnemySpawn, WaveDefinition, WaveEvent, WaveGroup, WaveSchedule, WaveScheduleError,
+    WaveScheduleErrorReason, Waves,
+};
+pub use world::{NextIds, SECONDS_PER_TICK, TICKS_PER_SECOND, World};
+pub use worldgen::{DeterministicMap, FlatGenerator, WorldGenerator};
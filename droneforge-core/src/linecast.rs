@@ -1,8 +1,8 @@
-use crate::block::{AIR, BlockId};
+use crate::block::{BlockId, BlockRegistry};
 use crate::coordinates::WorldCoord;
 
 fn is_solid(block: Option<BlockId>) -> bool {
-    block.map_or(false, |b| b != AIR)
+    block.is_some_and(|b| BlockRegistry::global().is_solid(b))
 }
 
 /// Returns the first solid block encountered along the line from `start` to `end`,
@@ -0,0 +1,273 @@
+use crate::block::{AIR, BEDROCK, BlockId, CORE, DIRT, IRON, STONE};
+use crate::chunk::{Chunk, ChunkBlocks};
+use crate::chunk_cache::chunk_and_local_for_world_coord;
+use crate::coordinates::{ChunkPosition, WorldCoord};
+use crate::worldgen::WorldGenerator;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// Symbols understood by [`ImportedMap::from_ascii`].
+pub const ASCII_LEGEND: &[(char, BlockId)] = &[
+    ('.', AIR),
+    ('D', DIRT),
+    ('#', STONE),
+    ('I', IRON),
+    ('B', BEDROCK),
+    ('C', CORE),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapImportErrorReason {
+    UnknownSymbol(char),
+    UnknownColor([u8; 4]),
+    MissingLayerHeader,
+    InvalidLayerHeader,
+    DuplicateLayer(i32),
+    Image(String),
+}
+
+/// A map that could not be imported. `line` and `column` are 1-based; for images they are the
+/// pixel row and column. Both are 0 when the error is not tied to a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapImportError {
+    pub reason: MapImportErrorReason,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl MapImportError {
+    pub fn new(reason: MapImportErrorReason, line: usize, column: usize) -> Self {
+        Self {
+            reason,
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            MapImportErrorReason::UnknownSymbol(_) => "unknown map symbol",
+            MapImportErrorReason::UnknownColor(_) => "unknown map colour",
+            MapImportErrorReason::MissingLayerHeader => "map rows must follow a `z=` header",
+            MapImportErrorReason::InvalidLayerHeader => "invalid layer header",
+            MapImportErrorReason::DuplicateLayer(_) => "layer is defined twice",
+            MapImportErrorReason::Image(_) => "failed to read map image",
+        }
+    }
+}
+
+impl fmt::Display for MapImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            MapImportErrorReason::UnknownSymbol(symbol) => {
+                write!(f, "{} `{symbol}`", self.message())?
+            }
+            MapImportErrorReason::UnknownColor([r, g, b, a]) => {
+                write!(f, "{} ({r}, {g}, {b}, {a})", self.message())?
+            }
+            MapImportErrorReason::DuplicateLayer(z) => write!(f, "{} (z={z})", self.message())?,
+            MapImportErrorReason::Image(detail) => write!(f, "{} ({detail})", self.message())?,
+            _ => f.write_str(self.message())?,
+        }
+        if self.line > 0 {
+            write!(f, " at line {}, column {}", self.line, self.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for MapImportError {}
+
+/// A fixed, hand-authored map. Everything outside the authored blocks is air.
+///
+/// It can seed a [`ChunkCache`](crate::ChunkCache) directly through `to_chunk_blocks`, or act
+/// as the world's generator so chunks stream in like procedural terrain.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedMap {
+    chunks: HashMap<ChunkPosition, Chunk>,
+}
+
+impl ImportedMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses ASCII layers: a `z=<level>` header followed by one row of symbols per `y`, one
+    /// symbol per `x`, starting at `(0, 0)`. Lines starting with `//` and blank lines are
+    /// skipped. See [`ASCII_LEGEND`] for the symbols.
+    pub fn from_ascii(source: &str) -> Result<Self, MapImportError> {
+        let mut map = Self::new();
+        let mut seen_layers = HashSet::new();
+        let mut layer: Option<(i32, i32)> = None;
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let row = line.trim_end();
+            if row.trim_start().is_empty() || row.trim_start().starts_with("//") {
+                continue;
+            }
+
+            if let Some(level) = row.strip_prefix("z=") {
+                let z = level.trim().parse::<i32>().map_err(|_| {
+                    MapImportError::new(MapImportErrorReason::InvalidLayerHeader, line_number, 1)
+                })?;
+                if !seen_layers.insert(z) {
+                    return Err(MapImportError::new(
+                        MapImportErrorReason::DuplicateLayer(z),
+                        line_number,
+                        1,
+                    ));
+                }
+                layer = Some((z, 0));
+                continue;
+            }
+
+            let Some((z, y)) = layer.as_mut() else {
+                return Err(MapImportError::new(
+                    MapImportErrorReason::MissingLayerHeader,
+                    line_number,
+                    1,
+                ));
+            };
+            for (x, symbol) in row.chars().enumerate() {
+                let block = block_for_symbol(symbol).ok_or_else(|| {
+                    MapImportError::new(
+                        MapImportErrorReason::UnknownSymbol(symbol),
+                        line_number,
+                        x + 1,
+                    )
+                })?;
+                map.set_block(WorldCoord::new(x as i32, *y, *z), block);
+            }
+            *y += 1;
+        }
+
+        Ok(map)
+    }
+
+    pub fn set_block(&mut self, coord: WorldCoord, block: BlockId) {
+        let (position, local) = chunk_and_local_for_world_coord(coord);
+        if block == AIR && !self.chunks.contains_key(&position) {
+            return;
+        }
+        self.chunks
+            .entry(position)
+            .or_insert_with(|| Chunk::new(position, AIR))
+            .set_block(local, block)
+            .expect("Local coordinate must be in chunk bounds");
+    }
+
+    /// Every chunk holding an authored block, ordered by position.
+    pub fn to_chunk_blocks(&self) -> Vec<ChunkBlocks> {
+        let ordered: BTreeMap<_, _> = self
+            .chunks
+            .iter()
+            .map(|(position, chunk)| ((position.x, position.y, position.z), chunk))
+            .collect();
+        ordered.into_values().map(Chunk::to_block_save).collect()
+    }
+}
+
+impl WorldGenerator for ImportedMap {
+    fn block_at(&self, coord: WorldCoord) -> BlockId {
+        let (position, local) = chunk_and_local_for_world_coord(coord);
+        self.chunks
+            .get(&position)
+            .and_then(|chunk| chunk.get_block(local).ok())
+            .unwrap_or(AIR)
+    }
+
+    fn populate_chunk(&self, chunk: &mut Chunk, position: ChunkPosition) {
+        match self.chunks.get(&position) {
+            Some(authored) => chunk.clone_from(authored),
+            None => *chunk = Chunk::new(position, AIR),
+        }
+    }
+}
+
+pub fn block_for_symbol(symbol: char) -> Option<BlockId> {
+    ASCII_LEGEND
+        .iter()
+        .find(|(candidate, _)| *candidate == symbol)
+        .map(|(_, block)| *block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_cache::{CachedChunk, ChunkCache};
+    use crate::world::World;
+
+    const ARENA: &str = "\
+// A walled arena with a core on the floor.
+z=0
+#####
+#.C.#
+#####
+
+z=-1
+DDDDD
+DIIID
+";
+
+    #[test]
+    fn parses_ascii_layers() {
+        let map = ImportedMap::from_ascii(ARENA).unwrap();
+
+        assert_eq!(map.block_at(WorldCoord::new(0, 0, 0)), STONE);
+        assert_eq!(map.block_at(WorldCoord::new(1, 1, 0)), AIR);
+        assert_eq!(map.block_at(WorldCoord::new(2, 1, 0)), CORE);
+        assert_eq!(map.block_at(WorldCoord::new(2, 1, -1)), IRON);
+        assert_eq!(map.block_at(WorldCoord::new(4, 0, -1)), DIRT);
+        assert_eq!(map.block_at(WorldCoord::new(9, 9, 0)), AIR);
+        assert_eq!(map.block_at(WorldCoord::new(0, 2, -1)), AIR);
+    }
+
+    #[test]
+    fn chunk_blocks_load_into_a_cache_and_a_world() {
+        let map = ImportedMap::from_ascii(ARENA).unwrap();
+        let chunks = map.to_chunk_blocks();
+        let positions: Vec<ChunkPosition> = chunks.iter().map(|chunk| chunk.position).collect();
+        assert_eq!(
+            positions,
+            vec![ChunkPosition::new(0, 0, -1), ChunkPosition::new(0, 0, 0)]
+        );
+
+        let mut cache = ChunkCache::new();
+        for chunk in &chunks {
+            cache.insert_chunk(CachedChunk::from_block_save(chunk).unwrap());
+        }
+        assert_eq!(cache.block_at_world(WorldCoord::new(2, 1, 0)), Some(CORE));
+
+        let world = World::with_generator(map);
+        assert_eq!(world.block_at(WorldCoord::new(3, 1, -1)), Some(IRON));
+        assert_eq!(world.block_at(WorldCoord::new(-40, 0, 0)), Some(AIR));
+    }
+
+    #[test]
+    fn reports_unknown_symbols_with_position() {
+        let err = ImportedMap::from_ascii("z=0\n###\n#?#\n").unwrap_err();
+
+        assert_eq!(err.reason, MapImportErrorReason::UnknownSymbol('?'));
+        assert_eq!((err.line, err.column), (3, 2));
+        assert_eq!(
+            err.to_string(),
+            "unknown map symbol `?` at line 3, column 2"
+        );
+    }
+
+    #[test]
+    fn rejects_rows_without_a_layer_and_bad_headers() {
+        let err = ImportedMap::from_ascii("// arena\n###\n").unwrap_err();
+        assert_eq!(err.reason, MapImportErrorReason::MissingLayerHeader);
+        assert_eq!(err.line, 2);
+
+        let err = ImportedMap::from_ascii("z=top\n").unwrap_err();
+        assert_eq!(err.reason, MapImportErrorReason::InvalidLayerHeader);
+
+        let err = ImportedMap::from_ascii("z=1\n#\nz=1\n#\n").unwrap_err();
+        assert_eq!(err.reason, MapImportErrorReason::DuplicateLayer(1));
+        assert_eq!(err.line, 3);
+    }
+}
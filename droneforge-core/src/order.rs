@@ -0,0 +1,717 @@
+use crate::block::{BlockId, BlockRegistry};
+use crate::chunk::ChunkError;
+use crate::chunk_cache::ChunkCache;
+use crate::coordinates::WorldCoord;
+use crate::drone::{DroneId, DronePose};
+use crate::pathfind::PathError;
+use crate::recipe::{CraftError, RecipeId, RecipeRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+pub const DRONE_MOVE_SPEED: f32 = 4.3; // tiles per second
+const MIN_MINING_SPEED: f32 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveOrder {
+    pub target_tile: WorldCoord,
+    waypoints: VecDeque<WorldCoord>,
+    leg_position: [f32; 3],
+    direction: [f32; 3],
+    delta_per_sec: [f32; 3],
+}
+
+impl MoveOrder {
+    /// Returns `None` when the drone already sits on `target_tile`.
+    pub fn for_target(current: [f32; 3], target_tile: WorldCoord) -> Option<Self> {
+        Self::along_path(current, vec![target_tile])
+    }
+
+    /// Builds an order that visits each waypoint in turn, typically the output of
+    /// `ChunkCache::find_path`. Returns `None` when there is nowhere left to go.
+    pub fn along_path(current: [f32; 3], waypoints: Vec<WorldCoord>) -> Option<Self> {
+        let target_tile = *waypoints.last()?;
+        let mut order = Self {
+            target_tile,
+            waypoints: waypoints.into(),
+            leg_position: current,
+            direction: [0.0; 3],
+            delta_per_sec: [0.0; 3],
+        };
+        order.start_next_leg(current).then_some(order)
+    }
+
+    /// Plans a route from the drone's tile to `target_tile`; `Ok(None)` means it is already there.
+    pub fn plan(
+        chunk_cache: &mut ChunkCache,
+        drone: &DronePose,
+        target_tile: WorldCoord,
+    ) -> Result<Option<Self>, PathError> {
+        let waypoints = chunk_cache.find_path(drone.tile(), target_tile)?;
+        Ok(Self::along_path(drone.position, waypoints))
+    }
+
+    pub fn direction(&self) -> [f32; 3] {
+        self.direction
+    }
+
+    /// The tile the drone enters next on its current leg when it stands at `position`.
+    pub fn next_tile(&self, position: [f32; 3]) -> WorldCoord {
+        let axis = |value: f32, direction: f32| {
+            if direction > f32::EPSILON {
+                value.floor() as i32 + 1
+            } else if direction < -f32::EPSILON {
+                value.ceil() as i32 - 1
+            } else {
+                value.round() as i32
+            }
+        };
+        WorldCoord::new(
+            axis(position[0], self.direction[0]),
+            axis(position[1], self.direction[1]),
+            axis(position[2], self.direction[2]),
+        )
+    }
+
+    /// The waypoint currently being approached followed by the ones after it.
+    pub fn remaining_waypoints(&self) -> impl Iterator<Item = WorldCoord> + '_ {
+        self.waypoints.iter().copied()
+    }
+
+    pub fn status_text(&self) -> String {
+        format!(
+            "moving to tile {}, {}, {} (d/s {:.2}, {:.2}, {:.2})",
+            self.target_tile.x,
+            self.target_tile.y,
+            self.target_tile.z,
+            self.delta_per_sec[0],
+            self.delta_per_sec[1],
+            self.delta_per_sec[2]
+        )
+    }
+
+    /// Moves the drone along its waypoints and reports whether it reached the last one.
+    /// Distance left over after reaching a waypoint carries into the next leg.
+    pub fn advance(&mut self, drone: &mut DronePose, delta_seconds: f32) -> bool {
+        let mut budget = DRONE_MOVE_SPEED * delta_seconds;
+
+        loop {
+            drone.face_towards(self.direction[0], self.direction[1]);
+            let remaining = [
+                self.leg_position[0] - drone.position[0],
+                self.leg_position[1] - drone.position[1],
+                self.leg_position[2] - drone.position[2],
+            ];
+            let remaining_distance = length(remaining);
+
+            if budget < remaining_distance {
+                for (axis, direction) in drone.position.iter_mut().zip(self.direction) {
+                    *axis += direction * budget;
+                }
+                return false;
+            }
+
+            drone.position = self.leg_position;
+            budget -= remaining_distance;
+            self.waypoints.pop_front();
+            if !self.start_next_leg(drone.position) {
+                return true;
+            }
+        }
+    }
+
+    /// Points the order at the next waypoint that differs from `current`.
+    fn start_next_leg(&mut self, current: [f32; 3]) -> bool {
+        while let Some(&waypoint) = self.waypoints.front() {
+            let leg_position = [waypoint.x as f32, waypoint.y as f32, waypoint.z as f32];
+            let offset = [
+                leg_position[0] - current[0],
+                leg_position[1] - current[1],
+                leg_position[2] - current[2],
+            ];
+            let distance = length(offset);
+            if distance <= f32::EPSILON {
+                self.waypoints.pop_front();
+                continue;
+            }
+
+            self.leg_position = leg_position;
+            self.direction = offset.map(|axis| axis / distance);
+            self.delta_per_sec = self.direction.map(|axis| axis * DRONE_MOVE_SPEED);
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigOrder {
+    pub target_tile: WorldCoord,
+    elapsed: f32,
+    /// Unknown until the drone starts digging, since it depends on the block and the drone.
+    duration: Option<f32>,
+}
+
+impl DigOrder {
+    pub fn new(target_tile: WorldCoord) -> Self {
+        Self {
+            target_tile,
+            elapsed: 0.0,
+            duration: None,
+        }
+    }
+
+    /// Restores a dig that had already run for `elapsed` seconds.
+    pub fn resume(target_tile: WorldCoord, elapsed: f32) -> Self {
+        let mut order = Self::new(target_tile);
+        order.elapsed = elapsed.max(0.0);
+        order
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn duration(&self) -> Option<f32> {
+        self.duration
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.duration.is_some()
+    }
+
+    /// Fixes how long the dig takes. Later calls are ignored so a dig in progress keeps its
+    /// pace.
+    pub fn start(&mut self, duration: f32) {
+        if self.duration.is_none() {
+            let duration = duration.max(0.0);
+            self.duration = Some(duration);
+            self.elapsed = self.elapsed.min(duration);
+        }
+    }
+
+    pub fn progress_fraction(&self) -> f32 {
+        match self.duration {
+            None => 0.0,
+            Some(duration) if duration <= 0.0 => 1.0,
+            Some(duration) => (self.elapsed / duration).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Progress rounded down to 5% steps so the UI only refreshes on visible changes.
+    pub fn progress_percent(&self) -> u32 {
+        if self
+            .duration
+            .is_some_and(|duration| self.elapsed >= duration)
+        {
+            return 100;
+        }
+        let stepped = ((self.progress_fraction() * 100.0) / 5.0).floor() as u32 * 5;
+        stepped.min(100)
+    }
+
+    pub fn status_text(&self) -> String {
+        format!(
+            "digging wall at {}, {}, {} ({}%)",
+            self.target_tile.x,
+            self.target_tile.y,
+            self.target_tile.z,
+            self.progress_percent()
+        )
+    }
+
+    /// Returns `(completed, progressed)`, where `progressed` means the reported percent changed.
+    /// A dig that has not been started makes no progress.
+    pub fn advance(&mut self, delta_seconds: f32) -> (bool, bool) {
+        let Some(duration) = self.duration else {
+            return (false, false);
+        };
+        let before = self.progress_percent();
+        self.elapsed = (self.elapsed + delta_seconds).min(duration);
+        let after = self.progress_percent();
+        let progressed = after != before;
+        let completed = self.elapsed >= duration - f32::EPSILON;
+        (completed, progressed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CraftOrder {
+    pub recipe: RecipeId,
+    /// The tile the drone crafts on; a station has to stay next to it.
+    pub tile: WorldCoord,
+    elapsed: f32,
+}
+
+impl CraftOrder {
+    pub fn new(recipe: RecipeId, tile: WorldCoord) -> Self {
+        Self {
+            recipe,
+            tile,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Restores a craft that had already run for `elapsed` seconds.
+    pub fn resume(recipe: RecipeId, tile: WorldCoord, elapsed: f32) -> Self {
+        let mut order = Self::new(recipe, tile);
+        order.elapsed = elapsed.max(0.0);
+        order
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Seconds the recipe takes; unknown recipes finish at once and fail when applied.
+    pub fn duration(&self) -> f32 {
+        RecipeRegistry::global()
+            .get(self.recipe)
+            .map_or(0.0, |recipe| recipe.craft_seconds.max(0.0))
+    }
+
+    /// Progress rounded down to 5% steps, like [`DigOrder::progress_percent`].
+    pub fn progress_percent(&self) -> u32 {
+        let duration = self.duration();
+        if self.elapsed >= duration {
+            return 100;
+        }
+        let fraction = (self.elapsed / duration).clamp(0.0, 1.0);
+        let stepped = ((fraction * 100.0) / 5.0).floor() as u32 * 5;
+        stepped.min(100)
+    }
+
+    pub fn status_text(&self) -> String {
+        let name = RecipeRegistry::global()
+            .get(self.recipe)
+            .map_or("unknown recipe", |recipe| recipe.name.as_str());
+        format!("crafting {} ({}%)", name, self.progress_percent())
+    }
+
+    /// Returns `(completed, progressed)` with the same meaning as [`DigOrder::advance`].
+    pub fn advance(&mut self, delta_seconds: f32) -> (bool, bool) {
+        let duration = self.duration();
+        let before = self.progress_percent();
+        self.elapsed = (self.elapsed + delta_seconds).min(duration);
+        let after = self.progress_percent();
+        let completed = self.elapsed >= duration - f32::EPSILON;
+        (completed, after != before)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Order {
+    Move(MoveOrder),
+    Dig(DigOrder),
+    Craft(CraftOrder),
+}
+
+impl Order {
+    pub fn status_text(&self) -> String {
+        match self {
+            Order::Move(order) => order.status_text(),
+            Order::Dig(order) => order.status_text(),
+            Order::Craft(order) => order.status_text(),
+        }
+    }
+
+    pub fn progress_percent(&self) -> Option<u32> {
+        match self {
+            Order::Move(_) => None,
+            Order::Dig(order) => Some(order.progress_percent()),
+            Order::Craft(order) => Some(order.progress_percent()),
+        }
+    }
+
+    pub fn target_tile(&self) -> WorldCoord {
+        match self {
+            Order::Move(order) => order.target_tile,
+            Order::Dig(order) => order.target_tile,
+            Order::Craft(order) => order.tile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderErrorReason {
+    UnknownDrone,
+    UnknownJob,
+    TileReserved,
+    PathBlocked,
+    DifferentLevel,
+    TooFar,
+    NothingToDig,
+    Undiggable,
+    DiagonalEnclosed,
+    TargetUnloaded,
+    Chunk(ChunkError),
+    Craft(CraftError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderError {
+    pub reason: OrderErrorReason,
+}
+
+impl OrderError {
+    pub fn new(reason: OrderErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            OrderErrorReason::UnknownDrone => "drone does not exist",
+            OrderErrorReason::UnknownJob => "job does not exist",
+            OrderErrorReason::TileReserved => "another drone is already digging there",
+            OrderErrorReason::PathBlocked => "path blocked",
+            OrderErrorReason::DifferentLevel => "can only dig straight up or down between levels",
+            OrderErrorReason::TooFar => "target must be adjacent to the drone",
+            OrderErrorReason::NothingToDig => "no wall to dig here",
+            OrderErrorReason::Undiggable => "this block cannot be dug",
+            OrderErrorReason::DiagonalEnclosed => "diagonal walls must touch air to dig safely",
+            OrderErrorReason::TargetUnloaded => "target tile is not loaded",
+            OrderErrorReason::Chunk(_) => "failed to save dug block",
+            OrderErrorReason::Craft(ref err) => err.message(),
+        }
+    }
+}
+
+impl From<ChunkError> for OrderError {
+    fn from(reason: ChunkError) -> Self {
+        Self {
+            reason: OrderErrorReason::Chunk(reason),
+        }
+    }
+}
+
+impl From<CraftError> for OrderError {
+    fn from(reason: CraftError) -> Self {
+        Self {
+            reason: OrderErrorReason::Craft(reason),
+        }
+    }
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            OrderErrorReason::Chunk(err) => {
+                write!(f, "{} ({:?})", self.message(), err)
+            }
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for OrderError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderOutcome {
+    Arrived {
+        tile: WorldCoord,
+    },
+    Dug {
+        tile: WorldCoord,
+        block: BlockId,
+        collected: bool,
+    },
+    Crafted {
+        recipe: RecipeId,
+    },
+}
+
+/// What became of a drone's current order. Drones are named by id, since a death later in
+/// the same tick shifts the indices of the drones behind it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderEvent {
+    Progressed {
+        drone: DroneId,
+        percent: u32,
+    },
+    Completed {
+        drone: DroneId,
+        outcome: OrderOutcome,
+    },
+    Failed {
+        drone: DroneId,
+        target: WorldCoord,
+        error: OrderError,
+    },
+}
+
+impl OrderEvent {
+    pub fn drone(&self) -> DroneId {
+        match self {
+            OrderEvent::Progressed { drone, .. }
+            | OrderEvent::Completed { drone, .. }
+            | OrderEvent::Failed { drone, .. } => *drone,
+        }
+    }
+
+    pub fn status_text(&self) -> Option<String> {
+        match self {
+            OrderEvent::Progressed { .. } => None,
+            OrderEvent::Completed {
+                outcome: OrderOutcome::Arrived { tile },
+                ..
+            } => Some(format!("arrived at {}, {}, {}", tile.x, tile.y, tile.z)),
+            OrderEvent::Completed {
+                outcome: OrderOutcome::Dug { tile, .. },
+                ..
+            } => Some(format!(
+                "finished digging at {}, {}, {}",
+                tile.x, tile.y, tile.z
+            )),
+            OrderEvent::Completed {
+                outcome: OrderOutcome::Crafted { recipe },
+                ..
+            } => Some(format!(
+                "crafted {}",
+                RecipeRegistry::global()
+                    .get(*recipe)
+                    .map_or("unknown recipe", |recipe| recipe.name.as_str())
+            )),
+            OrderEvent::Failed { error, .. } => Some(error.to_string()),
+        }
+    }
+}
+
+/// Seconds `drone` needs to dig out `block`: the block's dig time over the drone's mining
+/// speed.
+pub fn dig_duration(drone: &DronePose, block: BlockId) -> f32 {
+    BlockRegistry::global().dig_seconds(block) / drone.mining_speed.max(MIN_MINING_SPEED)
+}
+
+/// Blocks that leave something for the digging drone's inventory; everything else is
+/// discarded.
+pub fn collects_block(block: BlockId) -> bool {
+    BlockRegistry::global().drop(block).is_some()
+}
+
+/// Checks whether a drone standing on `drone_tile` may dig `target_tile`: one of the eight
+/// neighbours on its level, or the tile directly above or below it.
+pub fn validate_dig_target(
+    chunk_cache: &ChunkCache,
+    drone_tile: WorldCoord,
+    target_tile: WorldCoord,
+) -> Result<(), OrderError> {
+    let dx = target_tile.x - drone_tile.x;
+    let dy = target_tile.y - drone_tile.y;
+    let dz = target_tile.z - drone_tile.z;
+    if dz != 0 {
+        if dx != 0 || dy != 0 {
+            return Err(OrderError::new(OrderErrorReason::DifferentLevel));
+        }
+        if dz.abs() > 1 {
+            return Err(OrderError::new(OrderErrorReason::TooFar));
+        }
+        // Shafts open straight above or below the drone and are never diagonal.
+        return validate_diggable_block(chunk_cache, target_tile, false);
+    }
+
+    let adjacent = dx.abs() <= 1 && dy.abs() <= 1 && !(dx == 0 && dy == 0);
+    if !adjacent {
+        return Err(OrderError::new(OrderErrorReason::TooFar));
+    }
+
+    validate_diggable_block(chunk_cache, target_tile, dx != 0 && dy != 0)
+}
+
+/// Applies the per-block dig rules: the tile must hold a diggable wall and,
+/// when approached diagonally, must touch air so the drone never digs into a sealed corner.
+pub fn validate_diggable_block(
+    chunk_cache: &ChunkCache,
+    target_tile: WorldCoord,
+    diagonal: bool,
+) -> Result<(), OrderError> {
+    let registry = BlockRegistry::global();
+    let target_block = chunk_cache
+        .block_at_world(target_tile)
+        .filter(|&block| registry.is_solid(block));
+    match target_block {
+        None => Err(OrderError::new(OrderErrorReason::NothingToDig)),
+        Some(block) if !registry.is_diggable(block) => {
+            Err(OrderError::new(OrderErrorReason::Undiggable))
+        }
+        Some(_) if diagonal && !wall_adjacent_to_air(chunk_cache, target_tile) => {
+            Err(OrderError::new(OrderErrorReason::DiagonalEnclosed))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+pub fn wall_adjacent_to_air(chunk_cache: &ChunkCache, tile: WorldCoord) -> bool {
+    let WorldCoord { x, y, z } = tile;
+    let neighbours = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)];
+    neighbours.iter().any(|&(nx, ny)| {
+        chunk_cache
+            .block_at_world(WorldCoord::new(nx, ny, z))
+            .is_none_or(|block| !BlockRegistry::global().is_solid(block))
+    })
+}
+
+fn length(vector: [f32; 3]) -> f32 {
+    vector.iter().map(|axis| axis * axis).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, CORE, IRON, STONE};
+    use crate::chunk::Chunk;
+    use crate::chunk_cache::CachedChunk;
+    use crate::coordinates::{ChunkPosition, LocalBlockCoord};
+
+    fn stone_chunk_with_air(air: &[(usize, usize)]) -> ChunkCache {
+        let position = ChunkPosition::new(0, 0, 0);
+        let mut chunk = Chunk::new(position, STONE);
+        for &(x, y) in air {
+            chunk.set_block(LocalBlockCoord::new(x, y, 0), AIR).unwrap();
+        }
+        let mut cache = ChunkCache::new();
+        cache.insert_chunk(CachedChunk::from_chunk(&chunk));
+        cache
+    }
+
+    #[test]
+    fn dig_order_reports_progress_in_steps() {
+        let mut order = DigOrder::new(WorldCoord::new(0, 0, 0));
+        assert_eq!(order.advance(1.0), (false, false));
+        order.start(5.0);
+
+        assert_eq!(order.progress_percent(), 0);
+        let (completed_first, progressed_first) = order.advance(0.2);
+        assert!(!completed_first);
+        assert!(!progressed_first);
+        assert_eq!(order.progress_percent(), 0);
+
+        let (completed_second, progressed_second) = order.advance(0.05);
+        assert!(!completed_second);
+        assert!(progressed_second);
+        assert_eq!(order.progress_percent(), 5);
+
+        let (completed_final, progressed_final) = order.advance(5.0);
+        assert!(completed_final);
+        assert!(progressed_final);
+        assert_eq!(order.progress_percent(), 100);
+    }
+
+    #[test]
+    fn dig_progress_follows_its_own_duration() {
+        let mut quick = DigOrder::new(WorldCoord::new(0, 0, 0));
+        quick.start(2.0);
+        quick.start(9.0);
+        let mut slow = DigOrder::resume(WorldCoord::new(1, 0, 0), 2.0);
+        slow.start(8.0);
+
+        assert_eq!(quick.duration(), Some(2.0));
+        assert_eq!(slow.progress_percent(), 25);
+        quick.advance(1.0);
+        assert_eq!(quick.progress_percent(), 50);
+        assert_eq!(quick.advance(1.0), (true, true));
+        assert_eq!(slow.advance(1.0), (false, true));
+        assert_eq!(slow.progress_percent(), 35);
+    }
+
+    #[test]
+    fn move_order_snaps_to_target_on_arrival() {
+        let mut drone = DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10);
+        let mut order = MoveOrder::for_target(drone.position, WorldCoord::new(0, 2, 0)).unwrap();
+
+        assert!(!order.advance(&mut drone, 0.25));
+        assert!(drone.position[1] > 0.0 && drone.position[1] < 2.0);
+        assert_eq!(drone.heading, [0.0, 1.0]);
+
+        assert!(order.advance(&mut drone, 1.0));
+        assert_eq!(drone.position, [0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn move_order_to_current_tile_is_empty() {
+        assert!(MoveOrder::for_target([3.0, 4.0, 0.0], WorldCoord::new(3, 4, 0)).is_none());
+    }
+
+    #[test]
+    fn next_tile_looks_ahead_along_the_leg() {
+        let order = MoveOrder::for_target([2.0, 2.0, 0.0], WorldCoord::new(0, 4, 0)).unwrap();
+
+        assert_eq!(order.next_tile([2.0, 2.0, 0.0]), WorldCoord::new(1, 3, 0));
+        assert_eq!(order.next_tile([1.4, 2.6, 0.0]), WorldCoord::new(1, 3, 0));
+        assert_eq!(order.next_tile([1.0, 3.0, 0.0]), WorldCoord::new(0, 4, 0));
+    }
+
+    #[test]
+    fn move_order_follows_waypoints_in_turn() {
+        let mut drone = DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10);
+        let waypoints = vec![
+            WorldCoord::new(0, 0, 0),
+            WorldCoord::new(2, 0, 0),
+            WorldCoord::new(2, 3, 0),
+        ];
+        let mut order = MoveOrder::along_path(drone.position, waypoints).unwrap();
+
+        assert_eq!(order.target_tile, WorldCoord::new(2, 3, 0));
+        assert_eq!(order.direction(), [1.0, 0.0, 0.0]);
+
+        // 4.3 tiles: two along x, the rest carries into the y leg.
+        assert!(!order.advance(&mut drone, 1.0));
+        assert_eq!(drone.position[0], 2.0);
+        assert!((drone.position[1] - 2.3).abs() < 1e-4);
+        assert_eq!(drone.heading, [0.0, 1.0]);
+        assert_eq!(order.remaining_waypoints().count(), 1);
+
+        assert!(order.advance(&mut drone, 1.0));
+        assert_eq!(drone.position, [2.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn validates_dig_targets_like_manual_digging() {
+        let cache = stone_chunk_with_air(&[(5, 5), (6, 5)]);
+        let drone = WorldCoord::new(5, 5, 0);
+
+        assert!(validate_dig_target(&cache, drone, WorldCoord::new(5, 6, 0)).is_ok());
+        assert_eq!(
+            validate_dig_target(&cache, drone, WorldCoord::new(6, 5, 0))
+                .unwrap_err()
+                .reason,
+            OrderErrorReason::NothingToDig
+        );
+        assert_eq!(
+            validate_dig_target(&cache, drone, WorldCoord::new(7, 5, 0))
+                .unwrap_err()
+                .reason,
+            OrderErrorReason::TooFar
+        );
+        assert_eq!(
+            validate_dig_target(&cache, drone, WorldCoord::new(5, 6, 1))
+                .unwrap_err()
+                .reason,
+            OrderErrorReason::DifferentLevel
+        );
+        assert!(validate_dig_target(&cache, drone, WorldCoord::new(5, 5, 1)).is_ok());
+        assert_eq!(
+            validate_dig_target(&cache, drone, WorldCoord::new(5, 5, 2))
+                .unwrap_err()
+                .reason,
+            OrderErrorReason::TooFar
+        );
+        // (4, 6) is surrounded by stone, (6, 6) touches the air at (6, 5).
+        assert_eq!(
+            validate_dig_target(&cache, drone, WorldCoord::new(4, 6, 0))
+                .unwrap_err()
+                .reason,
+            OrderErrorReason::DiagonalEnclosed
+        );
+        assert!(validate_dig_target(&cache, drone, WorldCoord::new(6, 6, 0)).is_ok());
+    }
+
+    #[test]
+    fn only_stone_iron_and_core_are_collected() {
+        assert!(collects_block(STONE));
+        assert!(collects_block(IRON));
+        assert!(collects_block(CORE));
+        assert!(!collects_block(crate::block::DIRT));
+        assert!(!collects_block(AIR));
+    }
+}
@@ -0,0 +1,452 @@
+use crate::block::{BlockId, BlockRegistry};
+use crate::coordinates::WorldCoord;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+/// Upper bound on expanded nodes so a click on a sealed-off tile cannot stall a frame.
+pub const MAX_EXPANDED_NODES: usize = 50_000;
+
+const ORTHOGONAL_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+const NEIGHBOUR_OFFSETS: [(i32, i32, i32); 10] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (1, 1, 0),
+    (1, -1, 0),
+    (-1, 1, 0),
+    (-1, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathErrorReason {
+    TargetBlocked(WorldCoord),
+    Unreachable,
+    SearchLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    pub reason: PathErrorReason,
+}
+
+impl PathError {
+    pub fn new(reason: PathErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            PathErrorReason::TargetBlocked(_) => "target tile is not empty",
+            PathErrorReason::Unreachable => "unreachable",
+            PathErrorReason::SearchLimit => "unreachable (search limit reached)",
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            PathErrorReason::TargetBlocked(coord) => {
+                write!(
+                    f,
+                    "blocked by wall at {}, {}, {}",
+                    coord.x, coord.y, coord.z
+                )
+            }
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+/// Finds a route from `start` to `goal` over loaded air tiles using A*. Each level is an
+/// 8-connected grid whose diagonal steps obey the same corner rule as
+/// `first_solid_supercover`: both orthogonal neighbours must be open. Levels connect
+/// straight up or down wherever both tiles are open, so dug shafts act as lifts between
+/// Z levels. The start tile itself is never checked, so a drone can always leave the tile
+/// it occupies.
+///
+/// Returns the waypoints after `start` up to and including `goal`, with straight runs
+/// collapsed to their end points. An empty list means the drone is already there.
+pub fn find_path<F>(
+    mut block_at_world: F,
+    start: WorldCoord,
+    goal: WorldCoord,
+) -> Result<Vec<WorldCoord>, PathError>
+where
+    F: FnMut(WorldCoord) -> Option<BlockId>,
+{
+    if start == goal {
+        return Ok(Vec::new());
+    }
+    if !is_open(block_at_world(goal)) {
+        return Err(PathError::new(PathErrorReason::TargetBlocked(goal)));
+    }
+
+    let tiles = search(
+        |coord| is_open(block_at_world(coord)).then_some(0),
+        start,
+        goal,
+        &NEIGHBOUR_OFFSETS,
+    )?;
+    Ok(compress(start, tiles))
+}
+
+/// Finds the cheapest route across `start`'s level when some tiles can be broken through.
+/// `extra_cost` gives what entering a tile costs on top of walking onto it, in the same
+/// units as one orthogonal step (10), or `None` when it cannot be entered at all. Diagonal
+/// steps need the target and both sides to cost nothing extra, so routes never cut a
+/// corner through a wall. `goal` itself is always enterable.
+///
+/// Returns every tile after `start` up to and including `goal`, without collapsing runs, so
+/// the caller can tell which ones have to be broken on the way.
+pub fn find_tunnelling_path<F>(
+    mut extra_cost: F,
+    start: WorldCoord,
+    goal: WorldCoord,
+) -> Result<Vec<WorldCoord>, PathError>
+where
+    F: FnMut(WorldCoord) -> Option<u32>,
+{
+    if start == goal {
+        return Ok(Vec::new());
+    }
+    if start.z != goal.z {
+        return Err(PathError::new(PathErrorReason::Unreachable));
+    }
+    search(
+        |coord| {
+            if coord == goal {
+                Some(0)
+            } else {
+                extra_cost(coord)
+            }
+        },
+        start,
+        goal,
+        &NEIGHBOUR_OFFSETS[..8],
+    )
+}
+
+fn search<F>(
+    mut extra_cost: F,
+    start: WorldCoord,
+    goal: WorldCoord,
+    offsets: &[(i32, i32, i32)],
+) -> Result<Vec<WorldCoord>, PathError>
+where
+    F: FnMut(WorldCoord) -> Option<u32>,
+{
+    let mut open = BinaryHeap::new();
+    let mut best_cost: HashMap<WorldCoord, u32> = HashMap::new();
+    let mut came_from: HashMap<WorldCoord, WorldCoord> = HashMap::new();
+    let mut sequence = 0u64;
+    let mut expanded = 0usize;
+
+    best_cost.insert(start, 0);
+    open.push(Reverse((
+        octile_distance(start, goal),
+        sequence,
+        key(start),
+    )));
+
+    while let Some(Reverse((_, _, (x, y, z)))) = open.pop() {
+        let current = WorldCoord::new(x, y, z);
+        if current == goal {
+            return Ok(reconstruct(&came_from, start, goal));
+        }
+
+        expanded += 1;
+        if expanded > MAX_EXPANDED_NODES {
+            return Err(PathError::new(PathErrorReason::SearchLimit));
+        }
+
+        let current_cost = best_cost[&current];
+        for &(dx, dy, dz) in offsets {
+            let next = WorldCoord::new(current.x + dx, current.y + dy, current.z + dz);
+            let Some(extra) = extra_cost(next) else {
+                continue;
+            };
+
+            let diagonal = dx != 0 && dy != 0;
+            if diagonal {
+                let side_x = WorldCoord::new(current.x + dx, current.y, current.z);
+                let side_y = WorldCoord::new(current.x, current.y + dy, current.z);
+                if extra != 0 || extra_cost(side_x) != Some(0) || extra_cost(side_y) != Some(0) {
+                    continue;
+                }
+            }
+
+            let step_cost = if diagonal {
+                DIAGONAL_COST
+            } else {
+                ORTHOGONAL_COST
+            };
+            let next_cost = current_cost + step_cost + extra;
+            if best_cost
+                .get(&next)
+                .is_some_and(|&known| known <= next_cost)
+            {
+                continue;
+            }
+
+            best_cost.insert(next, next_cost);
+            came_from.insert(next, current);
+            sequence += 1;
+            let estimate = next_cost + octile_distance(next, goal);
+            open.push(Reverse((estimate, sequence, key(next))));
+        }
+    }
+
+    Err(PathError::new(PathErrorReason::Unreachable))
+}
+
+fn key(coord: WorldCoord) -> (i32, i32, i32) {
+    (coord.x, coord.y, coord.z)
+}
+
+/// Whether a drone may pass through a tile holding `block`; unloaded tiles are never open.
+pub(crate) fn is_open(block: Option<BlockId>) -> bool {
+    block.is_some_and(|block| !BlockRegistry::global().is_solid(block))
+}
+
+fn octile_distance(a: WorldCoord, b: WorldCoord) -> u32 {
+    let dx = a.x.abs_diff(b.x);
+    let dy = a.y.abs_diff(b.y);
+    let diagonal = dx.min(dy);
+    let straight = dx.max(dy) - diagonal + a.z.abs_diff(b.z);
+    diagonal * DIAGONAL_COST + straight * ORTHOGONAL_COST
+}
+
+fn reconstruct(
+    came_from: &HashMap<WorldCoord, WorldCoord>,
+    start: WorldCoord,
+    goal: WorldCoord,
+) -> Vec<WorldCoord> {
+    let mut tiles = vec![goal];
+    let mut current = goal;
+    while let Some(&previous) = came_from.get(&current) {
+        if previous == start {
+            break;
+        }
+        tiles.push(previous);
+        current = previous;
+    }
+    tiles.reverse();
+    tiles
+}
+
+/// Drops tiles that continue in the same direction as the previous step.
+fn compress(start: WorldCoord, tiles: Vec<WorldCoord>) -> Vec<WorldCoord> {
+    let mut waypoints: Vec<WorldCoord> = Vec::with_capacity(tiles.len());
+    let mut previous = start;
+    let mut previous_step = None;
+
+    for tile in tiles {
+        let step = (
+            tile.x - previous.x,
+            tile.y - previous.y,
+            tile.z - previous.z,
+        );
+        if previous_step == Some(step) {
+            waypoints.pop();
+        }
+        waypoints.push(tile);
+        previous_step = Some(step);
+        previous = tile;
+    }
+
+    waypoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, STONE};
+    use std::collections::HashSet;
+
+    fn walls(coords: &[(i32, i32)]) -> impl FnMut(WorldCoord) -> Option<BlockId> {
+        let solid: HashSet<(i32, i32)> = coords.iter().copied().collect();
+        move |coord: WorldCoord| {
+            if coord.x.abs() > 20 || coord.y.abs() > 20 || coord.z != 0 {
+                return None;
+            }
+            Some(if solid.contains(&(coord.x, coord.y)) {
+                STONE
+            } else {
+                AIR
+            })
+        }
+    }
+
+    fn expand(start: WorldCoord, waypoints: &[WorldCoord]) -> Vec<WorldCoord> {
+        let mut tiles = Vec::new();
+        let mut current = start;
+        for &waypoint in waypoints {
+            while current != waypoint {
+                current = WorldCoord::new(
+                    current.x + (waypoint.x - current.x).signum(),
+                    current.y + (waypoint.y - current.y).signum(),
+                    current.z + (waypoint.z - current.z).signum(),
+                );
+                tiles.push(current);
+            }
+        }
+        tiles
+    }
+
+    #[test]
+    fn straight_line_collapses_to_goal() {
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(5, 0, 0);
+
+        let path = find_path(walls(&[]), start, goal).unwrap();
+
+        assert_eq!(path, vec![goal]);
+    }
+
+    #[test]
+    fn routes_around_a_wall() {
+        let wall: Vec<(i32, i32)> = (-2..=2).map(|y| (2, y)).collect();
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(4, 0, 0);
+
+        let path = find_path(walls(&wall), start, goal).unwrap();
+        let tiles = expand(start, &path);
+
+        assert_eq!(path.last(), Some(&goal));
+        assert!(tiles.iter().all(|tile| !wall.contains(&(tile.x, tile.y))));
+    }
+
+    #[test]
+    fn never_cuts_wall_corners() {
+        // Moving from (0, 0) to (1, 1) would clip (1, 0).
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(1, 1, 0);
+
+        let path = find_path(walls(&[(1, 0)]), start, goal).unwrap();
+
+        assert_eq!(path, vec![WorldCoord::new(0, 1, 0), goal]);
+    }
+
+    #[test]
+    fn reports_unreachable_when_goal_is_enclosed() {
+        let ring = [
+            (4, -1),
+            (5, -1),
+            (6, -1),
+            (4, 0),
+            (6, 0),
+            (4, 1),
+            (5, 1),
+            (6, 1),
+        ];
+        let err = find_path(
+            walls(&ring),
+            WorldCoord::new(0, 0, 0),
+            WorldCoord::new(5, 0, 0),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.reason, PathErrorReason::Unreachable);
+        assert_eq!(err.to_string(), "unreachable");
+    }
+
+    #[test]
+    fn rejects_solid_goal() {
+        let goal = WorldCoord::new(3, 0, 0);
+        let err = find_path(walls(&[(3, 0)]), WorldCoord::new(0, 0, 0), goal).unwrap_err();
+
+        assert_eq!(err.reason, PathErrorReason::TargetBlocked(goal));
+    }
+
+    #[test]
+    fn descends_through_a_shaft() {
+        // Open ground level with a capped tunnel along x = 3 below it; (3, 0) is the only shaft.
+        let block_at_world = |coord: WorldCoord| match coord.z {
+            _ if coord.x.abs() > 20 || coord.y.abs() > 20 => None,
+            0 if coord.x == 3 && (1..=4).contains(&coord.y) => Some(STONE),
+            0 => Some(AIR),
+            -1 if coord.x == 3 && (0..=4).contains(&coord.y) => Some(AIR),
+            z if z < 0 => Some(STONE),
+            _ => None,
+        };
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(3, 4, -1);
+
+        let path = find_path(block_at_world, start, goal).unwrap();
+
+        assert_eq!(
+            path,
+            vec![WorldCoord::new(3, 0, 0), WorldCoord::new(3, 0, -1), goal]
+        );
+    }
+
+    #[test]
+    fn sealed_level_below_is_unreachable() {
+        // A pocket of air under a stone cap, with no shaft leading into it.
+        let goal = WorldCoord::new(5, 5, -1);
+        let block_at_world = |coord: WorldCoord| match coord.z {
+            _ if coord.x.abs() > 20 || coord.y.abs() > 20 => None,
+            0 if (coord.x, coord.y) == (5, 5) => Some(STONE),
+            0 => Some(AIR),
+            -1 if coord == goal => Some(AIR),
+            -1 => Some(STONE),
+            _ => None,
+        };
+        let err = find_path(block_at_world, WorldCoord::new(0, 0, 0), goal).unwrap_err();
+
+        assert_eq!(err.reason, PathErrorReason::Unreachable);
+    }
+
+    #[test]
+    fn start_tile_may_be_occupied() {
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(0, 2, 0);
+
+        let path = find_path(walls(&[(0, 0)]), start, goal).unwrap();
+
+        assert_eq!(path, vec![goal]);
+    }
+
+    fn wall_costs(wall: &[(i32, i32)], cost: u32) -> impl FnMut(WorldCoord) -> Option<u32> {
+        let mut block_at_world = walls(wall);
+        move |coord| match block_at_world(coord) {
+            Some(AIR) => Some(0),
+            Some(_) => Some(cost),
+            None => None,
+        }
+    }
+
+    #[test]
+    fn tunnels_when_the_detour_costs_more() {
+        let wall: Vec<(i32, i32)> = (-20..=20).map(|y| (2, y)).collect();
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(4, 0, 0);
+
+        let path = find_tunnelling_path(wall_costs(&wall, 30), start, goal).unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert!(path.contains(&WorldCoord::new(2, 0, 0)));
+    }
+
+    #[test]
+    fn walks_around_a_short_wall_that_is_costly_to_break() {
+        let wall: Vec<(i32, i32)> = (-1..=1).map(|y| (2, y)).collect();
+        let start = WorldCoord::new(0, 0, 0);
+        let goal = WorldCoord::new(4, 0, 0);
+
+        let path = find_tunnelling_path(wall_costs(&wall, 500), start, goal).unwrap();
+
+        assert!(path.iter().all(|tile| !wall.contains(&(tile.x, tile.y))));
+        assert_eq!(path.last(), Some(&goal));
+    }
+}
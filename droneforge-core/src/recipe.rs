@@ -0,0 +1,285 @@
+use crate::block::BlockId;
+use crate::chunk_cache::ChunkCache;
+use crate::coordinates::WorldCoord;
+use crate::inventory::{InventorySlots, add_items, item_count, remove_items};
+use crate::item::ItemId;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+pub type RecipeId = u16;
+
+pub const RECIPE_CORE: RecipeId = 1;
+pub const RECIPE_IRON_PLATE: RecipeId = 2;
+pub const RECIPE_GEAR: RecipeId = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub id: RecipeId,
+    pub name: String,
+    pub inputs: Vec<ItemStack>,
+    pub outputs: Vec<ItemStack>,
+    pub craft_seconds: f32,
+    /// A block the drone must stand next to while crafting.
+    pub station: Option<BlockId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecipeRegistryErrorReason {
+    Json(String),
+    DuplicateId(RecipeId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeRegistryError {
+    pub reason: RecipeRegistryErrorReason,
+}
+
+impl RecipeRegistryError {
+    pub fn new(reason: RecipeRegistryErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            RecipeRegistryErrorReason::Json(_) => "recipe definitions are not valid",
+            RecipeRegistryErrorReason::DuplicateId(_) => "recipe id is defined twice",
+        }
+    }
+}
+
+impl fmt::Display for RecipeRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            RecipeRegistryErrorReason::Json(detail) => write!(f, "{} ({})", self.message(), detail),
+            RecipeRegistryErrorReason::DuplicateId(id) => write!(f, "{} ({})", self.message(), id),
+        }
+    }
+}
+
+impl Error for RecipeRegistryError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CraftErrorReason {
+    UnknownRecipe,
+    MissingInputs,
+    NoSpace,
+    MissingStation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CraftError {
+    pub reason: CraftErrorReason,
+}
+
+impl CraftError {
+    pub fn new(reason: CraftErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            CraftErrorReason::UnknownRecipe => "recipe does not exist",
+            CraftErrorReason::MissingInputs => "missing ingredients",
+            CraftErrorReason::NoSpace => "inventory full",
+            CraftErrorReason::MissingStation => "needs a crafting station nearby",
+        }
+    }
+}
+
+impl fmt::Display for CraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl Error for CraftError {}
+
+const BUILTIN_RECIPES: &str = include_str!("../assets/recipes.json");
+
+static GLOBAL_REGISTRY: OnceLock<RecipeRegistry> = OnceLock::new();
+
+/// Recipes, looked up by [`RecipeId`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeRegistry {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeRegistry {
+    pub fn from_recipes(
+        recipes: impl IntoIterator<Item = Recipe>,
+    ) -> Result<Self, RecipeRegistryError> {
+        let mut registry = Self {
+            recipes: Vec::new(),
+        };
+        for recipe in recipes {
+            if registry.get(recipe.id).is_some() {
+                return Err(RecipeRegistryError::new(
+                    RecipeRegistryErrorReason::DuplicateId(recipe.id),
+                ));
+            }
+            registry.recipes.push(recipe);
+        }
+        Ok(registry)
+    }
+
+    /// Parses a JSON array of [`Recipe`]s.
+    pub fn from_json(source: &str) -> Result<Self, RecipeRegistryError> {
+        let recipes: Vec<Recipe> = serde_json::from_str(source).map_err(|err| {
+            RecipeRegistryError::new(RecipeRegistryErrorReason::Json(err.to_string()))
+        })?;
+        Self::from_recipes(recipes)
+    }
+
+    /// The recipes shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_RECIPES).expect("built-in recipes must be valid")
+    }
+
+    /// The registry crafting goes through. It is the built-in one unless
+    /// [`RecipeRegistry::install`] ran first.
+    pub fn global() -> &'static RecipeRegistry {
+        GLOBAL_REGISTRY.get_or_init(Self::builtin)
+    }
+
+    /// Makes `self` the global registry. Fails, handing the registry back, once the global
+    /// registry has been used or installed.
+    pub fn install(self) -> Result<(), RecipeRegistry> {
+        GLOBAL_REGISTRY.set(self)
+    }
+
+    pub fn get(&self, recipe: RecipeId) -> Option<&Recipe> {
+        self.recipes.iter().find(|candidate| candidate.id == recipe)
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+}
+
+impl Recipe {
+    /// Whether `slots` hold every input.
+    pub fn has_inputs(&self, slots: &InventorySlots) -> bool {
+        self.inputs
+            .iter()
+            .all(|input| item_count(slots, input.item) >= input.count)
+    }
+
+    /// Whether a drone on `tile` can reach the recipe's station, if it needs one.
+    pub fn station_in_reach(&self, chunk_cache: &ChunkCache, tile: WorldCoord) -> bool {
+        let Some(station) = self.station else {
+            return true;
+        };
+        (-1..=1).any(|dy| {
+            (-1..=1).any(|dx| {
+                let neighbour = WorldCoord::new(tile.x + dx, tile.y + dy, tile.z);
+                chunk_cache.block_at_world(neighbour) == Some(station)
+            })
+        })
+    }
+
+    /// Swaps the inputs in `slots` for the outputs. Either the whole recipe applies or
+    /// `slots` are left untouched.
+    pub fn craft(&self, slots: &mut InventorySlots) -> Result<(), CraftError> {
+        if !self.has_inputs(slots) {
+            return Err(CraftError::new(CraftErrorReason::MissingInputs));
+        }
+
+        let mut crafted = *slots;
+        for input in &self.inputs {
+            remove_items(&mut crafted, input.item, input.count);
+        }
+        for output in &self.outputs {
+            if add_items(&mut crafted, output.item, output.count) > 0 {
+                return Err(CraftError::new(CraftErrorReason::NoSpace));
+            }
+        }
+
+        *slots = crafted;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::{InventorySlot, empty_inventory, stack_limit};
+    use crate::item::{ITEM_CORE, ITEM_GEAR, ITEM_IRON_ORE, ITEM_IRON_PLATE, ITEM_STONE};
+
+    fn recipe(id: RecipeId) -> Recipe {
+        RecipeRegistry::builtin().get(id).unwrap().clone()
+    }
+
+    #[test]
+    fn crafting_swaps_inputs_for_outputs() {
+        let mut slots = empty_inventory();
+        slots[0] = InventorySlot {
+            item: Some(ITEM_STONE),
+            count: 2,
+        };
+
+        recipe(RECIPE_CORE).craft(&mut slots).unwrap();
+
+        assert_eq!(item_count(&slots, ITEM_STONE), 1);
+        assert_eq!(item_count(&slots, ITEM_CORE), 1);
+    }
+
+    #[test]
+    fn crafting_without_inputs_changes_nothing() {
+        let mut slots = empty_inventory();
+        slots[0] = InventorySlot {
+            item: Some(ITEM_STONE),
+            count: 1,
+        };
+        let before = slots;
+
+        let err = recipe(RECIPE_GEAR).craft(&mut slots).unwrap_err();
+
+        assert_eq!(err.reason, CraftErrorReason::MissingInputs);
+        assert_eq!(slots, before);
+    }
+
+    #[test]
+    fn crafting_into_a_full_inventory_rolls_back() {
+        let mut slots = empty_inventory();
+        slots[0] = InventorySlot {
+            item: Some(ITEM_IRON_PLATE),
+            count: 2,
+        };
+        for slot in slots.iter_mut().skip(1) {
+            *slot = InventorySlot {
+                item: Some(ITEM_IRON_ORE),
+                count: stack_limit(ITEM_IRON_ORE),
+            };
+        }
+        let before = slots;
+
+        // The plate left over keeps its slot, so the gears have nowhere to go.
+        let err = recipe(RECIPE_GEAR).craft(&mut slots).unwrap_err();
+
+        assert_eq!(err.reason, CraftErrorReason::NoSpace);
+        assert_eq!(slots, before);
+        assert_eq!(item_count(&slots, ITEM_GEAR), 0);
+    }
+
+    #[test]
+    fn rejects_duplicate_recipe_ids() {
+        let mut core = recipe(RECIPE_CORE);
+        let err = RecipeRegistry::from_recipes([core.clone(), {
+            core.name = "Another core".into();
+            core
+        }])
+        .unwrap_err();
+        assert_eq!(
+            err.reason,
+            RecipeRegistryErrorReason::DuplicateId(RECIPE_CORE)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use crate::item::ItemId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Items handed in at a core. Building defenses spends from here rather than from a drone's
+/// inventory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourcePool {
+    amounts: BTreeMap<ItemId, u32>,
+}
+
+impl ResourcePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn amount(&self, item: ItemId) -> u32 {
+        self.amounts.get(&item).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, item: ItemId, count: u32) {
+        if count > 0 {
+            let amount = self.amounts.entry(item).or_insert(0);
+            *amount = amount.saturating_add(count);
+        }
+    }
+
+    /// Takes `count` of `item` if the pool holds that many; otherwise leaves it untouched.
+    pub fn spend(&mut self, item: ItemId, count: u32) -> bool {
+        let available = self.amount(item);
+        if available < count {
+            return false;
+        }
+        if available == count {
+            self.amounts.remove(&item);
+        } else {
+            self.amounts.insert(item, available - count);
+        }
+        true
+    }
+}
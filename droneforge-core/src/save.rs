@@ -0,0 +1,1098 @@
+use crate::block::CORE;
+use crate::chunk::{ChunkBlocks, ChunkError};
+use crate::chunk_cache::{CachedChunk, PackedChunk};
+use crate::combat::ItemDrop;
+use crate::coordinates::WorldCoord;
+use crate::drone::{BASE_MINING_SPEED, DroneId, DronePose};
+use crate::enemy::Enemy;
+use crate::inventory::{INVENTORY_SLOTS, InventorySlot};
+use crate::item::{ITEM_CORE, ITEM_IRON_ORE, ITEM_STONE, ItemId};
+use crate::order::{CraftOrder, DigOrder, MoveOrder, Order, OrderError};
+use crate::recipe::RecipeId;
+use crate::resources::ResourcePool;
+use crate::scheduler::Scheduler;
+use crate::structure::{CoreStructure, GamePhase};
+use crate::warrior::{WARRIOR_MAX_HEALTH, Warrior};
+use crate::wave::Waves;
+use crate::world::{NextIds, World};
+use crate::worldgen::DeterministicMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::fmt;
+
+pub const SAVE_VERSION: u32 = 9;
+
+/// Upgrades a save document in place from one schema version to the next.
+pub type SaveMigration = fn(&mut JsonValue) -> Result<(), SaveError>;
+
+/// `MIGRATIONS[n]` turns a version `n + 1` document into version `n + 2`.
+const MIGRATIONS: &[SaveMigration] = &[
+    pack_chunk_blocks,
+    inventory_blocks_to_items,
+    add_idle_scheduler,
+    rebuild_cores,
+    add_warriors_and_resources,
+    add_fights,
+    add_ids,
+    add_phase,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveErrorReason {
+    Json(String),
+    MissingVersion,
+    UnsupportedVersion(u32),
+    Migration(String),
+    InvalidInventory,
+    InvalidScheduler,
+    Chunk(ChunkError),
+    Order(OrderError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveError {
+    pub reason: SaveErrorReason,
+}
+
+impl SaveError {
+    pub fn new(reason: SaveErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            SaveErrorReason::Json(_) => "save is not valid",
+            SaveErrorReason::MissingVersion => "save has no version",
+            SaveErrorReason::UnsupportedVersion(_) => "save version is not supported",
+            SaveErrorReason::Migration(_) => "failed to upgrade save",
+            SaveErrorReason::InvalidInventory => "save holds an inventory of the wrong size",
+            SaveErrorReason::InvalidScheduler => "save holds jobs for a different number of drones",
+            SaveErrorReason::Chunk(_) => "save holds an invalid chunk",
+            SaveErrorReason::Order(_) => "save holds an order that cannot be queued",
+        }
+    }
+}
+
+impl From<ChunkError> for SaveError {
+    fn from(reason: ChunkError) -> Self {
+        Self {
+            reason: SaveErrorReason::Chunk(reason),
+        }
+    }
+}
+
+impl From<OrderError> for SaveError {
+    fn from(reason: OrderError) -> Self {
+        Self {
+            reason: SaveErrorReason::Order(reason),
+        }
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            SaveErrorReason::Json(detail) | SaveErrorReason::Migration(detail) => {
+                write!(f, "{} ({})", self.message(), detail)
+            }
+            SaveErrorReason::UnsupportedVersion(version) => {
+                write!(f, "{} ({})", self.message(), version)
+            }
+            SaveErrorReason::Chunk(err) => write!(f, "{} ({:?})", self.message(), err),
+            SaveErrorReason::Order(err) => write!(f, "{} ({})", self.message(), err),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum OrderSave {
+    Move {
+        waypoints: Vec<WorldCoord>,
+    },
+    Dig {
+        target: WorldCoord,
+        elapsed: f32,
+        #[serde(default)]
+        duration: Option<f32>,
+    },
+    Craft {
+        recipe: RecipeId,
+        tile: WorldCoord,
+        elapsed: f32,
+    },
+}
+
+impl OrderSave {
+    fn capture(order: &Order) -> Self {
+        match order {
+            Order::Move(order) => OrderSave::Move {
+                waypoints: order.remaining_waypoints().collect(),
+            },
+            Order::Dig(order) => OrderSave::Dig {
+                target: order.target_tile,
+                elapsed: order.elapsed(),
+                duration: order.duration(),
+            },
+            Order::Craft(order) => OrderSave::Craft {
+                recipe: order.recipe,
+                tile: order.tile,
+                elapsed: order.elapsed(),
+            },
+        }
+    }
+
+    fn restore(&self, position: [f32; 3]) -> Option<Order> {
+        match self {
+            OrderSave::Move { waypoints } => {
+                MoveOrder::along_path(position, waypoints.clone()).map(Order::Move)
+            }
+            OrderSave::Dig {
+                target,
+                elapsed,
+                duration,
+            } => {
+                let mut order = DigOrder::resume(*target, *elapsed);
+                if let Some(duration) = duration {
+                    order.start(*duration);
+                }
+                Some(Order::Dig(order))
+            }
+            OrderSave::Craft {
+                recipe,
+                tile,
+                elapsed,
+            } => Some(Order::Craft(CraftOrder::resume(*recipe, *tile, *elapsed))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroneSave {
+    pub id: DroneId,
+    pub name: String,
+    pub position: [f32; 3],
+    pub heading: [f32; 2],
+    pub health: i32,
+    pub max_health: i32,
+    pub armour: i32,
+    #[serde(default = "base_mining_speed")]
+    pub mining_speed: f32,
+    pub inventory: Vec<InventorySlot>,
+    pub orders: Vec<OrderSave>,
+}
+
+fn base_mining_speed() -> f32 {
+    BASE_MINING_SPEED
+}
+
+/// Everything needed to rebuild a game: terrain comes from the world's generator, with
+/// `chunks` holding the chunks the player changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSave {
+    pub version: u32,
+    /// Seed of the procedural map the game was played on; see [`WorldSave::generator`].
+    pub seed: u64,
+    pub tick: u64,
+    pub drones: Vec<DroneSave>,
+    pub chunks: Vec<PackedChunk>,
+    /// Mine jobs, their tile progress and which drone works on each.
+    pub scheduler: Scheduler,
+    /// Placed cores and their HP.
+    pub cores: Vec<CoreStructure>,
+    pub warriors: Vec<Warrior>,
+    /// Items handed in at cores.
+    pub resources: ResourcePool,
+    /// Items dead drones left behind.
+    pub drops: Vec<ItemDrop>,
+    /// Enemies on the map, mid-route and mid-swing.
+    pub enemies: Vec<Enemy>,
+    /// The planned attack, if the game has one.
+    pub waves: Option<Waves>,
+    pub next_ids: NextIds,
+    /// Whether the game is still being played, or how it ended.
+    pub phase: GamePhase,
+}
+
+impl WorldSave {
+    /// Records `world`. `seed` is only needed to rebuild games played on a
+    /// [`DeterministicMap`]; pass anything for worlds on other generators.
+    pub fn capture(world: &World, seed: u64) -> Self {
+        let drones = world
+            .drones()
+            .iter()
+            .enumerate()
+            .map(|(index, drone)| DroneSave {
+                id: drone.id,
+                name: drone.name.clone(),
+                position: drone.position,
+                heading: drone.heading,
+                health: drone.health,
+                max_health: drone.max_health,
+                armour: drone.armour,
+                mining_speed: drone.mining_speed,
+                inventory: world
+                    .inventory(index)
+                    .map(|slots| slots.to_vec())
+                    .unwrap_or_default(),
+                orders: world.queued_orders(index).map(OrderSave::capture).collect(),
+            })
+            .collect();
+
+        let mut chunks: Vec<PackedChunk> = world
+            .chunk_cache()
+            .changed_chunks()
+            .map(CachedChunk::to_packed)
+            .collect();
+        chunks.sort_by_key(|chunk| (chunk.position.x, chunk.position.y, chunk.position.z));
+
+        Self {
+            version: SAVE_VERSION,
+            seed,
+            tick: world.tick,
+            drones,
+            chunks,
+            scheduler: world.scheduler().clone(),
+            cores: world.cores().to_vec(),
+            warriors: world.warriors().to_vec(),
+            resources: world.resources().clone(),
+            drops: world.drops().to_vec(),
+            enemies: world.enemies().to_vec(),
+            waves: world.waves().cloned(),
+            next_ids: world.next_ids(),
+            phase: world.phase(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        serde_json::to_string(self)
+            .map_err(|err| SaveError::new(SaveErrorReason::Json(err.to_string())))
+    }
+
+    /// Parses a save, upgrading older schema versions first.
+    pub fn from_json(source: &str) -> Result<Self, SaveError> {
+        let mut document: JsonValue = serde_json::from_str(source)
+            .map_err(|err| SaveError::new(SaveErrorReason::Json(err.to_string())))?;
+        migrate(&mut document, MIGRATIONS, SAVE_VERSION)?;
+        serde_json::from_value(document)
+            .map_err(|err| SaveError::new(SaveErrorReason::Json(err.to_string())))
+    }
+
+    /// The procedural map a game saved with its seed was played on.
+    pub fn generator(&self) -> DeterministicMap {
+        DeterministicMap::new(self.seed)
+    }
+
+    /// Loads the save into `world`, which should be built on the generator the saved game
+    /// used. Saved chunks replace whatever terrain the world holds, and everything else keeps
+    /// coming from the world's own generator.
+    pub fn restore(&self, world: &mut World) -> Result<(), SaveError> {
+        let mut inventories = Vec::with_capacity(self.drones.len());
+        for drone in &self.drones {
+            let slots: [InventorySlot; INVENTORY_SLOTS] = drone
+                .inventory
+                .as_slice()
+                .try_into()
+                .map_err(|_| SaveError::new(SaveErrorReason::InvalidInventory))?;
+            inventories.push(slots);
+        }
+        let chunks = self
+            .chunks
+            .iter()
+            .cloned()
+            .map(CachedChunk::from_packed)
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.scheduler.drone_count() != self.drones.len() {
+            return Err(SaveError::new(SaveErrorReason::InvalidScheduler));
+        }
+
+        world.tick = self.tick;
+        world.set_drones(
+            self.drones
+                .iter()
+                .map(|drone| DronePose {
+                    id: drone.id,
+                    name: drone.name.clone(),
+                    position: drone.position,
+                    heading: drone.heading,
+                    health: drone.health,
+                    max_health: drone.max_health,
+                    armour: drone.armour,
+                    mining_speed: drone.mining_speed,
+                })
+                .collect(),
+        );
+        for (index, (drone, slots)) in self.drones.iter().zip(inventories).enumerate() {
+            if let Some(inventory) = world.inventory_mut(index) {
+                *inventory = slots;
+            }
+            for order in drone
+                .orders
+                .iter()
+                .filter_map(|order| order.restore(drone.position))
+            {
+                world.queue_order(index, order)?;
+            }
+            // Queueing turns the drone towards its first order; keep the saved heading. Drones
+            // also keep their saved id rather than the one `set_drones` numbered them with.
+            let restored = &mut world.drones_mut()[index];
+            restored.heading = drone.heading;
+            restored.id = drone.id;
+        }
+        let chunk_cache = world.chunk_cache_mut();
+        for chunk in chunks {
+            chunk_cache.insert_chunk(chunk);
+        }
+        world.set_scheduler(self.scheduler.clone());
+        world.set_cores(self.cores.clone());
+        world.set_warriors(self.warriors.clone());
+        *world.resources_mut() = self.resources.clone();
+        world.set_drops(self.drops.clone());
+        world.set_enemies(self.enemies.clone());
+        if let Some(waves) = &self.waves {
+            world.set_waves(waves.clone());
+        }
+        world.set_next_ids(self.next_ids);
+        world.set_phase(self.phase);
+        Ok(())
+    }
+}
+
+/// Runs `migrations` until `document` reaches `target_version`.
+pub fn migrate(
+    document: &mut JsonValue,
+    migrations: &[SaveMigration],
+    target_version: u32,
+) -> Result<(), SaveError> {
+    loop {
+        let version = document
+            .get("version")
+            .and_then(JsonValue::as_u64)
+            .ok_or_else(|| SaveError::new(SaveErrorReason::MissingVersion))?;
+        let version = u32::try_from(version)
+            .map_err(|_| SaveError::new(SaveErrorReason::UnsupportedVersion(u32::MAX)))?;
+        if version == target_version {
+            return Ok(());
+        }
+
+        let migration = version
+            .checked_sub(1)
+            .and_then(|index| migrations.get(index as usize))
+            .filter(|_| version < target_version)
+            .ok_or_else(|| SaveError::new(SaveErrorReason::UnsupportedVersion(version)))?;
+        migration(document)?;
+        document["version"] = JsonValue::from(version + 1);
+    }
+}
+
+/// Version 2 stores chunks palette-packed instead of as flat block lists.
+fn pack_chunk_blocks(document: &mut JsonValue) -> Result<(), SaveError> {
+    let migration_error =
+        |err: serde_json::Error| SaveError::new(SaveErrorReason::Migration(err.to_string()));
+    let chunks: Vec<ChunkBlocks> =
+        serde_json::from_value(document["chunks"].take()).map_err(migration_error)?;
+    let packed = chunks
+        .iter()
+        .map(|chunk| CachedChunk::from_block_save(chunk).map(|cached| cached.to_packed()))
+        .collect::<Result<Vec<_>, _>>()?;
+    document["chunks"] = serde_json::to_value(packed).map_err(migration_error)?;
+    Ok(())
+}
+
+/// Version 3 inventories hold items rather than blocks.
+fn inventory_blocks_to_items(document: &mut JsonValue) -> Result<(), SaveError> {
+    // Version 2 inventories only ever held the blocks digging produced.
+    fn item_for_saved_block(block: u64) -> Option<ItemId> {
+        match block {
+            2 => Some(ITEM_STONE),
+            3 => Some(ITEM_IRON_ORE),
+            5 => Some(ITEM_CORE),
+            _ => None,
+        }
+    }
+
+    let Some(drones) = document["drones"].as_array_mut() else {
+        return Ok(());
+    };
+    for slot in drones
+        .iter_mut()
+        .filter_map(|drone| drone["inventory"].as_array_mut())
+        .flatten()
+    {
+        let Some(fields) = slot.as_object_mut() else {
+            continue;
+        };
+        let item = match fields.remove("block") {
+            None | Some(JsonValue::Null) => None,
+            Some(block) => {
+                let item = block
+                    .as_u64()
+                    .and_then(item_for_saved_block)
+                    .ok_or_else(|| {
+                        SaveError::new(SaveErrorReason::Migration(format!(
+                            "inventory holds block {block} with no matching item"
+                        )))
+                    })?;
+                Some(item)
+            }
+        };
+        fields.insert("item".to_owned(), JsonValue::from(item));
+    }
+    Ok(())
+}
+
+/// Version 4 saves mine jobs; older saves had none, so every drone starts without one.
+fn add_idle_scheduler(document: &mut JsonValue) -> Result<(), SaveError> {
+    let drone_count = document["drones"].as_array().map_or(0, Vec::len);
+    let mut scheduler = Scheduler::new();
+    scheduler.set_drone_count(drone_count);
+    document["scheduler"] = serde_json::to_value(scheduler)
+        .map_err(|err| SaveError::new(SaveErrorReason::Migration(err.to_string())))?;
+    Ok(())
+}
+
+/// Version 5 saves core HP. Cores an older save already lists keep their HP; every other
+/// `CORE` block it holds becomes a full-HP core.
+fn rebuild_cores(document: &mut JsonValue) -> Result<(), SaveError> {
+    let migration_error =
+        |err: serde_json::Error| SaveError::new(SaveErrorReason::Migration(err.to_string()));
+    let chunks: Vec<PackedChunk> =
+        serde_json::from_value(document["chunks"].clone()).map_err(migration_error)?;
+    let mut cores: Vec<CoreStructure> = match document.get("cores") {
+        Some(saved) => serde_json::from_value(saved.clone()).map_err(migration_error)?,
+        None => Vec::new(),
+    };
+    for chunk in chunks {
+        for tile in CachedChunk::from_packed(chunk)?.tiles_holding(CORE) {
+            if !cores.iter().any(|core| core.tile == tile) {
+                cores.push(CoreStructure::new(tile));
+            }
+        }
+    }
+    document["cores"] = serde_json::to_value(cores).map_err(migration_error)?;
+    Ok(())
+}
+
+/// Version 6 saves stationed warriors, with their health, and the resource pool. Older saves
+/// may hold neither, and warriors saved before they had health start at full health.
+fn add_warriors_and_resources(document: &mut JsonValue) -> Result<(), SaveError> {
+    let resources = serde_json::to_value(ResourcePool::new())
+        .map_err(|err| SaveError::new(SaveErrorReason::Migration(err.to_string())))?;
+    let Some(fields) = document.as_object_mut() else {
+        return Ok(());
+    };
+    fields.entry("resources").or_insert(resources);
+    let warriors = fields
+        .entry("warriors")
+        .or_insert_with(|| JsonValue::Array(Vec::new()));
+    for warrior in warriors
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(JsonValue::as_object_mut)
+    {
+        warrior
+            .entry("health")
+            .or_insert(JsonValue::from(WARRIOR_MAX_HEALTH));
+    }
+    Ok(())
+}
+
+/// Version 7 saves drone armour, item drops, enemies and the wave plan. Older saves had
+/// none of them, so drones start unarmoured and the map starts empty of fights.
+fn add_fights(document: &mut JsonValue) -> Result<(), SaveError> {
+    let Some(fields) = document.as_object_mut() else {
+        return Ok(());
+    };
+    fields
+        .entry("drops")
+        .or_insert_with(|| JsonValue::Array(Vec::new()));
+    fields
+        .entry("enemies")
+        .or_insert_with(|| JsonValue::Array(Vec::new()));
+    fields.entry("waves").or_insert(JsonValue::Null);
+    for drone in fields
+        .get_mut("drones")
+        .and_then(JsonValue::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(JsonValue::as_object_mut)
+    {
+        drone.entry("armour").or_insert(JsonValue::from(0));
+    }
+    Ok(())
+}
+
+/// Version 8 saves drone ids and the id counters. Older saves numbered drones by position,
+/// and their counters start after the highest id still in use.
+fn add_ids(document: &mut JsonValue) -> Result<(), SaveError> {
+    let Some(fields) = document.as_object_mut() else {
+        return Ok(());
+    };
+    let after_last = |list: Option<&JsonValue>| {
+        list.and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entity| entity["id"].as_u64())
+            .map(|id| id + 1)
+            .max()
+            .unwrap_or(0)
+    };
+    let next_ids = serde_json::json!({
+        "drone": fields.get("drones").and_then(JsonValue::as_array).map_or(0, Vec::len),
+        "enemy": after_last(fields.get("enemies")),
+        "warrior": after_last(fields.get("warriors")),
+    });
+    fields.insert("next_ids".to_owned(), next_ids);
+    let drones = fields.get_mut("drones").and_then(JsonValue::as_array_mut);
+    for (id, drone) in drones.into_iter().flatten().enumerate() {
+        if let Some(drone) = drone.as_object_mut() {
+            drone.insert("id".to_owned(), JsonValue::from(id));
+        }
+    }
+    Ok(())
+}
+
+/// Version 9 saves the game phase. Older saves did not record how a game ended, so they
+/// load as still running.
+fn add_phase(document: &mut JsonValue) -> Result<(), SaveError> {
+    let phase = serde_json::to_value(GamePhase::Running)
+        .map_err(|err| SaveError::new(SaveErrorReason::Migration(err.to_string())))?;
+    if let Some(fields) = document.as_object_mut() {
+        fields.entry("phase").or_insert(phase);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, DIRT, STONE, WARRIOR_SPOT};
+    use crate::combat::{CombatEvent, Combatant};
+    use crate::coordinates::{ChunkPosition, TileBox3};
+    use crate::order::OrderErrorReason;
+    use crate::structure::CORE_MAX_HP;
+    use crate::warrior::{WARRIOR_COST_ITEM, WARRIOR_IRON_COST};
+    use crate::wave::{EnemyKind, WaveSchedule};
+    use crate::world::TICKS_PER_SECOND;
+    use crate::worldgen::FlatGenerator;
+
+    fn generated_world(generator: &DeterministicMap) -> World {
+        World::with_generator(generator.clone())
+    }
+
+    #[test]
+    fn round_trips_world_with_dug_tiles_and_cores() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![
+            DronePose::new([2.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([1.0, 0.0, 0.0], [1.0, 0.0], "d2", 7, 10),
+        ]);
+
+        let dug = WorldCoord::new(3, 0, 0);
+        assert_eq!(world.block_at(dug), Some(STONE));
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(dug)))
+            .unwrap();
+        for _ in 0..(6 * TICKS_PER_SECOND) {
+            world.step();
+        }
+        let core = WorldCoord::new(2, 0, -1);
+        world.set_block(core, CORE).unwrap();
+        world.damage_core(core, 30);
+
+        // Leave an unfinished dig and a move in flight.
+        let half_dug = WorldCoord::new(2, 1, 0);
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(half_dug)))
+            .unwrap();
+        let route = vec![WorldCoord::new(1, 0, 0), dug];
+        world
+            .issue_order(
+                1,
+                Order::Move(MoveOrder::along_path([1.0, 0.0, 0.0], route).unwrap()),
+            )
+            .unwrap();
+        for _ in 0..30 {
+            world.step();
+        }
+
+        let save = WorldSave::capture(&world, generator.seed());
+        let json = save.to_json().unwrap();
+        let loaded = WorldSave::from_json(&json).unwrap();
+        assert_eq!(loaded, save);
+
+        let mut restored = generated_world(&loaded.generator());
+        loaded.restore(&mut restored).unwrap();
+
+        assert_eq!(restored.tick, world.tick);
+        assert_eq!(restored.drones(), world.drones());
+        assert_eq!(restored.inventory(0), world.inventory(0));
+        assert_eq!(restored.inventory(0).unwrap()[0].item, Some(ITEM_STONE));
+        assert_eq!(restored.block_at(dug), Some(AIR));
+        assert_eq!(restored.block_at(core), Some(CORE));
+        assert_eq!(restored.cores(), world.cores());
+        assert_eq!(restored.cores()[0].hp, 70);
+        assert_eq!(restored.block_at(half_dug), world.block_at(half_dug));
+        for index in 0..2 {
+            let expected: Vec<&Order> = world.queued_orders(index).collect();
+            let actual: Vec<&Order> = restored.queued_orders(index).collect();
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(WorldSave::capture(&restored, generator.seed()), save);
+    }
+
+    #[test]
+    fn restores_onto_the_worlds_own_generator() {
+        let terrain = || FlatGenerator::new(-1, DIRT);
+        let mut world = World::with_generator(terrain());
+        let dug = WorldCoord::new(1, 1, -1);
+        world.chunk_cache_mut().load_block_at(dug);
+        world.set_block(dug, AIR).unwrap();
+
+        let save = WorldSave::capture(&world, 0);
+        let mut restored = World::with_generator(terrain());
+        WorldSave::from_json(&save.to_json().unwrap())
+            .unwrap()
+            .restore(&mut restored)
+            .unwrap();
+
+        assert_eq!(restored.block_at(dug), Some(AIR));
+        let untouched =
+            TileBox3::new(WorldCoord::new(60, -60, -1), WorldCoord::new(67, -53, -1)).unwrap();
+        for tile in untouched.tiles() {
+            assert_eq!(restored.chunk_cache_mut().load_block_at(tile), Some(DIRT));
+        }
+    }
+
+    #[test]
+    fn round_trips_mine_jobs_and_their_reservations() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![
+            DronePose::new([2.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([1.0, 0.0, 0.0], [1.0, 0.0], "d2", 10, 10),
+        ]);
+        let tile_box = TileBox3::new(WorldCoord::new(3, 0, 0), WorldCoord::new(3, 1, 0)).unwrap();
+        for tile in tile_box.tiles() {
+            world.set_block(tile, STONE).unwrap();
+        }
+        let job = world.add_mine_job(tile_box);
+        for _ in 0..10 {
+            world.step();
+        }
+        assert!(world.job(job).unwrap().tile_assigned_to(0).is_some());
+
+        let save = WorldSave::capture(&world, generator.seed());
+        let loaded = WorldSave::from_json(&save.to_json().unwrap()).unwrap();
+        let mut restored = generated_world(&loaded.generator());
+        loaded.restore(&mut restored).unwrap();
+
+        assert_eq!(restored.scheduler(), world.scheduler());
+        assert_eq!(restored.drone_job(0), Some(job));
+        for _ in 0..(10 * TICKS_PER_SECOND) {
+            world.step();
+            restored.step();
+        }
+        assert!(restored.job(job).unwrap().is_finished());
+        assert_eq!(restored.jobs(), world.jobs());
+    }
+
+    #[test]
+    fn restore_reports_orders_that_cannot_be_queued() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![
+            DronePose::new([2.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([2.0, 1.0, 0.0], [1.0, 0.0], "d2", 10, 10),
+        ]);
+        let target = WorldCoord::new(3, 0, 0);
+        world.set_block(target, STONE).unwrap();
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(target)))
+            .unwrap();
+        let mut save = WorldSave::capture(&world, generator.seed());
+        save.drones[1].orders = save.drones[0].orders.clone();
+
+        let err = save.restore(&mut World::new()).unwrap_err();
+
+        assert_eq!(
+            err.reason,
+            SaveErrorReason::Order(OrderError::new(OrderErrorReason::TileReserved))
+        );
+    }
+
+    #[test]
+    fn saves_only_changed_chunks() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_block(WorldCoord::new(5, 5, -2), AIR).unwrap();
+
+        let save = WorldSave::capture(&world, generator.seed());
+
+        let positions: Vec<ChunkPosition> =
+            save.chunks.iter().map(|chunk| chunk.position).collect();
+        assert_eq!(positions, vec![ChunkPosition::new(0, 0, -1)]);
+    }
+
+    #[test]
+    fn upgrades_version_one_block_lists() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        let core = WorldCoord::new(4, 4, -1);
+        world.set_block(core, CORE).unwrap();
+        let chunk = world
+            .chunk_cache()
+            .changed_chunks()
+            .next()
+            .unwrap()
+            .to_block_save();
+        let document = serde_json::json!({
+            "version": 1,
+            "seed": 42,
+            "tick": 0,
+            "drones": [],
+            "chunks": [chunk],
+        });
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+        let mut restored = generated_world(&save.generator());
+        save.restore(&mut restored).unwrap();
+
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(restored.block_at(core), Some(CORE));
+        assert_eq!(save.cores, vec![CoreStructure::new(core)]);
+        assert_eq!(restored.cores(), &[CoreStructure::new(core)]);
+    }
+
+    #[test]
+    fn upgrades_version_four_cores_keeping_saved_hp() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        let (damaged, unlisted) = (WorldCoord::new(4, 4, -1), WorldCoord::new(6, 4, -1));
+        world.set_block(damaged, CORE).unwrap();
+        world.set_block(unlisted, CORE).unwrap();
+        world.damage_core(damaged, 60);
+        let mut document =
+            serde_json::to_value(WorldSave::capture(&world, generator.seed())).unwrap();
+        document["version"] = JsonValue::from(4);
+        document["cores"].as_array_mut().unwrap().truncate(1);
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(save.cores, world.cores());
+        assert_eq!(save.cores[0].hp, CORE_MAX_HP - 60);
+        assert_eq!(save.cores[1], CoreStructure::new(unlisted));
+    }
+
+    #[test]
+    fn upgrades_version_two_block_inventories_to_items() {
+        let document = serde_json::json!({
+            "version": 2,
+            "seed": 42,
+            "tick": 0,
+            "drones": [{
+                "name": "d1",
+                "position": [0.0, 0.0, 0.0],
+                "heading": [1.0, 0.0],
+                "health": 10,
+                "max_health": 10,
+                "inventory": (0..INVENTORY_SLOTS)
+                    .map(|index| match index {
+                        0 => serde_json::json!({ "block": STONE, "count": 4 }),
+                        1 => serde_json::json!({ "block": CORE, "count": 1 }),
+                        _ => serde_json::json!({ "block": null, "count": 0 }),
+                    })
+                    .collect::<Vec<_>>(),
+                "orders": [],
+            }],
+            "chunks": [],
+        });
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(save.scheduler.drone_count(), 1);
+        let inventory = &save.drones[0].inventory;
+        assert_eq!(inventory[0].item, Some(ITEM_STONE));
+        assert_eq!(inventory[0].count, 4);
+        assert_eq!(inventory[1].item, Some(ITEM_CORE));
+        assert_eq!(inventory[2].item, None);
+    }
+
+    #[test]
+    fn upgrades_version_five_warriors_and_resources() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_warriors(vec![Warrior::new(3, WorldCoord::new(1, 1, 0))]);
+        let mut document =
+            serde_json::to_value(WorldSave::capture(&world, generator.seed())).unwrap();
+        document["version"] = JsonValue::from(5);
+        document.as_object_mut().unwrap().remove("resources");
+        document["warriors"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("health");
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(save.warriors, world.warriors());
+        assert_eq!(save.warriors[0].health, WARRIOR_MAX_HEALTH);
+        assert_eq!(save.resources, ResourcePool::new());
+
+        document.as_object_mut().unwrap().remove("warriors");
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+        assert!(save.warriors.is_empty());
+    }
+
+    #[test]
+    fn round_trips_a_fight_in_progress() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![
+            DronePose::new([7.0, 8.0, 0.0], [1.0, 0.0], "d1", 10, 10).with_armour(1),
+        ]);
+        let schedule = WaveSchedule::from_json(
+            r#"[{ "delay_seconds": 1, "groups": [{ "enemy": "crawler", "count": 3 }] }]"#,
+        )
+        .unwrap();
+        let arena = TileBox3::new(WorldCoord::new(4, 4, 0), WorldCoord::new(12, 12, 0)).unwrap();
+        // An open floor keeps the enemies from tunnelling through half the map.
+        for tile in arena.tiles() {
+            world.set_block(tile, AIR).unwrap();
+        }
+        world.set_block(WorldCoord::new(8, 8, 0), CORE).unwrap();
+        world.set_waves(Waves::new(&schedule, 5, arena, world.tick));
+        for _ in 0..(2 * TICKS_PER_SECOND) {
+            world.step();
+        }
+        assert!(!world.enemies().is_empty());
+
+        let save = WorldSave::capture(&world, generator.seed());
+        let loaded = WorldSave::from_json(&save.to_json().unwrap()).unwrap();
+        assert_eq!(loaded, save);
+        let mut restored = generated_world(&loaded.generator());
+        loaded.restore(&mut restored).unwrap();
+        assert_eq!(restored.enemies(), world.enemies());
+        assert_eq!(restored.waves(), world.waves());
+
+        world.drain_combat_events();
+        for _ in 0..(4 * TICKS_PER_SECOND) {
+            world.step();
+            restored.step();
+        }
+        let log = world.drain_combat_events();
+        assert!(!log.is_empty());
+        assert_eq!(restored.drain_combat_events(), log);
+        assert_eq!(restored.enemies(), world.enemies());
+        assert_eq!(restored.drones(), world.drones());
+    }
+
+    #[test]
+    fn restored_games_keep_handing_out_the_same_ids() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        let arena = TileBox3::new(WorldCoord::new(0, 0, 0), WorldCoord::new(8, 8, 0)).unwrap();
+        for tile in arena.tiles() {
+            world.set_block(tile, AIR).unwrap();
+        }
+        let spot = WorldCoord::new(6, 6, 0);
+        world.set_block(spot, WARRIOR_SPOT).unwrap();
+        world
+            .resources_mut()
+            .add(WARRIOR_COST_ITEM, 2 * WARRIOR_IRON_COST);
+        for (index, x) in [1.0, 3.0].into_iter().enumerate() {
+            world.add_drone(DronePose::new(
+                [x, 1.0, 0.0],
+                [1.0, 0.0],
+                format!("d{index}"),
+                10,
+                10,
+            ));
+        }
+        world.place_warrior(spot).unwrap();
+        world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(8, 8, 0));
+        world.drones_mut()[0].health = 0;
+        let mut enemies = world.enemies().to_vec();
+        enemies[0].health = 0;
+        world.set_enemies(enemies);
+        let mut warriors = world.warriors().to_vec();
+        warriors[0].health = 0;
+        world.set_warriors(warriors);
+        world.step();
+        world.drain_combat_events();
+
+        let save = WorldSave::capture(&world, generator.seed());
+        let mut restored = generated_world(&save.generator());
+        WorldSave::from_json(&save.to_json().unwrap())
+            .unwrap()
+            .restore(&mut restored)
+            .unwrap();
+
+        let play_on = |world: &mut World| {
+            world.place_warrior(spot).unwrap();
+            world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(4, 1, 0));
+            for _ in 0..(2 * TICKS_PER_SECOND) {
+                world.step();
+            }
+            world.drain_combat_events()
+        };
+        let log = play_on(&mut world);
+        assert!(log.iter().any(|event| matches!(
+            event,
+            CombatEvent::Hit {
+                attacker: Combatant::Enemy(1),
+                target: Combatant::Drone(1),
+                ..
+            }
+        )));
+        assert_eq!(play_on(&mut restored), log);
+        assert_eq!(world.warriors()[0].id, 1);
+        assert_eq!(restored.warriors(), world.warriors());
+        assert_eq!(restored.next_ids(), world.next_ids());
+    }
+
+    #[test]
+    fn upgrades_version_six_without_fights() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![DronePose::new(
+            [1.0, 0.0, 0.0],
+            [1.0, 0.0],
+            "d1",
+            10,
+            10,
+        )]);
+        let mut document =
+            serde_json::to_value(WorldSave::capture(&world, generator.seed())).unwrap();
+        document["version"] = JsonValue::from(6);
+        let fields = document.as_object_mut().unwrap();
+        for field in ["drops", "enemies", "waves"] {
+            fields.remove(field);
+        }
+        document["drones"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("armour");
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.drones[0].armour, 0);
+        assert!(save.drops.is_empty());
+        assert!(save.enemies.is_empty());
+        assert_eq!(save.waves, None);
+    }
+
+    #[test]
+    fn upgrades_version_seven_ids() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        world.set_drones(vec![
+            DronePose::new([1.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([2.0, 0.0, 0.0], [1.0, 0.0], "d2", 10, 10),
+        ]);
+        world.set_warriors(vec![Warrior::new(4, WorldCoord::new(1, 1, 0))]);
+        world.set_enemies(vec![Enemy::new(
+            2,
+            EnemyKind::Brute,
+            WorldCoord::new(5, 5, 0),
+        )]);
+        let mut document =
+            serde_json::to_value(WorldSave::capture(&world, generator.seed())).unwrap();
+        document["version"] = JsonValue::from(7);
+        document.as_object_mut().unwrap().remove("next_ids");
+        for drone in document["drones"].as_array_mut().unwrap() {
+            drone.as_object_mut().unwrap().remove("id");
+        }
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(
+            save.drones.iter().map(|drone| drone.id).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(
+            save.next_ids,
+            NextIds {
+                drone: 2,
+                enemy: 3,
+                warrior: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn finished_games_stay_finished() {
+        let generator = DeterministicMap::new(42);
+        let mut world = generated_world(&generator);
+        let floor = TileBox3::new(WorldCoord::new(0, 0, 0), WorldCoord::new(4, 4, 0)).unwrap();
+        for tile in floor.tiles() {
+            world.set_block(tile, AIR).unwrap();
+        }
+        let core = WorldCoord::new(2, 2, 0);
+        world.set_block(core, CORE).unwrap();
+        world.damage_core(core, CORE_MAX_HP - 1);
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(3, 2, 0));
+        world.step();
+        assert_eq!(world.phase(), GamePhase::Defeated);
+
+        let save = WorldSave::capture(&world, generator.seed());
+        let mut restored = generated_world(&save.generator());
+        WorldSave::from_json(&save.to_json().unwrap())
+            .unwrap()
+            .restore(&mut restored)
+            .unwrap();
+
+        assert_eq!(restored.phase(), GamePhase::Defeated);
+        restored.step();
+        assert_eq!(restored.tick, world.tick);
+    }
+
+    #[test]
+    fn upgrades_version_eight_as_running() {
+        let generator = DeterministicMap::new(42);
+        let world = generated_world(&generator);
+        let mut document =
+            serde_json::to_value(WorldSave::capture(&world, generator.seed())).unwrap();
+        document["version"] = JsonValue::from(8);
+        document.as_object_mut().unwrap().remove("phase");
+
+        let save = WorldSave::from_json(&document.to_string()).unwrap();
+
+        assert_eq!(save.phase, GamePhase::Running);
+    }
+
+    #[test]
+    fn migrations_run_in_order_up_to_target() {
+        fn rename_seed(document: &mut JsonValue) -> Result<(), SaveError> {
+            let seed = document["map_seed"].take();
+            document["seed"] = seed;
+            Ok(())
+        }
+        fn add_tick(document: &mut JsonValue) -> Result<(), SaveError> {
+            document["tick"] = JsonValue::from(0);
+            Ok(())
+        }
+
+        let mut document = serde_json::json!({ "version": 1, "map_seed": 9 });
+        migrate(&mut document, &[rename_seed, add_tick], 3).unwrap();
+
+        assert_eq!(document["version"], 3);
+        assert_eq!(document["seed"], 9);
+        assert_eq!(document["tick"], 0);
+    }
+
+    #[test]
+    fn rejects_newer_and_unversioned_saves() {
+        let newer = WorldSave::from_json(r#"{ "version": 99 }"#).unwrap_err();
+        assert_eq!(newer.reason, SaveErrorReason::UnsupportedVersion(99));
+
+        let unversioned = WorldSave::from_json(r#"{ "seed": 1 }"#).unwrap_err();
+        assert_eq!(unversioned.reason, SaveErrorReason::MissingVersion);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::chunk_cache::ChunkCache;
+use crate::coordinates::{TileBox3, WorldCoord};
+use crate::drone::DronePose;
+use crate::job::{JobId, MineBoxJob};
+use crate::order::{DigOrder, MoveOrder, Order, OrderError, OrderErrorReason, OrderEvent};
+use crate::world::TICKS_PER_SECOND;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// Ticks an idle drone waits before looking at jobs again after finding nothing it could reach.
+pub const RETRY_TICKS: u64 = TICKS_PER_SECOND as u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DroneStatus {
+    #[default]
+    Idle,
+    Thinking,
+    Working,
+    Finished,
+}
+
+impl DroneStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            DroneStatus::Idle => "idle",
+            DroneStatus::Thinking => "thinking",
+            DroneStatus::Working => "working",
+            DroneStatus::Finished => "finished",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct DroneSlot {
+    job: Option<JobId>,
+    finished: bool,
+    retry_at_tick: u64,
+}
+
+/// Global job list plus the bookkeeping that decides which drone works on what.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scheduler {
+    jobs: Vec<MineBoxJob>,
+    slots: Vec<DroneSlot>,
+    next_job_id: u32,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn jobs(&self) -> &[MineBoxJob] {
+        &self.jobs
+    }
+
+    pub fn job(&self, job_id: JobId) -> Option<&MineBoxJob> {
+        self.jobs.iter().find(|job| job.id == job_id)
+    }
+
+    /// How many drones the scheduler keeps track of.
+    pub fn drone_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn drone_job(&self, drone_index: usize) -> Option<JobId> {
+        self.slots.get(drone_index).and_then(|slot| slot.job)
+    }
+
+    pub fn add_job(&mut self, tile_box: TileBox3, chunk_cache: &ChunkCache) -> JobId {
+        let id = JobId(self.next_job_id);
+        self.next_job_id += 1;
+        self.jobs.push(MineBoxJob::new(id, tile_box, chunk_cache));
+        id
+    }
+
+    pub fn status(&self, drone_index: usize, orders: &VecDeque<Order>) -> DroneStatus {
+        let Some(slot) = self.slots.get(drone_index) else {
+            return DroneStatus::Idle;
+        };
+        match slot.job.and_then(|job_id| self.job(job_id)) {
+            Some(job) if job.tile_assigned_to(drone_index).is_some() => DroneStatus::Working,
+            Some(_) => DroneStatus::Thinking,
+            None if !orders.is_empty() => DroneStatus::Working,
+            None if slot.finished => DroneStatus::Finished,
+            None => DroneStatus::Idle,
+        }
+    }
+
+    pub(crate) fn set_drone_count(&mut self, drone_count: usize) {
+        for drone_index in 0..self.slots.len() {
+            self.release(drone_index);
+        }
+        self.slots = vec![DroneSlot::default(); drone_count];
+    }
+
+    pub(crate) fn add_drone(&mut self) {
+        self.slots.push(DroneSlot::default());
+    }
+
+    /// Drops the drone's slot, handing its tile back and renumbering the drones after it.
+    pub(crate) fn remove_drone(&mut self, drone_index: usize) {
+        if drone_index >= self.slots.len() {
+            return;
+        }
+        self.release(drone_index);
+        self.slots.remove(drone_index);
+        for job in &mut self.jobs {
+            job.forget_drone(drone_index);
+        }
+    }
+
+    pub(crate) fn assign(&mut self, drone_index: usize, job_id: JobId) -> Result<(), OrderError> {
+        if self.job(job_id).is_none() {
+            return Err(OrderError::new(OrderErrorReason::UnknownJob));
+        }
+        self.release(drone_index);
+        let slot = self
+            .slots
+            .get_mut(drone_index)
+            .ok_or_else(|| OrderError::new(OrderErrorReason::UnknownDrone))?;
+        slot.job = Some(job_id);
+        Ok(())
+    }
+
+    /// Takes the drone off its job, handing its tile back to the job's pending pool.
+    pub(crate) fn release(&mut self, drone_index: usize) {
+        let Some(slot) = self.slots.get_mut(drone_index) else {
+            return;
+        };
+        slot.finished = false;
+        let Some(job_id) = slot.job.take() else {
+            return;
+        };
+        if let Some(job) = self.job_mut(job_id) {
+            job.release(drone_index);
+        }
+    }
+
+    /// Feeds this tick's order events to the jobs, before any drone has been removed from
+    /// `drones`.
+    pub(crate) fn apply_events(&mut self, events: &[OrderEvent], drones: &[DronePose]) {
+        for event in events {
+            let Some(drone_index) = drones.iter().position(|drone| drone.id == event.drone())
+            else {
+                continue;
+            };
+            let Some(job_id) = self.drone_job(drone_index) else {
+                continue;
+            };
+            if let Some(job) = self.job_mut(job_id) {
+                job.apply_event(drone_index, event);
+            }
+        }
+    }
+
+    /// Frees dead drones' work, sends idle drones to the nearest open job and queues the next
+    /// tile for every job drone without orders.
+    pub(crate) fn schedule(
+        &mut self,
+        tick: u64,
+        drones: &mut [DronePose],
+        orders: &mut [VecDeque<Order>],
+        chunk_cache: &mut ChunkCache,
+    ) {
+        for (drone_index, drone) in drones.iter().enumerate() {
+            if drone.health <= 0 {
+                orders[drone_index].clear();
+                self.release(drone_index);
+            }
+        }
+
+        let mut reserved: HashSet<WorldCoord> = orders
+            .iter()
+            .flatten()
+            .filter_map(|order| match order {
+                Order::Dig(dig) => Some(dig.target_tile),
+                Order::Move(_) | Order::Craft(_) => None,
+            })
+            .collect();
+
+        for drone_index in 0..drones.len() {
+            let drone = &drones[drone_index];
+            if drone.health <= 0 || !orders[drone_index].is_empty() {
+                continue;
+            }
+            if self.slots[drone_index].job.is_none() {
+                if tick < self.slots[drone_index].retry_at_tick {
+                    continue;
+                }
+                let Some(job_id) = self.nearest_open_job(drone.tile()) else {
+                    continue;
+                };
+                let slot = &mut self.slots[drone_index];
+                slot.job = Some(job_id);
+                slot.finished = false;
+            }
+
+            let Some(job_id) = self.slots[drone_index].job else {
+                continue;
+            };
+            let Some(job) = self.jobs.iter_mut().find(|job| job.id == job_id) else {
+                self.slots[drone_index].job = None;
+                continue;
+            };
+            let assignment = job.next_assignment(chunk_cache, drone_index, drone.tile(), |tile| {
+                !reserved.contains(&tile)
+            });
+            let Some(assignment) = assignment else {
+                // With no pending tiles left the drone's share is done, even if others are
+                // still digging; otherwise the rest is out of reach for now.
+                let finished = job.progress().pending == 0;
+                let slot = &mut self.slots[drone_index];
+                slot.job = None;
+                slot.finished = finished;
+                if !finished {
+                    slot.retry_at_tick = tick + RETRY_TICKS;
+                }
+                continue;
+            };
+
+            reserved.insert(assignment.tile);
+            let queue = &mut orders[drone_index];
+            if let Some(order) = MoveOrder::along_path(drone.position, assignment.route) {
+                queue.push_back(Order::Move(order));
+            }
+            queue.push_back(Order::Dig(DigOrder::new(assignment.tile)));
+            if let Some(first) = queue.front() {
+                face_order_target(&mut drones[drone_index], first);
+            }
+        }
+    }
+
+    fn job_mut(&mut self, job_id: JobId) -> Option<&mut MineBoxJob> {
+        self.jobs.iter_mut().find(|job| job.id == job_id)
+    }
+
+    /// The unfinished job with pending tiles whose box lies closest to `tile`.
+    fn nearest_open_job(&self, tile: WorldCoord) -> Option<JobId> {
+        self.jobs
+            .iter()
+            .filter(|job| job.progress().pending > 0)
+            .min_by_key(|job| (distance_squared_to_box(tile, job.tile_box), job.id))
+            .map(|job| job.id)
+    }
+}
+
+pub(crate) fn face_order_target(drone: &mut DronePose, order: &Order) {
+    match order {
+        Order::Move(move_order) => {
+            let direction = move_order.direction();
+            drone.face_towards(direction[0], direction[1]);
+        }
+        Order::Dig(dig_order) => {
+            let tile = drone.tile();
+            drone.face_towards(
+                (dig_order.target_tile.x - tile.x) as f32,
+                (dig_order.target_tile.y - tile.y) as f32,
+            );
+        }
+        Order::Craft(_) => {}
+    }
+}
+
+fn distance_squared_to_box(tile: WorldCoord, tile_box: TileBox3) -> i64 {
+    let axis = |value: i32, min: i32, max: i32| (value.clamp(min, max) - value) as i64;
+    let dx = axis(tile.x, tile_box.min.x, tile_box.max.x);
+    let dy = axis(tile.y, tile_box.min.y, tile_box.max.y);
+    let dz = axis(tile.z, tile_box.min.z, tile_box.max.z);
+    dx * dx + dy * dy + dz * dz
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, BlockId, STONE};
+    use crate::chunk::Chunk;
+    use crate::chunk_cache::CachedChunk;
+    use crate::coordinates::{ChunkPosition, LocalBlockCoord};
+    use crate::world::World;
+
+    fn load_blocks(world: &mut World, blocks: &[(WorldCoord, BlockId)]) {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0, 0), AIR);
+        for &(coord, block) in blocks {
+            let local = LocalBlockCoord::new(coord.x as usize, coord.y as usize, coord.z as usize);
+            chunk.set_block(local, block).unwrap();
+        }
+        world
+            .chunk_cache_mut()
+            .insert_chunk(CachedChunk::from_chunk(&chunk));
+    }
+
+    fn stone_box(
+        min: (i32, i32, i32),
+        max: (i32, i32, i32),
+    ) -> (TileBox3, Vec<(WorldCoord, BlockId)>) {
+        let tile_box = TileBox3::new(
+            WorldCoord::new(min.0, min.1, min.2),
+            WorldCoord::new(max.0, max.1, max.2),
+        )
+        .unwrap();
+        let blocks = tile_box.tiles().map(|coord| (coord, STONE)).collect();
+        (tile_box, blocks)
+    }
+
+    fn world_with_drones(positions: &[[f32; 3]]) -> World {
+        let mut world = World::new();
+        world.set_drones(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(index, &position)| {
+                    DronePose::new(position, [1.0, 0.0], format!("d{}", index + 1), 10, 10)
+                })
+                .collect(),
+        );
+        world
+    }
+
+    fn dig_targets(world: &World) -> Vec<WorldCoord> {
+        (0..world.drones().len())
+            .flat_map(|index| world.queued_orders(index))
+            .filter_map(|order| match order {
+                Order::Dig(dig) => Some(dig.target_tile),
+                Order::Move(_) | Order::Craft(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn idle_drones_join_the_nearest_job() {
+        let (near_a, mut blocks) = stone_box((2, 2, 0), (3, 3, 0));
+        let (near_b, far_blocks) = stone_box((20, 20, 0), (21, 21, 0));
+        blocks.extend(far_blocks);
+        let mut world = world_with_drones(&[[18.0, 18.0, 0.0], [0.0, 0.0, 0.0]]);
+        load_blocks(&mut world, &blocks);
+
+        let job_a = world.add_mine_job(near_a);
+        let job_b = world.add_mine_job(near_b);
+        assert_eq!(world.drone_status(0), DroneStatus::Idle);
+        world.step();
+
+        assert_eq!(world.drone_job(0), Some(job_b));
+        assert_eq!(world.drone_job(1), Some(job_a));
+        assert_eq!(world.drone_status(0), DroneStatus::Working);
+    }
+
+    #[test]
+    fn drones_never_dig_the_same_tile() {
+        let (tile_box, blocks) = stone_box((3, 3, 0), (6, 6, 0));
+        let mut world = world_with_drones(&[[1.0, 1.0, 0.0], [8.0, 8.0, 0.0], [1.0, 8.0, 0.0]]);
+        load_blocks(&mut world, &blocks);
+        let job_id = world.add_mine_job(tile_box);
+
+        for _ in 0..(40 * TICKS_PER_SECOND) {
+            world.step();
+            let targets = dig_targets(&world);
+            let unique: HashSet<WorldCoord> = targets.iter().copied().collect();
+            assert_eq!(unique.len(), targets.len());
+        }
+
+        assert!(world.job(job_id).unwrap().is_finished());
+        assert!(
+            tile_box
+                .tiles()
+                .all(|coord| world.block_at(coord) == Some(AIR))
+        );
+        for drone_index in 0..3 {
+            assert_eq!(world.drone_status(drone_index), DroneStatus::Finished);
+        }
+    }
+
+    #[test]
+    fn manual_dig_on_reserved_tile_is_rejected() {
+        let tile = WorldCoord::new(2, 1, 0);
+        let mut world = world_with_drones(&[[1.0, 1.0, 0.0], [3.0, 1.0, 0.0]]);
+        load_blocks(&mut world, &[(tile, STONE)]);
+        let job_id = world.add_mine_job(TileBox3::new(tile, tile).unwrap());
+        world.assign_job(0, job_id).unwrap();
+        world.step();
+        assert_eq!(world.tile_reserved_by(tile), Some(0));
+
+        let err = world
+            .issue_order(1, Order::Dig(DigOrder::new(tile)))
+            .unwrap_err();
+        assert_eq!(err.reason, OrderErrorReason::TileReserved);
+    }
+
+    #[test]
+    fn dead_drone_work_goes_to_another_drone() {
+        let tile = WorldCoord::new(4, 1, 0);
+        let mut world = world_with_drones(&[[3.0, 1.0, 0.0], [10.0, 1.0, 0.0]]);
+        load_blocks(&mut world, &[(tile, STONE)]);
+        let job_id = world.add_mine_job(TileBox3::new(tile, tile).unwrap());
+        world.assign_job(0, job_id).unwrap();
+        world.step();
+        assert_eq!(world.tile_reserved_by(tile), Some(0));
+
+        world.drones_mut()[0].health = 0;
+        world.step();
+
+        // The dead drone is gone and the survivor moved down to index 0.
+        assert_eq!(world.drones().len(), 1);
+        assert_eq!(world.drone_job(0), Some(job_id));
+        assert_eq!(world.tile_reserved_by(tile), Some(0));
+    }
+
+    #[test]
+    fn blocked_route_is_planned_again() {
+        let tile = WorldCoord::new(10, 1, 0);
+        let mut world = world_with_drones(&[[1.0, 1.0, 0.0]]);
+        load_blocks(&mut world, &[(tile, STONE)]);
+        let job_id = world.add_mine_job(TileBox3::new(tile, tile).unwrap());
+        world.assign_job(0, job_id).unwrap();
+        assert_eq!(world.drone_status(0), DroneStatus::Thinking);
+        world.step();
+
+        // Wall off the straight route while the drone is on its way.
+        for y in 0..=2 {
+            world.set_block(WorldCoord::new(5, y, 0), STONE).unwrap();
+        }
+        let mut blocked = false;
+        for _ in 0..(20 * TICKS_PER_SECOND) {
+            world.step();
+            blocked |= world.drain_order_events().iter().any(|event| {
+                matches!(event, OrderEvent::Failed { error, .. } if error.reason == OrderErrorReason::PathBlocked)
+            });
+        }
+
+        assert!(blocked);
+        assert!(world.job(job_id).unwrap().is_finished());
+        assert_eq!(world.block_at(tile), Some(AIR));
+    }
+}
@@ -0,0 +1,894 @@
+use crate::block::BlockId;
+use crate::coordinates::{TileBox3, WorldCoord};
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+pub const SCRIPT_VERSION: u32 = 1;
+pub const MAX_LOOP_ITERATIONS: u64 = 100_000;
+
+const ROOT_PATH: &str = "$";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgramNode {
+    Program,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Program {
+    pub version: u32,
+    pub node: ProgramNode,
+    pub statements: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScriptType {
+    Int,
+    Bool,
+    TileCoord3,
+    TileBox3,
+    Unit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileCoord3Literal {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl From<TileCoord3Literal> for WorldCoord {
+    fn from(literal: TileCoord3Literal) -> Self {
+        WorldCoord::new(literal.x, literal.y, literal.z)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "node")]
+pub enum Stmt {
+    Let {
+        name: String,
+        ty: ScriptType,
+        value: Expr,
+    },
+    ExprStmt {
+        expr: Expr,
+    },
+    ForIn {
+        var: String,
+        iter: Expr,
+        body: Vec<Stmt>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "node")]
+pub enum Expr {
+    Int {
+        value: i64,
+    },
+    Bool {
+        value: bool,
+    },
+    VarRef {
+        name: String,
+    },
+    TileCoord3 {
+        x: i32,
+        y: i32,
+        z: i32,
+    },
+    TileBox3 {
+        min: TileCoord3Literal,
+        max: TileCoord3Literal,
+    },
+    TileBoxFromCoords {
+        min: Box<Expr>,
+        max: Box<Expr>,
+    },
+    Call {
+        func: String,
+        #[serde(default)]
+        args: Vec<Expr>,
+    },
+    IterTiles {
+        #[serde(rename = "box")]
+        tile_box: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    TileCoord3(WorldCoord),
+    TileBox3(TileBox3),
+    Unit,
+}
+
+impl Value {
+    pub fn ty(&self) -> ScriptType {
+        match self {
+            Value::Int(_) => ScriptType::Int,
+            Value::Bool(_) => ScriptType::Bool,
+            Value::TileCoord3(_) => ScriptType::TileCoord3,
+            Value::TileBox3(_) => ScriptType::TileBox3,
+            Value::Unit => ScriptType::Unit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptErrorReason {
+    Parse(String),
+    UnsupportedVersion(u32),
+    UnknownVariable(String),
+    DuplicateVariable(String),
+    UnknownFunction(String),
+    ArgumentCount {
+        expected: usize,
+        found: usize,
+    },
+    TypeMismatch {
+        expected: ScriptType,
+        found: ScriptType,
+    },
+    NotIterable,
+    InvalidBox,
+    IterationLimit,
+    Host(String),
+}
+
+/// Validation or runtime failure, tagged with the JSON path (`$.statements[0].value`)
+/// of the node that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub path: String,
+    pub reason: ScriptErrorReason,
+}
+
+impl ScriptError {
+    pub fn new(path: impl Into<String>, reason: ScriptErrorReason) -> Self {
+        Self {
+            path: path.into(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.reason {
+            ScriptErrorReason::Parse(message) => write!(f, "invalid program ({message})"),
+            ScriptErrorReason::UnsupportedVersion(version) => {
+                write!(f, "unsupported script version {version}")
+            }
+            ScriptErrorReason::UnknownVariable(name) => write!(f, "unknown variable `{name}`"),
+            ScriptErrorReason::DuplicateVariable(name) => {
+                write!(f, "variable `{name}` is already defined")
+            }
+            ScriptErrorReason::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            ScriptErrorReason::ArgumentCount { expected, found } => {
+                write!(f, "expected {expected} arguments, found {found}")
+            }
+            ScriptErrorReason::TypeMismatch { expected, found } => {
+                write!(f, "expected {expected:?}, found {found:?}")
+            }
+            ScriptErrorReason::NotIterable => f.write_str("ForIn expects an IterTiles source"),
+            ScriptErrorReason::InvalidBox => f.write_str("box min must not exceed max"),
+            ScriptErrorReason::IterationLimit => {
+                write!(f, "program exceeded {MAX_LOOP_ITERATIONS} loop iterations")
+            }
+            ScriptErrorReason::Host(message) => write!(f, "host call failed ({message})"),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+/// Game state a script may touch through host functions.
+pub struct HostContext<'a> {
+    pub world: &'a mut World,
+}
+
+pub type HostFn =
+    Box<dyn Fn(&mut HostContext<'_>, &[Value]) -> Result<Value, String> + Send + Sync + 'static>;
+
+pub struct HostFunction {
+    pub params: Vec<ScriptType>,
+    pub returns: ScriptType,
+    call: HostFn,
+}
+
+#[derive(Default)]
+pub struct HostRegistry {
+    functions: HashMap<String, HostFunction>,
+}
+
+impl HostRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry preloaded with read-only queries that every host supports.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            "block_at",
+            vec![ScriptType::TileCoord3],
+            ScriptType::Int,
+            |ctx, args| match args {
+                [Value::TileCoord3(coord)] => ctx
+                    .world
+                    .block_at(*coord)
+                    .map(|block: BlockId| Value::Int(block as i64))
+                    .ok_or_else(|| "tile is not loaded".to_string()),
+                _ => Err("block_at expects a TileCoord3".to_string()),
+            },
+        );
+        registry.register(
+            "tile_count",
+            vec![ScriptType::TileBox3],
+            ScriptType::Int,
+            |_, args| match args {
+                [Value::TileBox3(tile_box)] => Ok(Value::Int(tile_box.tile_count() as i64)),
+                _ => Err("tile_count expects a TileBox3".to_string()),
+            },
+        );
+        registry.register(
+            "mine_box",
+            vec![ScriptType::TileBox3],
+            ScriptType::Unit,
+            |ctx, args| match args {
+                [Value::TileBox3(tile_box)] => {
+                    ctx.world.add_mine_job(*tile_box);
+                    Ok(Value::Unit)
+                }
+                _ => Err("mine_box expects a TileBox3".to_string()),
+            },
+        );
+        registry
+    }
+
+    pub fn register<F>(
+        &mut self,
+        name: impl Into<String>,
+        params: Vec<ScriptType>,
+        returns: ScriptType,
+        call: F,
+    ) where
+        F: Fn(&mut HostContext<'_>, &[Value]) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.functions.insert(
+            name.into(),
+            HostFunction {
+                params,
+                returns,
+                call: Box::new(call),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HostFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+
+#[derive(Deserialize)]
+struct ProgramHeader {
+    version: u32,
+    node: ProgramNode,
+}
+
+/// Parses a program, reporting shape errors at the statement that failed to decode.
+pub fn parse_program(source: &str) -> Result<Program, ScriptError> {
+    let value: serde_json::Value = serde_json::from_str(source)
+        .map_err(|err| ScriptError::new(ROOT_PATH, ScriptErrorReason::Parse(err.to_string())))?;
+    let serde_json::Value::Object(mut fields) = value else {
+        return Err(ScriptError::new(
+            ROOT_PATH,
+            ScriptErrorReason::Parse("expected a Program object".to_string()),
+        ));
+    };
+
+    let Some(serde_json::Value::Array(items)) = fields.remove("statements") else {
+        return Err(ScriptError::new(
+            "$.statements",
+            ScriptErrorReason::Parse("expected an array of statements".to_string()),
+        ));
+    };
+
+    let header: ProgramHeader = serde_json::from_value(serde_json::Value::Object(fields))
+        .map_err(|err| ScriptError::new(ROOT_PATH, ScriptErrorReason::Parse(err.to_string())))?;
+
+    let statements = items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            serde_json::from_value(item).map_err(|err| {
+                ScriptError::new(
+                    format!("$.statements[{index}]"),
+                    ScriptErrorReason::Parse(err.to_string()),
+                )
+            })
+        })
+        .collect::<Result<Vec<Stmt>, ScriptError>>()?;
+
+    Ok(Program {
+        version: header.version,
+        node: header.node,
+        statements,
+    })
+}
+
+/// Resolves names and checks types without touching any game state.
+pub fn validate_program(program: &Program, registry: &HostRegistry) -> Result<(), ScriptError> {
+    if program.version != SCRIPT_VERSION {
+        return Err(ScriptError::new(
+            "$.version",
+            ScriptErrorReason::UnsupportedVersion(program.version),
+        ));
+    }
+
+    let mut scopes = Scopes::new();
+    check_block(&program.statements, "$.statements", &mut scopes, registry)
+}
+
+/// Validates and then runs `program`, dispatching calls through `registry`.
+pub fn execute_program(
+    program: &Program,
+    registry: &HostRegistry,
+    ctx: &mut HostContext<'_>,
+) -> Result<(), ScriptError> {
+    validate_program(program, registry)?;
+
+    let mut interpreter = Interpreter {
+        registry,
+        scopes: Scopes::new(),
+        iterations: 0,
+    };
+    interpreter.run_block(&program.statements, "$.statements", ctx)
+}
+
+pub fn run_program_json(
+    source: &str,
+    registry: &HostRegistry,
+    ctx: &mut HostContext<'_>,
+) -> Result<(), ScriptError> {
+    let program = parse_program(source)?;
+    execute_program(&program, registry, ctx)
+}
+
+struct Scopes<T> {
+    frames: Vec<HashMap<String, T>>,
+}
+
+impl<T: Copy> Scopes<T> {
+    fn new() -> Self {
+        Self {
+            frames: vec![HashMap::new()],
+        }
+    }
+
+    fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    fn lookup(&self, name: &str) -> Option<T> {
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(name).copied())
+    }
+
+    fn define(&mut self, name: &str, value: T) -> bool {
+        let frame = self.frames.last_mut().expect("scope stack is never empty");
+        if frame.contains_key(name) {
+            return false;
+        }
+        frame.insert(name.to_string(), value);
+        true
+    }
+}
+
+fn expect_type(path: &str, expected: ScriptType, found: ScriptType) -> Result<(), ScriptError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ScriptError::new(
+            path,
+            ScriptErrorReason::TypeMismatch { expected, found },
+        ))
+    }
+}
+
+fn check_block(
+    statements: &[Stmt],
+    path: &str,
+    scopes: &mut Scopes<ScriptType>,
+    registry: &HostRegistry,
+) -> Result<(), ScriptError> {
+    for (index, stmt) in statements.iter().enumerate() {
+        check_stmt(stmt, &format!("{path}[{index}]"), scopes, registry)?;
+    }
+    Ok(())
+}
+
+fn check_stmt(
+    stmt: &Stmt,
+    path: &str,
+    scopes: &mut Scopes<ScriptType>,
+    registry: &HostRegistry,
+) -> Result<(), ScriptError> {
+    match stmt {
+        Stmt::Let { name, ty, value } => {
+            let value_path = format!("{path}.value");
+            let found = type_of(value, &value_path, scopes, registry)?;
+            expect_type(&value_path, *ty, found)?;
+            if !scopes.define(name, *ty) {
+                return Err(ScriptError::new(
+                    format!("{path}.name"),
+                    ScriptErrorReason::DuplicateVariable(name.clone()),
+                ));
+            }
+            Ok(())
+        }
+        Stmt::ExprStmt { expr } => {
+            type_of(expr, &format!("{path}.expr"), scopes, registry)?;
+            Ok(())
+        }
+        Stmt::ForIn { var, iter, body } => {
+            let iter_path = format!("{path}.iter");
+            let Expr::IterTiles { tile_box } = iter else {
+                return Err(ScriptError::new(iter_path, ScriptErrorReason::NotIterable));
+            };
+            let box_path = format!("{iter_path}.box");
+            let found = type_of(tile_box, &box_path, scopes, registry)?;
+            expect_type(&box_path, ScriptType::TileBox3, found)?;
+
+            scopes.push();
+            scopes.define(var, ScriptType::TileCoord3);
+            let result = check_block(body, &format!("{path}.body"), scopes, registry);
+            scopes.pop();
+            result
+        }
+    }
+}
+
+fn type_of(
+    expr: &Expr,
+    path: &str,
+    scopes: &Scopes<ScriptType>,
+    registry: &HostRegistry,
+) -> Result<ScriptType, ScriptError> {
+    match expr {
+        Expr::Int { .. } => Ok(ScriptType::Int),
+        Expr::Bool { .. } => Ok(ScriptType::Bool),
+        Expr::TileCoord3 { .. } => Ok(ScriptType::TileCoord3),
+        Expr::VarRef { name } => scopes.lookup(name).ok_or_else(|| {
+            ScriptError::new(path, ScriptErrorReason::UnknownVariable(name.clone()))
+        }),
+        Expr::TileBox3 { min, max } => {
+            TileBox3::new((*min).into(), (*max).into())
+                .ok_or_else(|| ScriptError::new(path, ScriptErrorReason::InvalidBox))?;
+            Ok(ScriptType::TileBox3)
+        }
+        Expr::TileBoxFromCoords { min, max } => {
+            let min_path = format!("{path}.min");
+            let max_path = format!("{path}.max");
+            let min_type = type_of(min, &min_path, scopes, registry)?;
+            expect_type(&min_path, ScriptType::TileCoord3, min_type)?;
+            let max_type = type_of(max, &max_path, scopes, registry)?;
+            expect_type(&max_path, ScriptType::TileCoord3, max_type)?;
+
+            if let (Some(min), Some(max)) = (literal_coord(min), literal_coord(max))
+                && TileBox3::new(min, max).is_none()
+            {
+                return Err(ScriptError::new(path, ScriptErrorReason::InvalidBox));
+            }
+            Ok(ScriptType::TileBox3)
+        }
+        Expr::Call { func, args } => {
+            let function = registry.get(func).ok_or_else(|| {
+                ScriptError::new(
+                    format!("{path}.func"),
+                    ScriptErrorReason::UnknownFunction(func.clone()),
+                )
+            })?;
+            if function.params.len() != args.len() {
+                return Err(ScriptError::new(
+                    format!("{path}.args"),
+                    ScriptErrorReason::ArgumentCount {
+                        expected: function.params.len(),
+                        found: args.len(),
+                    },
+                ));
+            }
+            for (index, (arg, expected)) in args.iter().zip(&function.params).enumerate() {
+                let arg_path = format!("{path}.args[{index}]");
+                let found = type_of(arg, &arg_path, scopes, registry)?;
+                expect_type(&arg_path, *expected, found)?;
+            }
+            Ok(function.returns)
+        }
+        Expr::IterTiles { .. } => Err(ScriptError::new(path, ScriptErrorReason::NotIterable)),
+    }
+}
+
+fn literal_coord(expr: &Expr) -> Option<WorldCoord> {
+    match expr {
+        Expr::TileCoord3 { x, y, z } => Some(WorldCoord::new(*x, *y, *z)),
+        _ => None,
+    }
+}
+
+struct Interpreter<'r> {
+    registry: &'r HostRegistry,
+    scopes: Scopes<Value>,
+    iterations: u64,
+}
+
+impl Interpreter<'_> {
+    fn run_block(
+        &mut self,
+        statements: &[Stmt],
+        path: &str,
+        ctx: &mut HostContext<'_>,
+    ) -> Result<(), ScriptError> {
+        for (index, stmt) in statements.iter().enumerate() {
+            self.run_stmt(stmt, &format!("{path}[{index}]"), ctx)?;
+        }
+        Ok(())
+    }
+
+    fn run_stmt(
+        &mut self,
+        stmt: &Stmt,
+        path: &str,
+        ctx: &mut HostContext<'_>,
+    ) -> Result<(), ScriptError> {
+        match stmt {
+            Stmt::Let { name, value, .. } => {
+                let value = self.eval(value, &format!("{path}.value"), ctx)?;
+                self.scopes.define(name, value);
+                Ok(())
+            }
+            Stmt::ExprStmt { expr } => {
+                self.eval(expr, &format!("{path}.expr"), ctx)?;
+                Ok(())
+            }
+            Stmt::ForIn { var, iter, body } => {
+                let Expr::IterTiles { tile_box } = iter else {
+                    return Err(ScriptError::new(
+                        format!("{path}.iter"),
+                        ScriptErrorReason::NotIterable,
+                    ));
+                };
+                let box_path = format!("{path}.iter.box");
+                let Value::TileBox3(tile_box) = self.eval(tile_box, &box_path, ctx)? else {
+                    return Err(ScriptError::new(box_path, ScriptErrorReason::NotIterable));
+                };
+
+                let body_path = format!("{path}.body");
+                for tile in tile_box.tiles() {
+                    self.iterations += 1;
+                    if self.iterations > MAX_LOOP_ITERATIONS {
+                        return Err(ScriptError::new(path, ScriptErrorReason::IterationLimit));
+                    }
+
+                    self.scopes.push();
+                    self.scopes.define(var, Value::TileCoord3(tile));
+                    let result = self.run_block(body, &body_path, ctx);
+                    self.scopes.pop();
+                    result?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn eval(
+        &mut self,
+        expr: &Expr,
+        path: &str,
+        ctx: &mut HostContext<'_>,
+    ) -> Result<Value, ScriptError> {
+        match expr {
+            Expr::Int { value } => Ok(Value::Int(*value)),
+            Expr::Bool { value } => Ok(Value::Bool(*value)),
+            Expr::TileCoord3 { x, y, z } => Ok(Value::TileCoord3(WorldCoord::new(*x, *y, *z))),
+            Expr::VarRef { name } => self.scopes.lookup(name).ok_or_else(|| {
+                ScriptError::new(path, ScriptErrorReason::UnknownVariable(name.clone()))
+            }),
+            Expr::TileBox3 { min, max } => TileBox3::new((*min).into(), (*max).into())
+                .map(Value::TileBox3)
+                .ok_or_else(|| ScriptError::new(path, ScriptErrorReason::InvalidBox)),
+            Expr::TileBoxFromCoords { min, max } => {
+                let min = self.eval(min, &format!("{path}.min"), ctx)?;
+                let max = self.eval(max, &format!("{path}.max"), ctx)?;
+                match (min, max) {
+                    (Value::TileCoord3(min), Value::TileCoord3(max)) => TileBox3::new(min, max)
+                        .map(Value::TileBox3)
+                        .ok_or_else(|| ScriptError::new(path, ScriptErrorReason::InvalidBox)),
+                    _ => Err(ScriptError::new(path, ScriptErrorReason::InvalidBox)),
+                }
+            }
+            Expr::Call { func, args } => {
+                let function = self.registry.get(func).ok_or_else(|| {
+                    ScriptError::new(
+                        format!("{path}.func"),
+                        ScriptErrorReason::UnknownFunction(func.clone()),
+                    )
+                })?;
+                let mut values = Vec::with_capacity(args.len());
+                for (index, arg) in args.iter().enumerate() {
+                    values.push(self.eval(arg, &format!("{path}.args[{index}]"), ctx)?);
+                }
+                (function.call)(ctx, &values)
+                    .map_err(|message| ScriptError::new(path, ScriptErrorReason::Host(message)))
+            }
+            Expr::IterTiles { .. } => Err(ScriptError::new(path, ScriptErrorReason::NotIterable)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, STONE};
+    use crate::coordinates::ChunkPosition;
+    use crate::worldgen::DeterministicMap;
+    use std::sync::{Arc, Mutex};
+
+    const EXAMPLE_PROGRAM: &str = r#"{
+        "version": 1,
+        "node": "Program",
+        "statements": [
+            {
+                "node": "Let",
+                "name": "area",
+                "ty": "TileBox3",
+                "value": {
+                    "node": "TileBoxFromCoords",
+                    "min": { "node": "TileCoord3", "x": 10, "y": 5, "z": 0 },
+                    "max": { "node": "TileCoord3", "x": 20, "y": 7, "z": 0 }
+                }
+            },
+            {
+                "node": "ExprStmt",
+                "expr": {
+                    "node": "Call",
+                    "func": "mine_box",
+                    "args": [{ "node": "VarRef", "name": "area" }]
+                }
+            }
+        ]
+    }"#;
+
+    fn recording_registry(calls: Arc<Mutex<Vec<Value>>>) -> HostRegistry {
+        let mut registry = HostRegistry::with_builtins();
+        registry.register(
+            "mine_box",
+            vec![ScriptType::TileBox3],
+            ScriptType::Unit,
+            move |_, args| {
+                calls.lock().unwrap().extend_from_slice(args);
+                Ok(Value::Unit)
+            },
+        );
+        registry
+    }
+
+    fn world_with_terrain() -> World {
+        let mut world = World::new();
+        world
+            .chunk_cache_mut()
+            .populate_chunk_at(&DeterministicMap::new(7), ChunkPosition::new(0, 0, 0));
+        world
+    }
+
+    fn with_context<R>(run: impl FnOnce(&mut HostContext<'_>) -> R) -> R {
+        let mut world = world_with_terrain();
+        let mut ctx = HostContext { world: &mut world };
+        run(&mut ctx)
+    }
+
+    fn program_with(statements: &str) -> String {
+        format!(r#"{{ "version": 1, "node": "Program", "statements": [{statements}] }}"#)
+    }
+
+    #[test]
+    fn parses_design_doc_example() {
+        let program = parse_program(EXAMPLE_PROGRAM).expect("example should parse");
+
+        assert_eq!(program.version, SCRIPT_VERSION);
+        assert_eq!(program.statements.len(), 2);
+        assert!(matches!(
+            &program.statements[0],
+            Stmt::Let { name, ty: ScriptType::TileBox3, .. } if name == "area"
+        ));
+    }
+
+    #[test]
+    fn executes_host_call_with_resolved_box() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let registry = recording_registry(Arc::clone(&calls));
+
+        with_context(|ctx| run_program_json(EXAMPLE_PROGRAM, &registry, ctx)).unwrap();
+
+        let expected = TileBox3::new(WorldCoord::new(10, 5, 0), WorldCoord::new(20, 7, 0)).unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![Value::TileBox3(expected)]);
+    }
+
+    #[test]
+    fn reports_unknown_function_with_json_path() {
+        let source = EXAMPLE_PROGRAM.replace("mine_box", "build_box");
+        let program = parse_program(&source).unwrap();
+        let err = validate_program(&program, &HostRegistry::with_builtins()).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[1].expr.func");
+        assert_eq!(
+            err.reason,
+            ScriptErrorReason::UnknownFunction("build_box".to_string())
+        );
+    }
+
+    #[test]
+    fn mine_box_builtin_queues_a_job() {
+        let registry = HostRegistry::with_builtins();
+        let mut world = world_with_terrain();
+        let mut ctx = HostContext { world: &mut world };
+
+        run_program_json(EXAMPLE_PROGRAM, &registry, &mut ctx).unwrap();
+
+        let expected = TileBox3::new(WorldCoord::new(10, 5, 0), WorldCoord::new(20, 7, 0)).unwrap();
+        assert_eq!(world.jobs().len(), 1);
+        assert_eq!(world.jobs()[0].tile_box, expected);
+    }
+
+    #[test]
+    fn rejects_let_with_mismatched_type() {
+        let source = program_with(
+            r#"{ "node": "Let", "name": "n", "ty": "Bool", "value": { "node": "Int", "value": 3 } }"#,
+        );
+        let program = parse_program(&source).unwrap();
+        let err = validate_program(&program, &HostRegistry::new()).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[0].value");
+        assert_eq!(
+            err.reason,
+            ScriptErrorReason::TypeMismatch {
+                expected: ScriptType::Bool,
+                found: ScriptType::Int,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_inverted_literal_box() {
+        let source = program_with(
+            r#"{ "node": "Let", "name": "b", "ty": "TileBox3", "value": {
+                "node": "TileBox3",
+                "min": { "x": 3, "y": 0, "z": 0 },
+                "max": { "x": 1, "y": 0, "z": 0 }
+            } }"#,
+        );
+        let program = parse_program(&source).unwrap();
+        let err = validate_program(&program, &HostRegistry::new()).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[0].value");
+        assert_eq!(err.reason, ScriptErrorReason::InvalidBox);
+    }
+
+    #[test]
+    fn rejects_unknown_variable_in_call_argument() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let registry = recording_registry(calls);
+        let source = program_with(
+            r#"{ "node": "ExprStmt", "expr": {
+                "node": "Call", "func": "mine_box", "args": [{ "node": "VarRef", "name": "nope" }]
+            } }"#,
+        );
+        let program = parse_program(&source).unwrap();
+        let err = validate_program(&program, &registry).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[0].expr.args[0]");
+        assert_eq!(
+            err.reason,
+            ScriptErrorReason::UnknownVariable("nope".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let source = r#"{ "version": 2, "node": "Program", "statements": [] }"#;
+        let program = parse_program(source).unwrap();
+        let err = validate_program(&program, &HostRegistry::new()).unwrap_err();
+
+        assert_eq!(err.path, "$.version");
+        assert_eq!(err.reason, ScriptErrorReason::UnsupportedVersion(2));
+    }
+
+    #[test]
+    fn parse_errors_point_at_failing_statement() {
+        let source = program_with(
+            r#"{ "node": "ExprStmt", "expr": { "node": "Int", "value": 1 } },
+               { "node": "Loop", "body": [] }"#,
+        );
+        let err = parse_program(&source).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[1]");
+        assert!(matches!(err.reason, ScriptErrorReason::Parse(_)));
+    }
+
+    #[test]
+    fn for_in_visits_each_tile_and_scopes_loop_variable() {
+        let visited = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&visited);
+        let mut registry = HostRegistry::with_builtins();
+        registry.register(
+            "clear_tile",
+            vec![ScriptType::TileCoord3],
+            ScriptType::Unit,
+            move |ctx, args| {
+                let [Value::TileCoord3(coord)] = args else {
+                    return Err("expected a tile".to_string());
+                };
+                recorder.lock().unwrap().push(*coord);
+                ctx.world
+                    .set_block(*coord, AIR)
+                    .map(|_| Value::Unit)
+                    .map_err(|err| format!("{err:?}"))
+            },
+        );
+        let source = program_with(
+            r#"{ "node": "ForIn", "var": "t",
+                 "iter": { "node": "IterTiles", "box": { "node": "TileBox3",
+                     "min": { "x": 3, "y": 0, "z": 0 }, "max": { "x": 4, "y": 1, "z": 0 } } },
+                 "body": [{ "node": "ExprStmt", "expr": {
+                     "node": "Call", "func": "clear_tile", "args": [{ "node": "VarRef", "name": "t" }]
+                 } }] },
+               { "node": "ExprStmt", "expr": { "node": "VarRef", "name": "t" } }"#,
+        );
+        let program = parse_program(&source).unwrap();
+
+        let err = validate_program(&program, &registry).unwrap_err();
+        assert_eq!(err.path, "$.statements[1].expr");
+
+        let mut loop_only = program.clone();
+        loop_only.statements.truncate(1);
+        with_context(|ctx| {
+            ctx.world
+                .set_block(WorldCoord::new(3, 0, 0), STONE)
+                .unwrap();
+            execute_program(&loop_only, &registry, ctx).unwrap();
+            assert_eq!(ctx.world.block_at(WorldCoord::new(3, 0, 0)), Some(AIR));
+        });
+        assert_eq!(visited.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn host_failures_carry_call_path() {
+        let registry = HostRegistry::with_builtins();
+        let source = program_with(
+            r#"{ "node": "ExprStmt", "expr": { "node": "Call", "func": "block_at",
+                 "args": [{ "node": "TileCoord3", "x": 5000, "y": 0, "z": 0 }] } }"#,
+        );
+
+        let err = with_context(|ctx| run_program_json(&source, &registry, ctx)).unwrap_err();
+
+        assert_eq!(err.path, "$.statements[0].expr");
+        assert!(matches!(err.reason, ScriptErrorReason::Host(_)));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::coordinates::WorldCoord;
+use serde::{Deserialize, Serialize};
+
+/// Hit points of a freshly placed core.
+pub const CORE_MAX_HP: i32 = 100;
+
+/// A placed `CORE` block. Enemies wear it down; at zero HP the block is destroyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreStructure {
+    pub tile: WorldCoord,
+    pub hp: i32,
+    pub max_hp: i32,
+}
+
+impl CoreStructure {
+    pub fn new(tile: WorldCoord) -> Self {
+        Self {
+            tile,
+            hp: CORE_MAX_HP,
+            max_hp: CORE_MAX_HP,
+        }
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.hp <= 0
+    }
+
+    /// Takes `damage` and returns the HP left.
+    pub fn take_damage(&mut self, damage: i32) -> i32 {
+        self.hp = (self.hp - damage.max(0)).max(0);
+        self.hp
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructureEvent {
+    Damaged {
+        tile: WorldCoord,
+        damage: i32,
+        hp: i32,
+    },
+    Destroyed {
+        tile: WorldCoord,
+    },
+}
+
+/// Where a game stands. Once it leaves `Running` the world stops advancing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    #[default]
+    Running,
+    /// Enemies destroyed the last core.
+    Defeated,
+    /// Every wave was sent and every enemy is dead.
+    Victory,
+}
+
+impl GamePhase {
+    pub fn is_over(self) -> bool {
+        self != GamePhase::Running
+    }
+}
@@ -0,0 +1,14 @@
+use crate::block::STONE;
+use crate::coordinates::WorldCoord;
+use crate::world::World;
+use crate::worldgen::FlatGenerator;
+
+/// A world with the chunk at the origin loaded: open floor at z = 0 over solid stone.
+/// Neighbouring chunks are generated the same way once something reaches them.
+pub(crate) fn arena() -> World {
+    let mut world = World::with_generator(FlatGenerator::new(-1, STONE));
+    world
+        .chunk_cache_mut()
+        .load_block_at(WorldCoord::new(0, 0, 0));
+    world
+}
@@ -1,14 +1,15 @@
-use crate::block::{AIR, BlockId};
+use crate::block::{BlockId, BlockRegistry};
 use crate::chunk::ChunkError;
 use crate::chunk_cache::ChunkCache;
 use crate::coordinates::WorldCoord;
-use crate::inventory::{InventorySlots, remove_block_from_slot, slot_block};
+use crate::inventory::{InventorySlots, remove_item_from_slot, slot_item};
+use crate::item::{ItemId, ItemRegistry};
 use std::error::Error;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ToolSelection {
-    pub block: BlockId,
+    pub item: ItemId,
     pub slot_index: usize,
     pub remaining: u32,
 }
@@ -23,7 +24,9 @@ pub struct PlacementOutcome {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PlacementErrorReason {
+    UnknownDrone,
     NoSelection,
+    NotPlaceable,
     SlotEmpty,
     SlotMismatch,
     DifferentLevel,
@@ -46,7 +49,9 @@ impl PlacementError {
 
     pub fn message(&self) -> &'static str {
         match self.reason {
+            PlacementErrorReason::UnknownDrone => "drone does not exist",
             PlacementErrorReason::NoSelection => "select a block to place",
+            PlacementErrorReason::NotPlaceable => "selected item cannot be placed",
             PlacementErrorReason::SlotEmpty => "selected slot is empty",
             PlacementErrorReason::SlotMismatch => "selected slot changed",
             PlacementErrorReason::DifferentLevel => "target must be on the same level",
@@ -86,6 +91,12 @@ pub struct ToolController {
     max_distance: i32,
 }
 
+impl Default for ToolController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ToolController {
     pub fn new() -> Self {
         Self::with_max_distance(1)
@@ -111,8 +122,8 @@ impl ToolController {
         slots: &InventorySlots,
         slot_index: usize,
     ) -> Option<ToolSelection> {
-        let selection = slot_block(slots, slot_index).map(|(block, remaining)| ToolSelection {
-            block,
+        let selection = slot_item(slots, slot_index).map(|(item, remaining)| ToolSelection {
+            item,
             slot_index,
             remaining,
         });
@@ -122,9 +133,9 @@ impl ToolController {
 
     pub fn refresh_from_inventory(&mut self, slots: &InventorySlots) {
         if let Some(current) = self.selection {
-            let refreshed = slot_block(slots, current.slot_index).and_then(|(block, count)| {
-                (block == current.block).then_some(ToolSelection {
-                    block,
+            let refreshed = slot_item(slots, current.slot_index).and_then(|(item, count)| {
+                (item == current.item).then_some(ToolSelection {
+                    item,
                     slot_index: current.slot_index,
                     remaining: count,
                 })
@@ -143,39 +154,42 @@ impl ToolController {
         let selection = self
             .selection
             .ok_or_else(|| PlacementError::new(PlacementErrorReason::NoSelection))?;
+        let placed_block = ItemRegistry::global()
+            .placed_block(selection.item)
+            .ok_or_else(|| PlacementError::new(PlacementErrorReason::NotPlaceable))?;
 
         self.validate_target(drone_tile, target_tile)?;
 
         let current_block = chunk_cache
             .block_at_world(target_tile)
             .ok_or_else(|| PlacementError::new(PlacementErrorReason::TargetUnloaded))?;
-        if current_block != AIR {
+        if BlockRegistry::global().is_solid(current_block) {
             return Err(PlacementError::new(PlacementErrorReason::TargetBlocked));
         }
 
-        let Some(removed_block) = remove_block_from_slot(slots, selection.slot_index) else {
+        let Some(removed_item) = remove_item_from_slot(slots, selection.slot_index) else {
             self.refresh_from_inventory(slots);
             return Err(PlacementError::new(PlacementErrorReason::SlotEmpty));
         };
 
-        if removed_block != selection.block {
+        if removed_item != selection.item {
             self.refresh_from_inventory(slots);
             return Err(PlacementError::new(PlacementErrorReason::SlotMismatch));
         }
 
-        chunk_cache.set_block(target_tile, removed_block)?;
+        chunk_cache.set_block(target_tile, placed_block)?;
         self.refresh_from_inventory(slots);
 
         let remaining_in_slot = slots
             .get(selection.slot_index)
-            .filter(|slot| slot.block == Some(selection.block))
+            .filter(|slot| slot.item == Some(selection.item))
             .map(|slot| slot.count)
             .unwrap_or(0);
 
         let selection_cleared = self.selection.is_none();
 
         Ok(PlacementOutcome {
-            placed_block: removed_block,
+            placed_block,
             target: target_tile,
             remaining_in_slot,
             selection_cleared,
@@ -209,9 +223,10 @@ impl ToolController {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::{AIR, STONE};
+    use crate::block::{AIR, CORE, STONE};
     use crate::coordinates::ChunkPosition;
     use crate::inventory::{InventorySlot, empty_inventory};
+    use crate::item::{ITEM_CORE, ITEM_STONE};
     use crate::worldgen::DeterministicMap;
 
     fn cache_with_air_at(target: WorldCoord) -> ChunkCache {
@@ -232,7 +247,7 @@ mod tests {
 
         let mut slots = empty_inventory();
         slots[0] = InventorySlot {
-            block: Some(STONE),
+            item: Some(ITEM_CORE),
             count: 2,
         };
 
@@ -243,7 +258,7 @@ mod tests {
             .place_selected_block(&mut slots, &mut cache, drone_tile, target)
             .expect("placement should succeed");
 
-        assert_eq!(cache.block_at_world(target), Some(STONE));
+        assert_eq!(cache.block_at_world(target), Some(CORE));
         assert_eq!(outcome.remaining_in_slot, 1);
         assert!(!outcome.selection_cleared);
         assert_eq!(controller.selection().unwrap().remaining, 1);
@@ -259,7 +274,7 @@ mod tests {
 
         let mut slots = empty_inventory();
         slots[0] = InventorySlot {
-            block: Some(STONE),
+            item: Some(ITEM_CORE),
             count: 1,
         };
 
@@ -280,7 +295,7 @@ mod tests {
 
         let mut slots = empty_inventory();
         slots[0] = InventorySlot {
-            block: Some(STONE),
+            item: Some(ITEM_CORE),
             count: 1,
         };
 
@@ -294,6 +309,29 @@ mod tests {
         assert!(outcome.selection_cleared);
         assert!(controller.selection().is_none());
         assert_eq!(slots[0].count, 0);
-        assert!(slots[0].block.is_none());
+        assert!(slots[0].item.is_none());
+    }
+
+    #[test]
+    fn rejects_items_that_do_not_place_a_block() {
+        let target = WorldCoord::new(0, 0, 0);
+        let drone_tile = WorldCoord::new(1, 0, 0);
+        let mut cache = cache_with_air_at(target);
+
+        let mut slots = empty_inventory();
+        slots[0] = InventorySlot {
+            item: Some(ITEM_STONE),
+            count: 1,
+        };
+
+        let mut controller = ToolController::new();
+        controller.select_from_inventory(&slots, 0);
+
+        let err = controller
+            .place_selected_block(&mut slots, &mut cache, drone_tile, target)
+            .unwrap_err();
+        assert_eq!(err.reason, PlacementErrorReason::NotPlaceable);
+        assert_eq!(cache.block_at_world(target), Some(AIR));
+        assert_eq!(slots[0].count, 1);
     }
 }
@@ -0,0 +1,256 @@
+use crate::combat::{Attack, CombatEvent, Combatant, Cooldown, Target, strike};
+use crate::coordinates::WorldCoord;
+use crate::enemy::Enemy;
+use crate::item::{ITEM_IRON_ORE, ItemId};
+use crate::world::TICKS_PER_SECOND;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+pub type WarriorId = u32;
+
+/// What a warrior is paid for with, taken from the resource pool.
+pub const WARRIOR_COST_ITEM: ItemId = ITEM_IRON_ORE;
+pub const WARRIOR_IRON_COST: u32 = 5;
+pub const WARRIOR_ATTACK: Attack = Attack::new(2, 1, TICKS_PER_SECOND as u64 * 3 / 4);
+pub const WARRIOR_MAX_HEALTH: i32 = 9;
+
+/// A stationary melee defender standing on a warrior spot. It strikes any enemy on a
+/// neighbouring tile, and enemies in reach strike back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warrior {
+    pub id: WarriorId,
+    pub tile: WorldCoord,
+    pub health: i32,
+    cooldown: Cooldown,
+}
+
+impl Warrior {
+    pub fn new(id: WarriorId, tile: WorldCoord) -> Self {
+        Self {
+            id,
+            tile,
+            health: WARRIOR_MAX_HEALTH,
+            cooldown: Cooldown::default(),
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarriorPlacementErrorReason {
+    NotASpot,
+    Occupied,
+    TargetUnloaded,
+    NotEnoughIron { needed: u32, available: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarriorPlacementError {
+    pub reason: WarriorPlacementErrorReason,
+}
+
+impl WarriorPlacementError {
+    pub fn new(reason: WarriorPlacementErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            WarriorPlacementErrorReason::NotASpot => "warriors can only stand on warrior spots",
+            WarriorPlacementErrorReason::Occupied => "a warrior already stands there",
+            WarriorPlacementErrorReason::TargetUnloaded => "target tile is not loaded",
+            WarriorPlacementErrorReason::NotEnoughIron { .. } => "not enough iron",
+        }
+    }
+}
+
+impl fmt::Display for WarriorPlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            WarriorPlacementErrorReason::NotEnoughIron { needed, available } => {
+                write!(f, "{} ({}/{})", self.message(), available, needed)
+            }
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for WarriorPlacementError {}
+
+/// Changes to the line of defence. Every hit a warrior lands or takes, and its death, is in
+/// the combat log instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarriorEvent {
+    Placed {
+        warrior: WarriorId,
+        tile: WorldCoord,
+    },
+}
+
+/// Lets every warrior whose cooldown has passed strike one living enemy in reach, the
+/// weakest first so damage is not spread thin.
+pub(crate) fn advance_warriors(
+    tick: u64,
+    warriors: &mut [Warrior],
+    enemies: &mut [Enemy],
+    combat: &mut Vec<CombatEvent>,
+) {
+    for warrior in warriors.iter_mut().filter(|warrior| warrior.is_alive()) {
+        if !warrior.cooldown.is_ready(tick) {
+            continue;
+        }
+        let Some(enemy) = enemies
+            .iter_mut()
+            .filter(|enemy| enemy.is_alive() && WARRIOR_ATTACK.reaches(warrior.tile, enemy.tile()))
+            .min_by_key(|enemy| (enemy.health, enemy.id))
+        else {
+            continue;
+        };
+
+        let target = Target {
+            combatant: Combatant::Enemy(enemy.id),
+            armour: enemy.stats().armour,
+            health: &mut enemy.health,
+        };
+        strike(
+            tick,
+            Combatant::Warrior(warrior.id),
+            &WARRIOR_ATTACK,
+            &mut warrior.cooldown,
+            target,
+            combat,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::WARRIOR_SPOT;
+    use crate::test_support::arena;
+    use crate::wave::EnemyKind;
+    use crate::world::World;
+
+    fn arena_with_spot(spot: WorldCoord) -> World {
+        let mut world = arena();
+        world.set_block(spot, WARRIOR_SPOT).unwrap();
+        world
+    }
+
+    #[test]
+    fn placement_checks_the_spot_and_spends_iron() {
+        let spot = WorldCoord::new(4, 4, 0);
+        let mut world = arena_with_spot(spot);
+
+        let err = world.place_warrior(spot).unwrap_err();
+        assert_eq!(
+            err.reason,
+            WarriorPlacementErrorReason::NotEnoughIron {
+                needed: WARRIOR_IRON_COST,
+                available: 0,
+            }
+        );
+
+        world
+            .resources_mut()
+            .add(WARRIOR_COST_ITEM, WARRIOR_IRON_COST + 1);
+        let err = world.place_warrior(WorldCoord::new(5, 4, 0)).unwrap_err();
+        assert_eq!(err.reason, WarriorPlacementErrorReason::NotASpot);
+
+        let id = world.place_warrior(spot).unwrap();
+        assert_eq!(world.warriors(), &[Warrior::new(id, spot)]);
+        assert_eq!(world.resources().amount(WARRIOR_COST_ITEM), 1);
+
+        let err = world.place_warrior(spot).unwrap_err();
+        assert_eq!(err.reason, WarriorPlacementErrorReason::Occupied);
+    }
+
+    #[test]
+    fn enemies_in_reach_strike_back_and_can_kill_a_warrior() {
+        let spot = WorldCoord::new(4, 4, 0);
+        let mut world = arena_with_spot(spot);
+        world
+            .resources_mut()
+            .add(WARRIOR_COST_ITEM, WARRIOR_IRON_COST);
+        let warrior = world.place_warrior(spot).unwrap();
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(4, 5, 0));
+
+        for _ in 0..10 * TICKS_PER_SECOND {
+            world.step();
+        }
+
+        assert!(world.warriors().is_empty());
+        assert_eq!(world.enemies().len(), 1);
+        let log = world.drain_combat_events();
+        assert!(log.iter().any(|event| matches!(
+            event,
+            CombatEvent::Hit { target: Combatant::Warrior(id), .. } if *id == warrior
+        )));
+        assert!(log.iter().any(|event| matches!(
+            event,
+            CombatEvent::Died { combatant: Combatant::Warrior(id), tile, .. }
+                if *id == warrior && *tile == spot
+        )));
+        assert_eq!(world.drain_warrior_events().len(), 1);
+    }
+
+    #[test]
+    fn brute_armour_blunts_warrior_strikes() {
+        let spot = WorldCoord::new(4, 4, 0);
+        let mut world = arena_with_spot(spot);
+        world
+            .resources_mut()
+            .add(WARRIOR_COST_ITEM, WARRIOR_IRON_COST);
+        let warrior = world.place_warrior(spot).unwrap();
+        let enemy = world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(5, 4, 0));
+
+        world.step();
+
+        let brute = EnemyKind::Brute.stats();
+        let damage = WARRIOR_ATTACK.damage - brute.armour;
+        assert!(world.drain_combat_events().contains(&CombatEvent::Hit {
+            tick: world.tick,
+            attacker: Combatant::Warrior(warrior),
+            target: Combatant::Enemy(enemy),
+            damage,
+            health: brute.max_health - damage,
+        }));
+        assert_eq!(world.enemies()[0].health, brute.max_health - damage);
+    }
+
+    #[test]
+    fn warriors_strike_enemies_next_to_them() {
+        let spot = WorldCoord::new(4, 4, 0);
+        let mut world = arena_with_spot(spot);
+        world
+            .resources_mut()
+            .add(WARRIOR_COST_ITEM, WARRIOR_IRON_COST);
+        let warrior = world.place_warrior(spot).unwrap();
+        let enemy = world.spawn_enemy(EnemyKind::Crawler, WorldCoord::new(5, 4, 0));
+
+        for _ in 0..3 * WARRIOR_ATTACK.cooldown_ticks {
+            world.step();
+        }
+
+        assert!(world.enemies().is_empty());
+        assert_eq!(
+            world.drain_warrior_events(),
+            vec![WarriorEvent::Placed {
+                warrior,
+                tile: spot
+            }]
+        );
+        assert_eq!(
+            world.drain_combat_events().last(),
+            Some(&CombatEvent::Died {
+                tick: 2 * WARRIOR_ATTACK.cooldown_ticks + 1,
+                combatant: Combatant::Enemy(enemy),
+                tile: WorldCoord::new(5, 4, 0),
+            })
+        );
+    }
+}
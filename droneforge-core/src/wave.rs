@@ -0,0 +1,370 @@
+use crate::coordinates::{TileBox3, WorldCoord};
+use crate::world::TICKS_PER_SECOND;
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Ticks between two enemies of the same wave entering the map.
+pub const SPAWN_INTERVAL_TICKS: u64 = TICKS_PER_SECOND as u64 / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyKind {
+    /// Fast and fragile.
+    Crawler,
+    /// Slow, tough and good at breaking walls.
+    Brute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaveGroup {
+    pub enemy: EnemyKind,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveDefinition {
+    /// Seconds from the previous wave starting (or the game starting) to this one.
+    pub delay_seconds: f32,
+    /// The delay varies by up to this much either way, picked from the seed.
+    #[serde(default)]
+    pub jitter_seconds: f32,
+    pub groups: Vec<WaveGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaveScheduleErrorReason {
+    Json(String),
+    NoWaves,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveScheduleError {
+    pub reason: WaveScheduleErrorReason,
+}
+
+impl WaveScheduleError {
+    pub fn new(reason: WaveScheduleErrorReason) -> Self {
+        Self { reason }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.reason {
+            WaveScheduleErrorReason::Json(_) => "wave definitions are not valid",
+            WaveScheduleErrorReason::NoWaves => "wave schedule is empty",
+        }
+    }
+}
+
+impl fmt::Display for WaveScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            WaveScheduleErrorReason::Json(detail) => write!(f, "{} ({})", self.message(), detail),
+            WaveScheduleErrorReason::NoWaves => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for WaveScheduleError {}
+
+const BUILTIN_WAVES: &str = include_str!("../assets/waves.json");
+
+/// The attacks of a game, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveSchedule {
+    waves: Vec<WaveDefinition>,
+}
+
+impl WaveSchedule {
+    pub fn from_waves(
+        waves: impl IntoIterator<Item = WaveDefinition>,
+    ) -> Result<Self, WaveScheduleError> {
+        let waves: Vec<WaveDefinition> = waves.into_iter().collect();
+        if waves.is_empty() {
+            return Err(WaveScheduleError::new(WaveScheduleErrorReason::NoWaves));
+        }
+        Ok(Self { waves })
+    }
+
+    /// Parses a JSON array of [`WaveDefinition`]s.
+    pub fn from_json(source: &str) -> Result<Self, WaveScheduleError> {
+        let waves: Vec<WaveDefinition> = serde_json::from_str(source).map_err(|err| {
+            WaveScheduleError::new(WaveScheduleErrorReason::Json(err.to_string()))
+        })?;
+        Self::from_waves(waves)
+    }
+
+    /// The waves shipped with the game.
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_WAVES).expect("built-in waves must be valid")
+    }
+
+    pub fn waves(&self) -> &[WaveDefinition] {
+        &self.waves
+    }
+}
+
+/// One enemy entering the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnemySpawn {
+    /// Zero-based index of the wave the enemy belongs to.
+    pub wave: usize,
+    pub enemy: EnemyKind,
+    pub tile: WorldCoord,
+    pub tick: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaveEvent {
+    Started {
+        wave: usize,
+    },
+    Spawned(EnemySpawn),
+    /// The last enemy of the last wave has entered the map.
+    AllSpawned,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PlannedWave {
+    start_tick: u64,
+    spawns: Vec<EnemySpawn>,
+}
+
+/// Runs a [`WaveSchedule`] against the world clock. Every start time and spawn point is
+/// rolled from the seed up front, so a seed always plays out the same attack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waves {
+    plan: Vec<PlannedWave>,
+}
+
+impl Waves {
+    /// Plans `schedule` for a game starting at `start_tick`, with enemies entering on the
+    /// border of `arena` at its lowest level.
+    pub fn new(schedule: &WaveSchedule, seed: u64, arena: TileBox3, start_tick: u64) -> Self {
+        let mut rng = Rng::with_seed(seed);
+        let mut wave_start = start_tick;
+        let plan = schedule
+            .waves()
+            .iter()
+            .enumerate()
+            .map(|(wave, definition)| {
+                let jitter = definition.jitter_seconds.abs();
+                let delay = definition.delay_seconds + (rng.f32() * 2.0 - 1.0) * jitter;
+                wave_start += seconds_to_ticks(delay.max(0.0));
+
+                let spawns = definition
+                    .groups
+                    .iter()
+                    .flat_map(|group| (0..group.count).map(|_| group.enemy))
+                    .enumerate()
+                    .map(|(order, enemy)| EnemySpawn {
+                        wave,
+                        enemy,
+                        tile: edge_tile(&mut rng, arena),
+                        tick: wave_start + order as u64 * SPAWN_INTERVAL_TICKS,
+                    })
+                    .collect();
+                PlannedWave {
+                    start_tick: wave_start,
+                    spawns,
+                }
+            })
+            .collect();
+        Self { plan }
+    }
+
+    pub fn wave_count(&self) -> usize {
+        self.plan.len()
+    }
+
+    /// Waves that have started by `tick`.
+    pub fn started_waves(&self, tick: u64) -> usize {
+        self.plan
+            .iter()
+            .take_while(|wave| wave.start_tick <= tick)
+            .count()
+    }
+
+    /// Zero-based index of the next wave to start after `tick`, if any are left.
+    pub fn next_wave(&self, tick: u64) -> Option<usize> {
+        let started = self.started_waves(tick);
+        (started < self.plan.len()).then_some(started)
+    }
+
+    pub fn ticks_until_next_wave(&self, tick: u64) -> Option<u64> {
+        self.next_wave(tick)
+            .map(|wave| self.plan[wave].start_tick - tick)
+    }
+
+    pub fn seconds_until_next_wave(&self, tick: u64) -> Option<f32> {
+        self.ticks_until_next_wave(tick)
+            .map(|ticks| ticks as f32 / TICKS_PER_SECOND as f32)
+    }
+
+    /// Whether the most recent wave still has enemies waiting to enter.
+    pub fn is_spawning(&self, tick: u64) -> bool {
+        self.started_waves(tick)
+            .checked_sub(1)
+            .and_then(|wave| self.plan[wave].spawns.last())
+            .is_some_and(|last| last.tick > tick)
+    }
+
+    /// Whether every enemy of every wave has entered by `tick`.
+    pub fn all_spawned(&self, tick: u64) -> bool {
+        self.plan
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .all(|spawn| spawn.tick <= tick)
+    }
+
+    /// "Wave N in mm:ss" while counting down and "Wave N active" while it spawns, with `N`
+    /// counted from one.
+    pub fn status_text(&self, tick: u64) -> String {
+        if self.is_spawning(tick) {
+            return format!("Wave {} active", self.started_waves(tick));
+        }
+        match (self.next_wave(tick), self.ticks_until_next_wave(tick)) {
+            (Some(wave), Some(ticks)) => {
+                let seconds = ticks.div_ceil(TICKS_PER_SECOND as u64);
+                format!(
+                    "Wave {} in {:02}:{:02}",
+                    wave + 1,
+                    seconds / 60,
+                    seconds % 60
+                )
+            }
+            _ => "All waves sent".to_string(),
+        }
+    }
+
+    /// Events for exactly `tick`; call it once per tick to see each event once.
+    pub fn events_at(&self, tick: u64) -> Vec<WaveEvent> {
+        let mut events = Vec::new();
+        for (wave, planned) in self.plan.iter().enumerate() {
+            if planned.start_tick == tick {
+                events.push(WaveEvent::Started { wave });
+            }
+            events.extend(
+                planned
+                    .spawns
+                    .iter()
+                    .filter(|spawn| spawn.tick == tick)
+                    .copied()
+                    .map(WaveEvent::Spawned),
+            );
+        }
+        let last_spawn = self
+            .plan
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .map(|spawn| spawn.tick)
+            .max();
+        if last_spawn == Some(tick) {
+            events.push(WaveEvent::AllSpawned);
+        }
+        events
+    }
+}
+
+fn seconds_to_ticks(seconds: f32) -> u64 {
+    (seconds * TICKS_PER_SECOND as f32).round() as u64
+}
+
+/// A random tile on the border of `arena`'s lowest level.
+fn edge_tile(rng: &mut Rng, arena: TileBox3) -> WorldCoord {
+    let (min, max) = (arena.min, arena.max);
+    let x = rng.i32(min.x..=max.x);
+    let y = rng.i32(min.y..=max.y);
+    match rng.u8(0..4) {
+        0 => WorldCoord::new(min.x, y, min.z),
+        1 => WorldCoord::new(max.x, y, min.z),
+        2 => WorldCoord::new(x, min.y, min.z),
+        _ => WorldCoord::new(x, max.y, min.z),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arena() -> TileBox3 {
+        TileBox3::new(WorldCoord::new(-20, -20, 0), WorldCoord::new(20, 20, 0)).unwrap()
+    }
+
+    fn schedule() -> WaveSchedule {
+        WaveSchedule::from_json(
+            r#"[
+                { "delay_seconds": 10.0, "groups": [{ "enemy": "crawler", "count": 3 }] },
+                {
+                    "delay_seconds": 20.0,
+                    "jitter_seconds": 5.0,
+                    "groups": [
+                        { "enemy": "crawler", "count": 2 },
+                        { "enemy": "brute", "count": 1 }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn all_events(waves: &Waves, ticks: u64) -> Vec<(u64, WaveEvent)> {
+        (0..=ticks)
+            .flat_map(|tick| {
+                waves
+                    .events_at(tick)
+                    .into_iter()
+                    .map(move |event| (tick, event))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same_attack() {
+        let first = Waves::new(&schedule(), 7, arena(), 0);
+        let again = Waves::new(&schedule(), 7, arena(), 0);
+        let other = Waves::new(&schedule(), 8, arena(), 0);
+
+        assert_eq!(first, again);
+        assert_eq!(all_events(&first, 3000), all_events(&again, 3000));
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn counts_down_then_spawns_on_the_arena_edge() {
+        let waves = Waves::new(&schedule(), 3, arena(), 0);
+
+        assert_eq!(waves.next_wave(0), Some(0));
+        assert_eq!(waves.ticks_until_next_wave(0), Some(600));
+        assert_eq!(waves.status_text(0), "Wave 1 in 00:10");
+
+        let spawns: Vec<EnemySpawn> = all_events(&waves, 3000)
+            .into_iter()
+            .filter_map(|(_, event)| match event {
+                WaveEvent::Spawned(spawn) => Some(spawn),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(spawns.len(), 6);
+        assert_eq!(spawns[0].tick, 600);
+        assert_eq!(spawns[1].tick, 600 + SPAWN_INTERVAL_TICKS);
+        assert_eq!(waves.status_text(600), "Wave 1 active");
+        assert!(spawns.iter().all(|spawn| {
+            let tile = spawn.tile;
+            tile.z == 0 && (tile.x.abs() == 20 || tile.y.abs() == 20)
+        }));
+
+        let second_start = waves.plan[1].start_tick - waves.plan[0].start_tick;
+        assert!((900..=1500).contains(&second_start));
+        assert!(waves.all_spawned(3000));
+        assert_eq!(waves.next_wave(3000), None);
+    }
+
+    #[test]
+    fn rejects_an_empty_schedule() {
+        let err = WaveSchedule::from_json("[]").unwrap_err();
+        assert_eq!(err.reason, WaveScheduleErrorReason::NoWaves);
+        assert!(WaveSchedule::builtin().waves().len() > 1);
+    }
+}
@@ -1,17 +1,72 @@
-use crate::block::BlockId;
+use crate::block::{AIR, BlockId, BlockRegistry, CORE, WARRIOR_SPOT};
 use crate::chunk::{Chunk, ChunkBlocks, ChunkError};
-use crate::coordinates::{ChunkPosition, LocalBlockCoord};
-use crate::drone::DronePose;
-use crate::inventory::{InventorySlots, add_block_to_slots, empty_inventory};
+use crate::chunk_cache::{CachedChunk, ChunkCache, chunk_and_local_for_world_coord};
+use crate::combat::{CombatEvent, Combatant, ItemDrop};
+use crate::coordinates::TileBox3;
+use crate::coordinates::{ChunkPosition, WorldCoord};
+use crate::drone::{DroneId, DronePose};
+use crate::enemy::{Enemy, EnemyEvent, EnemyId, advance_enemies, within_reach};
+use crate::inventory::{
+    InventoryError, InventoryErrorReason, InventorySlots, add_item_to_slots, add_items,
+    empty_inventory, item_count, remove_items, space_for, transfer_items,
+};
+use crate::item::ItemId;
+use crate::job::{JobId, MineBoxJob};
+use crate::order::{
+    CraftOrder, Order, OrderError, OrderErrorReason, OrderEvent, OrderOutcome, dig_duration,
+    validate_dig_target,
+};
+use crate::pathfind::is_open;
+use crate::recipe::{CraftError, CraftErrorReason, RecipeId, RecipeRegistry};
+use crate::resources::ResourcePool;
+use crate::scheduler::{DroneStatus, Scheduler, face_order_target};
 use crate::storage::{LoadBlocksFn, SaveBlocksFn, StorageError};
-use crate::worldgen::DeterministicMap;
-use std::collections::HashMap;
+use crate::structure::{CoreStructure, GamePhase, StructureEvent};
+use crate::tool::{PlacementError, PlacementErrorReason, PlacementOutcome, ToolController};
+use crate::warrior::{
+    WARRIOR_COST_ITEM, WARRIOR_IRON_COST, Warrior, WarriorEvent, WarriorId, WarriorPlacementError,
+    WarriorPlacementErrorReason, advance_warriors,
+};
+use crate::wave::{EnemyKind, WaveEvent, Waves};
+use crate::worldgen::WorldGenerator;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const TICKS_PER_SECOND: u32 = 60;
+pub const SECONDS_PER_TICK: f32 = 1.0 / TICKS_PER_SECOND as f32;
+
+/// Id counters for everything that can die. Ids of the dead are never reused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NextIds {
+    pub drone: DroneId,
+    pub enemy: EnemyId,
+    pub warrior: WarriorId,
+}
 
 pub struct World {
     pub tick: u64,
-    chunks: HashMap<ChunkPosition, Chunk>,
+    blocks: ChunkCache,
     drones: Vec<DronePose>,
+    next_drone_id: DroneId,
     inventories: Vec<InventorySlots>,
+    orders: Vec<VecDeque<Order>>,
+    order_events: Vec<OrderEvent>,
+    scheduler: Scheduler,
+    waves: Option<Waves>,
+    wave_events: Vec<WaveEvent>,
+    enemies: Vec<Enemy>,
+    next_enemy_id: EnemyId,
+    enemy_events: Vec<EnemyEvent>,
+    /// Every placed core, in placement order.
+    cores: Vec<CoreStructure>,
+    structure_events: Vec<StructureEvent>,
+    phase: GamePhase,
+    warriors: Vec<Warrior>,
+    next_warrior_id: WarriorId,
+    warrior_events: Vec<WarriorEvent>,
+    resources: ResourcePool,
+    combat_events: Vec<CombatEvent>,
+    drops: Vec<ItemDrop>,
     save_blocks: SaveBlocksFn,
     load_blocks: LoadBlocksFn,
 }
@@ -24,16 +79,197 @@ impl World {
     pub fn new_with_callbacks(save_blocks: SaveBlocksFn, load_blocks: LoadBlocksFn) -> Self {
         Self {
             tick: 0,
-            chunks: HashMap::new(),
+            blocks: ChunkCache::new(),
             drones: Vec::new(),
+            next_drone_id: 0,
             inventories: Vec::new(),
+            orders: Vec::new(),
+            order_events: Vec::new(),
+            scheduler: Scheduler::new(),
+            waves: None,
+            wave_events: Vec::new(),
+            enemies: Vec::new(),
+            next_enemy_id: 0,
+            enemy_events: Vec::new(),
+            cores: Vec::new(),
+            structure_events: Vec::new(),
+            phase: GamePhase::Running,
+            warriors: Vec::new(),
+            next_warrior_id: 0,
+            warrior_events: Vec::new(),
+            resources: ResourcePool::new(),
+            combat_events: Vec::new(),
+            drops: Vec::new(),
             save_blocks,
             load_blocks,
         }
     }
 
+    /// A world whose terrain lives in `chunk_cache`.
+    pub fn with_chunk_cache(chunk_cache: ChunkCache) -> Self {
+        let mut world = Self::new();
+        world.blocks = chunk_cache;
+        world.sync_cores();
+        world
+    }
+
+    /// A world that generates its terrain from `generator` as chunks are first used.
+    pub fn with_generator(generator: impl WorldGenerator + 'static) -> Self {
+        Self::with_chunk_cache(ChunkCache::with_generator(generator))
+    }
+
+    /// Advances the simulation by one fixed tick: waves send their enemies, every drone runs
+    /// its current order, the scheduler hands out job work, the enemies act, the warriors
+    /// strike back and finally the dead are cleared away. Does nothing once the game is over.
     pub fn step(&mut self) {
+        if self.phase.is_over() {
+            return;
+        }
         self.tick += 1;
+        self.sync_cores();
+        self.spawn_wave_enemies();
+        let first_new_event = self.order_events.len();
+        self.advance_orders(SECONDS_PER_TICK);
+        self.scheduler
+            .apply_events(&self.order_events[first_new_event..], &self.drones);
+        self.scheduler.schedule(
+            self.tick,
+            &mut self.drones,
+            &mut self.orders,
+            &mut self.blocks,
+        );
+
+        let first_enemy_event = self.enemy_events.len();
+        advance_enemies(
+            self.tick,
+            &mut self.enemies,
+            &mut self.drones,
+            &mut self.warriors,
+            &mut self.blocks,
+            &mut self.cores,
+            &mut self.enemy_events,
+            &mut self.combat_events,
+        );
+        let mut core_lost = false;
+        for index in first_enemy_event..self.enemy_events.len() {
+            match self.enemy_events[index] {
+                EnemyEvent::AttackedCore { tile, damage, .. } => {
+                    let destroyed = self.settle_core_damage(tile, damage);
+                    if destroyed {
+                        self.combat_events.push(CombatEvent::Died {
+                            tick: self.tick,
+                            combatant: Combatant::Core(tile),
+                            tile,
+                        });
+                        core_lost = true;
+                    }
+                }
+                EnemyEvent::BrokeBlock { tile, block, .. } if block == CORE => {
+                    self.track_core(tile, AIR);
+                    self.structure_events
+                        .push(StructureEvent::Destroyed { tile });
+                    core_lost = true;
+                }
+                _ => {}
+            }
+        }
+        advance_warriors(
+            self.tick,
+            &mut self.warriors,
+            &mut self.enemies,
+            &mut self.combat_events,
+        );
+        self.remove_the_dead();
+        self.update_phase(core_lost);
+    }
+
+    /// The block store every system reads terrain from.
+    pub fn chunk_cache(&self) -> &ChunkCache {
+        &self.blocks
+    }
+
+    pub fn chunk_cache_mut(&mut self) -> &mut ChunkCache {
+        &mut self.blocks
+    }
+
+    pub fn block_at(&self, coord: WorldCoord) -> Option<BlockId> {
+        self.blocks.block_at_world(coord)
+    }
+
+    pub fn set_block(&mut self, coord: WorldCoord, block: BlockId) -> Result<(), ChunkError> {
+        self.blocks.set_block(coord, block)?;
+        self.track_core(coord, block);
+        Ok(())
+    }
+
+    /// Placed cores, oldest first.
+    pub fn cores(&self) -> &[CoreStructure] {
+        &self.cores
+    }
+
+    /// The core the HUD reports on: the first one placed that still stands.
+    pub fn primary_core(&self) -> Option<&CoreStructure> {
+        self.cores.first()
+    }
+
+    /// Replaces the core list, e.g. when loading a save. Tiles that do not hold a core are
+    /// dropped, and `CORE` blocks missing from the list join it at full HP.
+    pub fn set_cores(&mut self, cores: Vec<CoreStructure>) {
+        self.cores = cores;
+        let blocks = &self.blocks;
+        self.cores
+            .retain(|core| blocks.block_at_world(core.tile) == Some(CORE));
+        self.sync_cores();
+    }
+
+    /// Deals `damage` to the core on `tile`, destroying the block at zero HP. Returns whether
+    /// the core was destroyed.
+    pub fn damage_core(&mut self, tile: WorldCoord, damage: i32) -> bool {
+        let Some(core) = self.cores.iter_mut().find(|core| core.tile == tile) else {
+            return false;
+        };
+        core.take_damage(damage);
+        self.settle_core_damage(tile, damage)
+    }
+
+    /// Reports `damage` already taken off the core on `tile` and breaks the block once the
+    /// core is out of HP. Returns whether the core was destroyed.
+    fn settle_core_damage(&mut self, tile: WorldCoord, damage: i32) -> bool {
+        let Some(hp) = self
+            .cores
+            .iter()
+            .find(|core| core.tile == tile)
+            .map(|core| core.hp)
+        else {
+            return false;
+        };
+        self.structure_events
+            .push(StructureEvent::Damaged { tile, damage, hp });
+        if hp > 0 {
+            return false;
+        }
+        if self.blocks.set_block(tile, AIR).is_err() {
+            // The block is still there, so keep tracking it; the next hit tries again.
+            return false;
+        }
+        self.track_core(tile, AIR);
+        self.structure_events
+            .push(StructureEvent::Destroyed { tile });
+        true
+    }
+
+    /// Returns and clears core damage and destruction since the last call.
+    pub fn drain_structure_events(&mut self) -> Vec<StructureEvent> {
+        std::mem::take(&mut self.structure_events)
+    }
+
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    /// Puts the game back in `phase`, e.g. when loading a save of a finished game.
+    pub fn set_phase(&mut self, phase: GamePhase) {
+        self.phase = phase;
     }
 
     pub fn drones(&self) -> &[DronePose] {
@@ -44,14 +280,165 @@ impl World {
         &mut self.drones
     }
 
+    /// Replaces the drones, numbering their ids afresh from zero.
     pub fn set_drones(&mut self, drones: Vec<DronePose>) {
         self.drones = drones;
+        for (id, drone) in (0..).zip(self.drones.iter_mut()) {
+            drone.id = id;
+        }
+        self.next_drone_id = self.drones.len() as DroneId;
         self.reset_inventories_for(self.drones.len());
+        self.orders = vec![VecDeque::new(); self.drones.len()];
+        self.scheduler.set_drone_count(self.drones.len());
     }
 
-    pub fn add_drone(&mut self, drone: DronePose) {
+    /// Takes the drone, its inventory and its orders out of the world. Drones after it move
+    /// down one index.
+    pub fn remove_drone(&mut self, drone_index: usize) -> Option<DronePose> {
+        if drone_index >= self.drones.len() {
+            return None;
+        }
+        self.scheduler.remove_drone(drone_index);
+        self.orders.remove(drone_index);
+        self.inventories.remove(drone_index);
+        Some(self.drones.remove(drone_index))
+    }
+
+    /// Adds a drone at the end of the list and returns the id it was given.
+    pub fn add_drone(&mut self, mut drone: DronePose) -> DroneId {
+        let id = self.next_drone_id;
+        self.next_drone_id += 1;
+        drone.id = id;
         self.drones.push(drone);
         self.inventories.push(empty_inventory());
+        self.orders.push(VecDeque::new());
+        self.scheduler.add_drone();
+        id
+    }
+
+    /// The ids the next drone, enemy and warrior will get.
+    pub fn next_ids(&self) -> NextIds {
+        NextIds {
+            drone: self.next_drone_id,
+            enemy: self.next_enemy_id,
+            warrior: self.next_warrior_id,
+        }
+    }
+
+    /// Picks up id counting where a saved game left off, so a restored game hands out the same
+    /// ids the saved one would have.
+    pub fn set_next_ids(&mut self, next: NextIds) {
+        self.next_drone_id = next.drone;
+        self.next_enemy_id = next.enemy;
+        self.next_warrior_id = next.warrior;
+    }
+
+    /// Where the drone with `id` currently sits in [`World::drones`].
+    pub fn drone_index(&self, id: DroneId) -> Option<usize> {
+        self.drones.iter().position(|drone| drone.id == id)
+    }
+
+    /// Replaces whatever the drone was doing with `order`, taking it off any job.
+    pub fn issue_order(&mut self, drone_index: usize, order: Order) -> Result<(), OrderError> {
+        if drone_index >= self.orders.len() {
+            return Err(OrderError::new(OrderErrorReason::UnknownDrone));
+        }
+        self.clear_orders(drone_index);
+        self.queue_order(drone_index, order)
+    }
+
+    /// Appends `order` to the drone's queue; it starts once earlier orders finish.
+    pub fn queue_order(&mut self, drone_index: usize, order: Order) -> Result<(), OrderError> {
+        if drone_index >= self.orders.len() || drone_index >= self.drones.len() {
+            return Err(OrderError::new(OrderErrorReason::UnknownDrone));
+        }
+
+        if let Order::Dig(dig) = &order
+            && let Some(owner) = self.tile_reserved_by(dig.target_tile)
+            && owner != drone_index
+        {
+            return Err(OrderError::new(OrderErrorReason::TileReserved));
+        }
+
+        let (queue, drone) = (&mut self.orders[drone_index], &mut self.drones[drone_index]);
+        if queue.is_empty() {
+            face_order_target(drone, &order);
+        }
+        queue.push_back(order);
+        Ok(())
+    }
+
+    pub fn clear_orders(&mut self, drone_index: usize) {
+        if let Some(queue) = self.orders.get_mut(drone_index) {
+            queue.clear();
+        }
+        self.scheduler.release(drone_index);
+    }
+
+    pub fn current_order(&self, drone_index: usize) -> Option<&Order> {
+        self.orders.get(drone_index).and_then(|queue| queue.front())
+    }
+
+    pub fn queued_orders(&self, drone_index: usize) -> impl Iterator<Item = &Order> {
+        self.orders.get(drone_index).into_iter().flatten()
+    }
+
+    /// Returns and clears the progress/completion/failure events produced since the last call.
+    pub fn drain_order_events(&mut self) -> Vec<OrderEvent> {
+        std::mem::take(&mut self.order_events)
+    }
+
+    /// The drone whose queued dig order targets `tile`, if any.
+    pub fn tile_reserved_by(&self, tile: WorldCoord) -> Option<usize> {
+        self.orders.iter().position(|queue| {
+            queue
+                .iter()
+                .any(|order| matches!(order, Order::Dig(dig) if dig.target_tile == tile))
+        })
+    }
+
+    /// Lists the diggable tiles in `tile_box` as a new job. Idle drones pick it up on their
+    /// own; `assign_job` sends a specific drone.
+    pub fn add_mine_job(&mut self, tile_box: TileBox3) -> JobId {
+        self.scheduler.add_job(tile_box, &self.blocks)
+    }
+
+    /// Puts the drone to work on `job_id`, replacing its current orders.
+    pub fn assign_job(&mut self, drone_index: usize, job_id: JobId) -> Result<(), OrderError> {
+        if drone_index >= self.drones.len() {
+            return Err(OrderError::new(OrderErrorReason::UnknownDrone));
+        }
+        self.clear_orders(drone_index);
+        self.scheduler.assign(drone_index, job_id)
+    }
+
+    pub fn job(&self, job_id: JobId) -> Option<&MineBoxJob> {
+        self.scheduler.job(job_id)
+    }
+
+    pub fn jobs(&self) -> &[MineBoxJob] {
+        self.scheduler.jobs()
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    /// Replaces the job list and every drone's job; `scheduler` must track one slot per drone.
+    pub fn set_scheduler(&mut self, scheduler: Scheduler) {
+        debug_assert_eq!(scheduler.drone_count(), self.drones.len());
+        self.scheduler = scheduler;
+    }
+
+    pub fn drone_job(&self, drone_index: usize) -> Option<JobId> {
+        self.scheduler.drone_job(drone_index)
+    }
+
+    pub fn drone_status(&self, drone_index: usize) -> DroneStatus {
+        match self.orders.get(drone_index) {
+            Some(queue) => self.scheduler.status(drone_index, queue),
+            None => DroneStatus::Idle,
+        }
     }
 
     pub fn inventory(&self, drone_index: usize) -> Option<&InventorySlots> {
@@ -62,48 +449,288 @@ impl World {
         self.inventories.get_mut(drone_index)
     }
 
-    pub fn add_block_to_inventory(&mut self, drone_index: usize, block: BlockId) -> bool {
+    pub fn add_item_to_inventory(&mut self, drone_index: usize, item: ItemId) -> bool {
         let Some(slots) = self.inventory_mut(drone_index) else {
             return false;
         };
-        add_block_to_slots(slots, block)
+        add_item_to_slots(slots, item)
     }
 
-    pub fn register_chunk(&mut self, position: ChunkPosition, default_block: BlockId) {
-        self.chunks
-            .entry(position)
-            .or_insert_with(|| Chunk::new(position, default_block));
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
     }
 
-    pub fn register_generated_chunk(
+    /// Replaces the enemies on the map, e.g. when loading a save. Ids already handed out are
+    /// never handed out again.
+    pub fn set_enemies(&mut self, enemies: Vec<Enemy>) {
+        let after_last = enemies.iter().map(|enemy| enemy.id + 1).max();
+        self.next_enemy_id = self.next_enemy_id.max(after_last.unwrap_or(0));
+        self.enemies = enemies;
+    }
+
+    pub fn spawn_enemy(&mut self, kind: EnemyKind, tile: WorldCoord) -> EnemyId {
+        let id = self.next_enemy_id;
+        self.next_enemy_id += 1;
+        self.enemies.push(Enemy::new(id, kind, tile));
+        self.enemy_events.push(EnemyEvent::Spawned {
+            enemy: id,
+            kind,
+            tile,
+        });
+        id
+    }
+
+    /// Returns and clears what enemies did since the last call.
+    pub fn drain_enemy_events(&mut self) -> Vec<EnemyEvent> {
+        std::mem::take(&mut self.enemy_events)
+    }
+
+    pub fn warriors(&self) -> &[Warrior] {
+        &self.warriors
+    }
+
+    /// Replaces the stationed warriors, e.g. when loading a save. Ids already handed out are
+    /// never handed out again.
+    pub fn set_warriors(&mut self, warriors: Vec<Warrior>) {
+        let after_last = warriors.iter().map(|warrior| warrior.id + 1).max();
+        self.next_warrior_id = self.next_warrior_id.max(after_last.unwrap_or(0));
+        self.warriors = warriors;
+    }
+
+    /// Stations a warrior on the warrior spot at `tile`, paying for it from the resource pool.
+    pub fn place_warrior(&mut self, tile: WorldCoord) -> Result<WarriorId, WarriorPlacementError> {
+        match self.blocks.block_at_world(tile) {
+            None => {
+                return Err(WarriorPlacementError::new(
+                    WarriorPlacementErrorReason::TargetUnloaded,
+                ));
+            }
+            Some(block) if block != WARRIOR_SPOT => {
+                return Err(WarriorPlacementError::new(
+                    WarriorPlacementErrorReason::NotASpot,
+                ));
+            }
+            Some(_) => {}
+        }
+        if self.warriors.iter().any(|warrior| warrior.tile == tile) {
+            return Err(WarriorPlacementError::new(
+                WarriorPlacementErrorReason::Occupied,
+            ));
+        }
+        if !self.resources.spend(WARRIOR_COST_ITEM, WARRIOR_IRON_COST) {
+            return Err(WarriorPlacementError::new(
+                WarriorPlacementErrorReason::NotEnoughIron {
+                    needed: WARRIOR_IRON_COST,
+                    available: self.resources.amount(WARRIOR_COST_ITEM),
+                },
+            ));
+        }
+
+        let id = self.next_warrior_id;
+        self.next_warrior_id += 1;
+        self.warriors.push(Warrior::new(id, tile));
+        self.warrior_events
+            .push(WarriorEvent::Placed { warrior: id, tile });
+        Ok(id)
+    }
+
+    /// Returns and clears warrior placements since the last call.
+    pub fn drain_warrior_events(&mut self) -> Vec<WarriorEvent> {
+        std::mem::take(&mut self.warrior_events)
+    }
+
+    pub fn resources(&self) -> &ResourcePool {
+        &self.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut ResourcePool {
+        &mut self.resources
+    }
+
+    /// Hands up to `count` of `item` from a drone standing next to a core into the resource
+    /// pool and returns how many went in.
+    pub fn deposit_items(
         &mut self,
-        position: ChunkPosition,
-        generator: &DeterministicMap,
-    ) {
-        self.chunks
-            .entry(position)
-            .or_insert_with(|| generator.chunk_for_position(position));
+        drone_index: usize,
+        item: ItemId,
+        count: u32,
+    ) -> Result<u32, InventoryError> {
+        let (Some(drone), Some(slots)) = (
+            self.drones.get(drone_index),
+            self.inventories.get_mut(drone_index),
+        ) else {
+            return Err(InventoryError::new(InventoryErrorReason::UnknownInventory));
+        };
+        let tile = drone.tile();
+        if !self.cores.iter().any(|core| within_reach(tile, core.tile)) {
+            return Err(InventoryError::new(InventoryErrorReason::OutOfReach));
+        }
+
+        let moved = count.min(item_count(slots, item));
+        remove_items(slots, item, moved);
+        self.resources.add(item, moved);
+        Ok(moved)
+    }
+
+    /// Returns and clears hits and deaths since the last call.
+    pub fn drain_combat_events(&mut self) -> Vec<CombatEvent> {
+        std::mem::take(&mut self.combat_events)
+    }
+
+    /// Items lying on the ground, oldest first.
+    pub fn drops(&self) -> &[ItemDrop] {
+        &self.drops
+    }
+
+    pub fn set_drops(&mut self, drops: Vec<ItemDrop>) {
+        self.drops = drops;
     }
 
-    pub fn chunk(&self, position: &ChunkPosition) -> Option<&Chunk> {
-        self.chunks.get(position)
+    /// Collects what fits from every drop on or next to the drone's tile and returns the units
+    /// picked up.
+    pub fn pick_up_drops(&mut self, drone_index: usize) -> Result<u32, InventoryError> {
+        let (Some(drone), Some(slots)) = (
+            self.drones.get(drone_index),
+            self.inventories.get_mut(drone_index),
+        ) else {
+            return Err(InventoryError::new(InventoryErrorReason::UnknownInventory));
+        };
+        let tile = drone.tile();
+        let mut picked = 0;
+        for drop in self
+            .drops
+            .iter_mut()
+            .filter(|drop| within_reach(tile, drop.tile))
+        {
+            for stack in &mut drop.items {
+                let taken = stack.count.min(space_for(slots, stack.item));
+                add_items(slots, stack.item, taken);
+                stack.count -= taken;
+                picked += taken;
+            }
+            drop.items.retain(|stack| stack.count > 0);
+        }
+        self.drops.retain(|drop| !drop.is_empty());
+        Ok(picked)
+    }
+
+    /// Leaves `count` of `item` on `tile`, joining whatever already lies there.
+    fn leave_on_ground(&mut self, tile: WorldCoord, item: ItemId, count: u32) {
+        match self.drops.iter_mut().find(|drop| drop.tile == tile) {
+            Some(drop) => drop.add(item, count),
+            None => {
+                let mut drop = ItemDrop {
+                    tile,
+                    items: Vec::new(),
+                };
+                drop.add(item, count);
+                self.drops.push(drop);
+            }
+        }
+    }
+
+    /// Starts sending `waves`; worlds without them are never attacked.
+    pub fn set_waves(&mut self, waves: Waves) {
+        self.waves = Some(waves);
     }
 
-    pub fn set_block(
+    pub fn waves(&self) -> Option<&Waves> {
+        self.waves.as_ref()
+    }
+
+    /// The HUD line for the wave timer, such as "Wave 2 in 01:30".
+    pub fn wave_status_text(&self) -> Option<String> {
+        self.waves
+            .as_ref()
+            .map(|waves| waves.status_text(self.tick))
+    }
+
+    /// Returns and clears the wave starts and spawns since the last call.
+    pub fn drain_wave_events(&mut self) -> Vec<WaveEvent> {
+        std::mem::take(&mut self.wave_events)
+    }
+
+    /// Hands up to `count` of `item` from one drone to a drone on a neighbouring tile and
+    /// returns how many changed hands.
+    pub fn transfer_items(
+        &mut self,
+        from_drone: usize,
+        to_drone: usize,
+        item: ItemId,
+        count: u32,
+    ) -> Result<u32, InventoryError> {
+        if from_drone == to_drone {
+            return Err(InventoryError::new(InventoryErrorReason::SameInventory));
+        }
+        let (Some(from), Some(to)) = (self.drones.get(from_drone), self.drones.get(to_drone))
+        else {
+            return Err(InventoryError::new(InventoryErrorReason::UnknownInventory));
+        };
+        let (from_tile, to_tile) = (from.tile(), to.tile());
+        let neighbours = from_tile.z == to_tile.z
+            && (from_tile.x - to_tile.x).abs() <= 1
+            && (from_tile.y - to_tile.y).abs() <= 1;
+        if !neighbours {
+            return Err(InventoryError::new(InventoryErrorReason::OutOfReach));
+        }
+
+        let [from_slots, to_slots] = self
+            .inventories
+            .get_disjoint_mut([from_drone, to_drone])
+            .map_err(|_| InventoryError::new(InventoryErrorReason::UnknownInventory))?;
+        Ok(transfer_items(from_slots, to_slots, item, count))
+    }
+
+    /// Starts the drone crafting `recipe` where it stands, replacing its other orders. The
+    /// inputs are only taken once the craft finishes.
+    pub fn craft(&mut self, drone_index: usize, recipe: RecipeId) -> Result<(), OrderError> {
+        let (Some(drone), Some(slots)) = (
+            self.drones.get(drone_index),
+            self.inventories.get(drone_index),
+        ) else {
+            return Err(OrderError::new(OrderErrorReason::UnknownDrone));
+        };
+        let tile = drone.tile();
+        let definition = RecipeRegistry::global()
+            .get(recipe)
+            .ok_or_else(|| CraftError::new(CraftErrorReason::UnknownRecipe))?;
+        if !definition.has_inputs(slots) {
+            return Err(CraftError::new(CraftErrorReason::MissingInputs).into());
+        }
+        if !definition.station_in_reach(&self.blocks, tile) {
+            return Err(CraftError::new(CraftErrorReason::MissingStation).into());
+        }
+        self.issue_order(drone_index, Order::Craft(CraftOrder::new(recipe, tile)))
+    }
+
+    /// Places the tool's selected block from the drone's inventory at `target_tile`.
+    pub fn place_selected_block(
         &mut self,
-        chunk: ChunkPosition,
-        coord: LocalBlockCoord,
-        block: BlockId,
-    ) -> Result<(), ChunkError> {
-        let chunk = self
-            .chunks
-            .get_mut(&chunk)
-            .ok_or(ChunkError::InvalidBlockCount(0))?;
-        chunk.set_block(coord, block)
+        tool: &mut ToolController,
+        drone_index: usize,
+        target_tile: WorldCoord,
+    ) -> Result<PlacementOutcome, PlacementError> {
+        let (Some(drone), Some(slots)) = (
+            self.drones.get(drone_index),
+            self.inventories.get_mut(drone_index),
+        ) else {
+            return Err(PlacementError::new(PlacementErrorReason::UnknownDrone));
+        };
+        let outcome =
+            tool.place_selected_block(slots, &mut self.blocks, drone.tile(), target_tile)?;
+        self.track_core(outcome.target, outcome.placed_block);
+        Ok(outcome)
+    }
+
+    pub fn register_chunk(&mut self, position: ChunkPosition, default_block: BlockId) {
+        if !self.blocks.has_chunk(&position) {
+            let chunk = Chunk::new(position, default_block);
+            self.blocks.insert_chunk(CachedChunk::from_chunk(&chunk));
+        }
     }
 
     pub fn save_chunk_blocks(&self, position: &ChunkPosition) -> Result<(), StorageError> {
-        if let Some(chunk) = self.chunks.get(position) {
+        if let Some(chunk) = self.blocks.chunk(position) {
             let data = chunk.to_block_save();
             (self.save_blocks)(*position, data.blocks)
         } else {
@@ -116,16 +743,15 @@ impl World {
             if loaded.is_empty() {
                 return Ok(false);
             }
-            let chunk = self
-                .chunks
-                .get_mut(position)
-                .ok_or_else(|| StorageError::new("Chunk not registered"))?;
+            if !self.blocks.has_chunk(position) {
+                return Err(StorageError::new("Chunk not registered"));
+            }
             let data = ChunkBlocks::new(*position, loaded).map_err(|err| {
                 StorageError::new(format!("Chunk error while loading: {:?}", err))
             })?;
-            chunk
-                .apply_block_save(&data)
+            let chunk = CachedChunk::from_block_save(&data)
                 .map_err(|err| StorageError::new(format!("Failed to apply chunk: {:?}", err)))?;
+            self.blocks.insert_chunk(chunk);
             Ok(true)
         } else {
             Ok(false)
@@ -133,7 +759,7 @@ impl World {
     }
 
     pub fn load_all_chunks(&mut self) -> Result<(), StorageError> {
-        let positions: Vec<ChunkPosition> = self.chunks.keys().copied().collect();
+        let positions: Vec<ChunkPosition> = self.blocks.positions().copied().collect();
         for position in positions {
             self.load_chunk_blocks(&position)?;
         }
@@ -148,9 +774,252 @@ impl World {
         Box::new(|_| Ok(None))
     }
 
+    fn spawn_wave_enemies(&mut self) {
+        let Some(waves) = &self.waves else {
+            return;
+        };
+        let events = waves.events_at(self.tick);
+        for event in &events {
+            if let WaveEvent::Spawned(spawn) = event {
+                self.spawn_enemy(spawn.enemy, spawn.tile);
+            }
+        }
+        self.wave_events.extend(events);
+    }
+
+    /// Keeps the core list in step with a block change at `coord`.
+    fn track_core(&mut self, coord: WorldCoord, block: BlockId) {
+        if block == CORE {
+            if !self.cores.iter().any(|core| core.tile == coord) {
+                self.cores.push(CoreStructure::new(coord));
+            }
+        } else {
+            self.cores.retain(|core| core.tile != coord);
+        }
+    }
+
+    /// Tracks cores that reached the terrain without [`World::set_block`]: chunks the cache
+    /// loaded, generated or was handed, and writes made through [`World::chunk_cache_mut`].
+    fn sync_cores(&mut self) {
+        for position in self.blocks.take_fresh_chunks() {
+            let Some(chunk) = self.blocks.chunk(&position) else {
+                continue;
+            };
+            let core_tiles = chunk.tiles_holding(CORE);
+            self.cores.retain(|core| {
+                chunk_and_local_for_world_coord(core.tile).0 != position
+                    || core_tiles.contains(&core.tile)
+            });
+            for tile in core_tiles {
+                self.track_core(tile, CORE);
+            }
+        }
+    }
+
+    /// Logs this tick's deaths, takes dead enemies and warriors off the map and removes dead
+    /// drones, leaving their inventories on the ground.
+    fn remove_the_dead(&mut self) {
+        for enemy in self.enemies.iter().filter(|enemy| !enemy.is_alive()) {
+            self.combat_events.push(CombatEvent::Died {
+                tick: self.tick,
+                combatant: Combatant::Enemy(enemy.id),
+                tile: enemy.tile(),
+            });
+        }
+        self.enemies.retain(Enemy::is_alive);
+
+        for warrior in self.warriors.iter().filter(|warrior| !warrior.is_alive()) {
+            self.combat_events.push(CombatEvent::Died {
+                tick: self.tick,
+                combatant: Combatant::Warrior(warrior.id),
+                tile: warrior.tile,
+            });
+        }
+        self.warriors.retain(Warrior::is_alive);
+
+        let dead: Vec<usize> = (0..self.drones.len())
+            .filter(|&index| self.drones[index].health <= 0)
+            .collect();
+        for &index in &dead {
+            let tile = self.drones[index].tile();
+            self.combat_events.push(CombatEvent::Died {
+                tick: self.tick,
+                combatant: Combatant::Drone(self.drones[index].id),
+                tile,
+            });
+            let drop = ItemDrop::from_inventory(tile, &self.inventories[index]);
+            if !drop.is_empty() {
+                self.drops.push(drop);
+            }
+        }
+        // Highest index first so removing one drone does not shift the others still to go.
+        for &index in dead.iter().rev() {
+            self.remove_drone(index);
+        }
+    }
+
+    /// Losing the last core to enemies ends the game; so does outlasting every wave.
+    fn update_phase(&mut self, core_lost: bool) {
+        if core_lost && self.cores.is_empty() {
+            self.phase = GamePhase::Defeated;
+            return;
+        }
+        let waves_beaten = self.waves.as_ref().is_some_and(|waves| {
+            waves.all_spawned(self.tick) && !self.enemies.iter().any(Enemy::is_alive)
+        });
+        if waves_beaten {
+            self.phase = GamePhase::Victory;
+        }
+    }
+
     fn reset_inventories_for(&mut self, drone_count: usize) {
         self.inventories = vec![empty_inventory(); drone_count];
     }
+
+    fn advance_orders(&mut self, delta_seconds: f32) {
+        for index in 0..self.drones.len() {
+            if self.drones[index].health <= 0 {
+                continue;
+            }
+            let Some(order) = self
+                .orders
+                .get_mut(index)
+                .and_then(|queue| queue.front_mut())
+            else {
+                continue;
+            };
+
+            let finished = match order {
+                Order::Move(move_order) => {
+                    let next_tile = move_order.next_tile(self.drones[index].position);
+                    if !is_open(self.blocks.block_at_world(next_tile)) {
+                        Some(Err(OrderError::new(OrderErrorReason::PathBlocked)))
+                    } else {
+                        let arrived = move_order.advance(&mut self.drones[index], delta_seconds);
+                        arrived.then_some(Ok(OrderOutcome::Arrived {
+                            tile: move_order.target_tile,
+                        }))
+                    }
+                }
+                Order::Dig(dig_order) => {
+                    // The terrain may have changed since the dig was queued.
+                    if !dig_order.is_started()
+                        && let Err(error) = validate_dig_target(
+                            &self.blocks,
+                            self.drones[index].tile(),
+                            dig_order.target_tile,
+                        )
+                    {
+                        Some(Err(error))
+                    } else {
+                        if !dig_order.is_started() {
+                            // Unloaded targets finish at once and fail in `finish_dig`.
+                            let duration = self
+                                .blocks
+                                .block_at_world(dig_order.target_tile)
+                                .map_or(0.0, |block| dig_duration(&self.drones[index], block));
+                            dig_order.start(duration);
+                        }
+                        let (completed, progressed) = dig_order.advance(delta_seconds);
+                        if progressed {
+                            self.order_events.push(OrderEvent::Progressed {
+                                drone: self.drones[index].id,
+                                percent: dig_order.progress_percent(),
+                            });
+                        }
+                        let target = dig_order.target_tile;
+                        completed.then(|| self.finish_dig(index, target))
+                    }
+                }
+                Order::Craft(craft_order) => {
+                    let (completed, progressed) = craft_order.advance(delta_seconds);
+                    if progressed {
+                        self.order_events.push(OrderEvent::Progressed {
+                            drone: self.drones[index].id,
+                            percent: craft_order.progress_percent(),
+                        });
+                    }
+                    let (recipe, tile) = (craft_order.recipe, craft_order.tile);
+                    completed.then(|| self.finish_craft(index, recipe, tile))
+                }
+            };
+
+            let Some(result) = finished else {
+                continue;
+            };
+            let Some(order) = self.orders[index].pop_front() else {
+                continue;
+            };
+            // Whatever was queued behind a failed move assumed the drone would get there.
+            if result.is_err() && matches!(order, Order::Move(_)) {
+                self.orders[index].clear();
+            }
+            self.order_events.push(match result {
+                Ok(outcome) => OrderEvent::Completed {
+                    drone: self.drones[index].id,
+                    outcome,
+                },
+                Err(error) => OrderEvent::Failed {
+                    drone: self.drones[index].id,
+                    target: order.target_tile(),
+                    error,
+                },
+            });
+
+            if let Some(next) = self.orders[index].front() {
+                face_order_target(&mut self.drones[index], next);
+            }
+        }
+    }
+
+    fn finish_dig(
+        &mut self,
+        drone_index: usize,
+        target: WorldCoord,
+    ) -> Result<OrderOutcome, OrderError> {
+        let block = self
+            .blocks
+            .block_at_world(target)
+            .ok_or_else(|| OrderError::new(OrderErrorReason::TargetUnloaded))?;
+        let registry = BlockRegistry::global();
+        if !registry.is_solid(block) {
+            return Err(OrderError::new(OrderErrorReason::NothingToDig));
+        }
+
+        self.blocks.set_block(target, AIR)?;
+        self.track_core(target, AIR);
+        let item = registry.drop(block);
+        let collected = item.is_some_and(|item| self.add_item_to_inventory(drone_index, item));
+        if let Some(item) = item.filter(|_| !collected) {
+            self.leave_on_ground(target, item, 1);
+        }
+        Ok(OrderOutcome::Dug {
+            tile: target,
+            block,
+            collected,
+        })
+    }
+
+    fn finish_craft(
+        &mut self,
+        drone_index: usize,
+        recipe: RecipeId,
+        tile: WorldCoord,
+    ) -> Result<OrderOutcome, OrderError> {
+        let definition = RecipeRegistry::global()
+            .get(recipe)
+            .ok_or_else(|| CraftError::new(CraftErrorReason::UnknownRecipe))?;
+        // The station may have been dug out while the drone was busy.
+        if !definition.station_in_reach(&self.blocks, tile) {
+            return Err(CraftError::new(CraftErrorReason::MissingStation).into());
+        }
+        let slots = self
+            .inventories
+            .get_mut(drone_index)
+            .ok_or_else(|| OrderError::new(OrderErrorReason::UnknownDrone))?;
+        definition.craft(slots)?;
+        Ok(OrderOutcome::Crafted { recipe })
+    }
 }
 
 impl Default for World {
@@ -164,8 +1033,18 @@ mod tests {
     use super::*;
     use crate::WorldCoord;
     use crate::chunk::{CHUNK_DEPTH, CHUNK_HEIGHT, CHUNK_WIDTH};
-    use crate::worldgen::DeterministicMap;
-    use crate::{INVENTORY_SLOTS, IRON, MAX_INVENTORY_UNITS, STONE};
+    use crate::coordinates::LocalBlockCoord;
+    use crate::inventory::{item_count, stack_limit};
+    use crate::map_import::ImportedMap;
+    use crate::order::{DigOrder, MoveOrder};
+    use crate::recipe::{RECIPE_CORE, RECIPE_IRON_PLATE};
+    use crate::structure::CORE_MAX_HP;
+    use crate::test_support::arena;
+    use crate::wave::{EnemyKind, EnemySpawn, WaveSchedule};
+    use crate::worldgen::{DeterministicMap, WorldGenerator};
+    use crate::{CORE, DIRT, INVENTORY_SLOTS, IRON, ITEM_CORE, ITEM_IRON_ORE, ITEM_STONE, STONE};
+
+    const TICKS_PER_SECOND_U64: u64 = TICKS_PER_SECOND as u64;
 
     fn test_positions() -> ChunkPosition {
         ChunkPosition::new(0, 0, 0)
@@ -188,8 +1067,7 @@ mod tests {
 
         let mut world = World::new_with_callbacks(save, load);
         world.register_chunk(position, 0);
-        let coord = LocalBlockCoord::new(0, 0, 0);
-        world.set_block(position, coord, 7).unwrap();
+        world.set_block(WorldCoord::new(0, 0, 0), 7).unwrap();
         world.save_chunk_blocks(&position).unwrap();
 
         let saved = saved.lock().unwrap();
@@ -210,16 +1088,7 @@ mod tests {
         let mut world = World::new_with_callbacks(save, load);
         world.register_chunk(position, 0);
         world.load_chunk_blocks(&position).unwrap();
-        let coord = LocalBlockCoord::new(1, 0, 0);
-        assert_eq!(
-            world
-                .chunks
-                .get(&position)
-                .unwrap()
-                .get_block(coord)
-                .unwrap(),
-            1
-        );
+        assert_eq!(world.block_at(WorldCoord::new(1, 0, 0)), Some(1));
     }
 
     #[test]
@@ -241,35 +1110,29 @@ mod tests {
         world.load_all_chunks().unwrap();
 
         assert_eq!(calls.load(Ordering::SeqCst), 1);
-        let coord = LocalBlockCoord::new(0, 0, 0);
-        assert_eq!(
-            world
-                .chunks
-                .get(&position)
-                .unwrap()
-                .get_block(coord)
-                .unwrap(),
-            2
-        );
+        assert_eq!(world.block_at(WorldCoord::new(0, 0, 0)), Some(2));
     }
 
     #[test]
-    fn registers_generated_chunk() {
-        let position = test_positions();
-        let mut world = World::new();
+    fn generates_missing_chunks_from_its_generator() {
         let generator = DeterministicMap::new(42);
+        let mut world = World::with_generator(generator.clone());
+        let coord = WorldCoord::new(10, 0, -1);
 
-        world.register_generated_chunk(position, &generator);
+        assert_eq!(world.block_at(coord), Some(generator.block_at(coord)));
+        world.set_block(coord, AIR).unwrap();
+
+        assert_eq!(world.block_at(coord), Some(AIR));
+        assert!(world.chunk_cache().has_chunk(&ChunkPosition::new(0, 0, -1)));
+    }
+
+    #[test]
+    fn set_block_without_chunk_or_generator_is_out_of_bounds() {
+        let mut world = World::new();
 
-        let coord = LocalBlockCoord::new(10, 0, 0);
         assert_eq!(
-            world
-                .chunks
-                .get(&position)
-                .unwrap()
-                .get_block(coord)
-                .unwrap(),
-            generator.block_at(WorldCoord::new(10, 0, 0))
+            world.set_block(WorldCoord::new(0, 0, 0), STONE),
+            Err(ChunkError::OutOfBounds)
         );
     }
 
@@ -287,52 +1150,611 @@ mod tests {
     }
 
     #[test]
-    fn add_block_to_inventory_stacks_and_fills_new_slot() {
+    fn add_item_to_inventory_stacks_and_fills_new_slot() {
         let mut world = World::new();
         world.add_drone(DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10));
 
-        assert!(world.add_block_to_inventory(0, STONE));
-        assert!(world.add_block_to_inventory(0, STONE));
-        assert!(world.add_block_to_inventory(0, IRON));
+        assert!(world.add_item_to_inventory(0, ITEM_STONE));
+        assert!(world.add_item_to_inventory(0, ITEM_STONE));
+        assert!(world.add_item_to_inventory(0, ITEM_IRON_ORE));
 
         let slots = world.inventory(0).unwrap();
-        let stone_slot = slots.iter().find(|slot| slot.block == Some(STONE)).unwrap();
+        let stone_slot = slots
+            .iter()
+            .find(|slot| slot.item == Some(ITEM_STONE))
+            .unwrap();
         assert_eq!(stone_slot.count, 2);
-        let iron_slot = slots.iter().find(|slot| slot.block == Some(IRON)).unwrap();
+        let iron_slot = slots
+            .iter()
+            .find(|slot| slot.item == Some(ITEM_IRON_ORE))
+            .unwrap();
         assert_eq!(iron_slot.count, 1);
     }
 
     #[test]
-    fn add_block_to_full_inventory_returns_false() {
+    fn add_item_to_full_inventory_returns_false() {
         let mut world = World::new();
         world.add_drone(DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10));
 
         for i in 0..INVENTORY_SLOTS {
-            let block = (i as BlockId) + 10;
-            assert!(world.add_block_to_inventory(0, block));
+            let item = (i as ItemId) + 10;
+            assert!(world.add_item_to_inventory(0, item));
         }
 
-        assert!(!world.add_block_to_inventory(0, 999));
+        assert!(!world.add_item_to_inventory(0, 999));
     }
 
     #[test]
-    fn add_block_to_inventory_respects_unit_capacity() {
+    fn add_item_to_inventory_respects_stack_limits() {
         let mut world = World::new();
         world.add_drone(DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10));
 
-        for _ in 0..MAX_INVENTORY_UNITS {
-            assert!(world.add_block_to_inventory(0, STONE));
+        let capacity = stack_limit(ITEM_STONE) * INVENTORY_SLOTS as u32;
+        for _ in 0..capacity {
+            assert!(world.add_item_to_inventory(0, ITEM_STONE));
+        }
+
+        assert!(!world.add_item_to_inventory(0, ITEM_STONE));
+        assert!(
+            world
+                .inventory(0)
+                .unwrap()
+                .iter()
+                .all(|slot| slot.count == stack_limit(ITEM_STONE))
+        );
+    }
+
+    #[test]
+    fn transfer_items_between_neighbouring_drones() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        world.add_drone(DronePose::new([2.0, 2.0, 0.0], [1.0, 0.0], "d2", 10, 10));
+        world.add_drone(DronePose::new([5.0, 1.0, 0.0], [1.0, 0.0], "d3", 10, 10));
+        for _ in 0..6 {
+            assert!(world.add_item_to_inventory(0, ITEM_STONE));
         }
 
-        assert!(!world.add_block_to_inventory(0, STONE));
+        assert_eq!(world.transfer_items(0, 1, ITEM_STONE, 4), Ok(4));
+        assert_eq!(world.transfer_items(0, 1, ITEM_STONE, 4), Ok(2));
+        assert_eq!(item_count(world.inventory(1).unwrap(), ITEM_STONE), 6);
 
-        let total_units: u32 = world
-            .inventory(0)
+        let err = world.transfer_items(1, 2, ITEM_STONE, 1).unwrap_err();
+        assert_eq!(err.reason, InventoryErrorReason::OutOfReach);
+        let err = world.transfer_items(1, 1, ITEM_STONE, 1).unwrap_err();
+        assert_eq!(err.reason, InventoryErrorReason::SameInventory);
+        let err = world.transfer_items(1, 9, ITEM_STONE, 1).unwrap_err();
+        assert_eq!(err.reason, InventoryErrorReason::UnknownInventory);
+    }
+
+    #[test]
+    fn deposit_items_only_next_to_a_core() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[]);
+        for _ in 0..3 {
+            assert!(world.add_item_to_inventory(0, ITEM_IRON_ORE));
+        }
+
+        let err = world.deposit_items(0, ITEM_IRON_ORE, 3).unwrap_err();
+        assert_eq!(err.reason, InventoryErrorReason::OutOfReach);
+
+        world.set_block(WorldCoord::new(2, 2, 0), CORE).unwrap();
+        assert_eq!(world.deposit_items(0, ITEM_IRON_ORE, 5), Ok(3));
+        assert_eq!(world.resources().amount(ITEM_IRON_ORE), 3);
+        assert_eq!(item_count(world.inventory(0).unwrap(), ITEM_IRON_ORE), 0);
+    }
+
+    fn world_with_drone_at(position: [f32; 3]) -> World {
+        let mut world = World::new();
+        world.add_drone(DronePose::new(position, [1.0, 0.0], "d1", 10, 10));
+        world
+    }
+
+    fn load_blocks(world: &mut World, blocks: &[(WorldCoord, BlockId)]) {
+        let position = test_positions();
+        let mut chunk = Chunk::new(position, AIR);
+        for &(coord, block) in blocks {
+            let local = LocalBlockCoord::new(coord.x as usize, coord.y as usize, coord.z as usize);
+            chunk.set_block(local, block).unwrap();
+        }
+        world
+            .chunk_cache_mut()
+            .insert_chunk(CachedChunk::from_chunk(&chunk));
+    }
+
+    fn step_seconds(world: &mut World, seconds: f32) {
+        let ticks = (seconds * TICKS_PER_SECOND as f32).ceil() as u32;
+        for _ in 0..ticks {
+            world.step();
+        }
+    }
+
+    #[test]
+    fn craft_order_takes_recipe_time_before_swapping_items() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[]);
+        assert!(world.add_item_to_inventory(0, ITEM_STONE));
+
+        world.craft(0, RECIPE_CORE).unwrap();
+        step_seconds(&mut world, 0.5);
+        assert_eq!(world.inventory(0).unwrap()[0].item, Some(ITEM_STONE));
+
+        step_seconds(&mut world, 0.6);
+        assert_eq!(world.inventory(0).unwrap()[0].item, Some(ITEM_CORE));
+        assert_eq!(
+            world.drain_order_events().last(),
+            Some(&OrderEvent::Completed {
+                drone: 0,
+                outcome: OrderOutcome::Crafted {
+                    recipe: RECIPE_CORE
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn craft_requires_inputs_and_station() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[]);
+
+        let err = world.craft(0, RECIPE_CORE).unwrap_err();
+        assert_eq!(
+            err.reason,
+            OrderErrorReason::Craft(CraftError::new(CraftErrorReason::MissingInputs))
+        );
+
+        assert!(world.add_item_to_inventory(0, ITEM_IRON_ORE));
+        assert!(world.add_item_to_inventory(0, ITEM_IRON_ORE));
+        let err = world.craft(0, RECIPE_IRON_PLATE).unwrap_err();
+        assert_eq!(
+            err.reason,
+            OrderErrorReason::Craft(CraftError::new(CraftErrorReason::MissingStation))
+        );
+
+        world.set_block(WorldCoord::new(2, 1, 0), CORE).unwrap();
+        world.craft(0, RECIPE_IRON_PLATE).unwrap();
+        assert!(matches!(world.current_order(0), Some(Order::Craft(_))));
+    }
+
+    #[test]
+    fn step_reports_waves_on_the_world_clock() {
+        let schedule = WaveSchedule::from_json(
+            r#"[{ "delay_seconds": 1.0, "groups": [{ "enemy": "brute", "count": 1 }] }]"#,
+        )
+        .unwrap();
+        let arena = TileBox3::new(WorldCoord::new(0, 0, 0), WorldCoord::new(9, 9, 0)).unwrap();
+        let mut world = World::new();
+        world.set_waves(Waves::new(&schedule, 1, arena, world.tick));
+        assert_eq!(world.wave_status_text().as_deref(), Some("Wave 1 in 00:01"));
+
+        step_seconds(&mut world, 1.0);
+
+        let events = world.drain_wave_events();
+        assert_eq!(events[0], WaveEvent::Started { wave: 0 });
+        assert!(matches!(
+            events[1],
+            WaveEvent::Spawned(EnemySpawn {
+                enemy: EnemyKind::Brute,
+                tick: TICKS_PER_SECOND_U64,
+                ..
+            })
+        ));
+        assert_eq!(events.last(), Some(&WaveEvent::AllSpawned));
+        assert!(world.drain_wave_events().is_empty());
+    }
+
+    #[test]
+    fn cores_that_arrive_with_terrain_are_tracked() {
+        let map = ImportedMap::from_ascii("z=0\n..C\n").unwrap();
+        let mut world = World::with_generator(map);
+        world
+            .chunk_cache_mut()
+            .load_block_at(WorldCoord::new(0, 0, 0));
+        let direct = WorldCoord::new(5, 5, 0);
+        world.chunk_cache_mut().set_block(direct, CORE).unwrap();
+
+        world.step();
+
+        let tracked: Vec<(WorldCoord, i32)> = world
+            .cores()
+            .iter()
+            .map(|core| (core.tile, core.hp))
+            .collect();
+        assert_eq!(
+            tracked,
+            vec![
+                (WorldCoord::new(2, 0, 0), CORE_MAX_HP),
+                (direct, CORE_MAX_HP)
+            ]
+        );
+
+        world.chunk_cache_mut().set_block(direct, AIR).unwrap();
+        world.step();
+        assert_eq!(world.cores().len(), 1);
+    }
+
+    #[test]
+    fn losing_the_last_core_defeats_the_player() {
+        let mut world = arena();
+        let core = WorldCoord::new(4, 4, 0);
+        world.set_block(core, CORE).unwrap();
+        assert_eq!(world.primary_core().map(|core| core.hp), Some(CORE_MAX_HP));
+        world.damage_core(core, CORE_MAX_HP - 1);
+        world.spawn_enemy(EnemyKind::Brute, WorldCoord::new(5, 4, 0));
+
+        step_seconds(&mut world, 1.0);
+
+        assert_eq!(world.phase(), GamePhase::Defeated);
+        assert_eq!(world.block_at(core), Some(AIR));
+        assert!(world.cores().is_empty());
+        assert_eq!(
+            world.drain_structure_events(),
+            vec![
+                StructureEvent::Damaged {
+                    tile: core,
+                    damage: CORE_MAX_HP - 1,
+                    hp: 1,
+                },
+                StructureEvent::Damaged {
+                    tile: core,
+                    damage: 3,
+                    hp: 0,
+                },
+                StructureEvent::Destroyed { tile: core },
+            ]
+        );
+        assert_eq!(
+            world.drain_combat_events(),
+            vec![
+                CombatEvent::Hit {
+                    tick: world.tick,
+                    attacker: Combatant::Enemy(0),
+                    target: Combatant::Core(core),
+                    damage: 3,
+                    health: 0,
+                },
+                CombatEvent::Died {
+                    tick: world.tick,
+                    combatant: Combatant::Core(core),
+                    tile: core,
+                },
+            ]
+        );
+        let tick = world.tick;
+        world.step();
+        assert_eq!(world.tick, tick);
+    }
+
+    #[test]
+    fn clearing_every_wave_wins_the_game() {
+        let schedule = WaveSchedule::from_json(
+            r#"[{ "delay_seconds": 0.5, "groups": [{ "enemy": "crawler", "count": 1 }] }]"#,
+        )
+        .unwrap();
+        let arena = TileBox3::new(WorldCoord::new(0, 0, 0), WorldCoord::new(9, 9, 0)).unwrap();
+        let mut world = World::new();
+        world.set_waves(Waves::new(&schedule, 1, arena, world.tick));
+
+        step_seconds(&mut world, 1.0);
+        assert_eq!(world.phase(), GamePhase::Running);
+
+        world.enemies[0].health = 0;
+        world.step();
+        assert_eq!(world.phase(), GamePhase::Victory);
+    }
+
+    #[test]
+    fn step_moves_drone_and_reports_arrival() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[]);
+        let target = WorldCoord::new(4, 1, 0);
+        let order = MoveOrder::for_target(world.drones()[0].position, target).unwrap();
+        world.issue_order(0, Order::Move(order)).unwrap();
+
+        step_seconds(&mut world, 1.0);
+
+        assert_eq!(world.drones()[0].position, [4.0, 1.0, 0.0]);
+        assert!(world.current_order(0).is_none());
+        assert_eq!(
+            world.drain_order_events(),
+            vec![OrderEvent::Completed {
+                drone: 0,
+                outcome: OrderOutcome::Arrived { tile: target },
+            }]
+        );
+        assert!(world.drain_order_events().is_empty());
+    }
+
+    #[test]
+    fn order_events_name_the_drone_after_an_earlier_one_dies() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        let mover = world.add_drone(DronePose::new([1.0, 2.0, 0.0], [1.0, 0.0], "d2", 10, 10));
+        load_blocks(&mut world, &[]);
+        let target = WorldCoord::new(3, 2, 0);
+        let order = MoveOrder::for_target(world.drones()[1].position, target).unwrap();
+        world.issue_order(1, Order::Move(order)).unwrap();
+        world.drones_mut()[0].health = 0;
+
+        step_seconds(&mut world, 1.0);
+
+        assert_eq!(world.drone_index(mover), Some(0));
+        assert_eq!(
+            world.drain_order_events(),
+            vec![OrderEvent::Completed {
+                drone: mover,
+                outcome: OrderOutcome::Arrived { tile: target },
+            }]
+        );
+    }
+
+    #[test]
+    fn dig_order_removes_block_and_collects_stone() {
+        let target = WorldCoord::new(2, 1, 0);
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[(target, STONE)]);
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(target)))
+            .unwrap();
+
+        step_seconds(&mut world, 5.0);
+
+        assert_eq!(world.block_at(target), Some(AIR));
+        assert_eq!(world.inventory(0).unwrap()[0].item, Some(ITEM_STONE));
+        let events = world.drain_order_events();
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, OrderEvent::Progressed { percent: 50, .. }))
+        );
+        assert_eq!(
+            events.last(),
+            Some(&OrderEvent::Completed {
+                drone: 0,
+                outcome: OrderOutcome::Dug {
+                    tile: target,
+                    block: STONE,
+                    collected: true,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn dig_time_depends_on_block_and_mining_speed() {
+        let dirt = WorldCoord::new(2, 1, 0);
+        let iron = WorldCoord::new(0, 1, 0);
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[(dirt, DIRT), (iron, IRON)]);
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(dirt)))
+            .unwrap();
+        world
+            .queue_order(0, Order::Dig(DigOrder::new(iron)))
+            .unwrap();
+
+        step_seconds(&mut world, 3.0);
+        assert_eq!(world.block_at(dirt), Some(AIR));
+        assert_eq!(world.block_at(iron), Some(IRON));
+        let Some(Order::Dig(iron_dig)) = world.current_order(0) else {
+            panic!("iron dig should be running");
+        };
+        assert!(iron_dig.duration().unwrap() > 2.0 * dig_duration(&world.drones()[0], DIRT));
+
+        let mut upgraded = world_with_drone_at([1.0, 1.0, 0.0]);
+        upgraded.drones_mut()[0].mining_speed = 4.0;
+        load_blocks(&mut upgraded, &[(iron, IRON)]);
+        upgraded
+            .issue_order(0, Order::Dig(DigOrder::new(iron)))
+            .unwrap();
+        step_seconds(&mut upgraded, 2.5);
+        assert_eq!(upgraded.block_at(iron), Some(AIR));
+    }
+
+    #[test]
+    fn queued_orders_run_in_sequence_and_failures_are_reported() {
+        let dirt = WorldCoord::new(2, 1, 0);
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[(dirt, DIRT)]);
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(dirt)))
+            .unwrap();
+        world
+            .queue_order(0, Order::Dig(DigOrder::new(dirt)))
+            .unwrap();
+        assert_eq!(world.queued_orders(0).count(), 2);
+
+        step_seconds(&mut world, 10.0);
+
+        let finished: Vec<OrderEvent> = world
+            .drain_order_events()
+            .into_iter()
+            .filter(|event| !matches!(event, OrderEvent::Progressed { .. }))
+            .collect();
+        assert_eq!(
+            finished,
+            vec![
+                OrderEvent::Completed {
+                    drone: 0,
+                    outcome: OrderOutcome::Dug {
+                        tile: dirt,
+                        block: DIRT,
+                        collected: false,
+                    },
+                },
+                OrderEvent::Failed {
+                    drone: 0,
+                    target: dirt,
+                    error: OrderError::new(OrderErrorReason::NothingToDig),
+                },
+            ]
+        );
+        assert_eq!(world.inventory(0).unwrap()[0].count, 0);
+    }
+
+    #[test]
+    fn queued_digs_are_rechecked_when_they_start() {
+        let dirt = WorldCoord::new(2, 1, 0);
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[(dirt, DIRT)]);
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(dirt)))
+            .unwrap();
+        world.set_block(dirt, AIR).unwrap();
+
+        world.step();
+
+        assert_eq!(
+            world.drain_order_events(),
+            vec![OrderEvent::Failed {
+                drone: 0,
+                target: dirt,
+                error: OrderError::new(OrderErrorReason::NothingToDig),
+            }]
+        );
+    }
+
+    #[test]
+    fn drone_descends_through_dug_shaft() {
+        let floor: Vec<(WorldCoord, BlockId)> = (0..8)
+            .flat_map(|x| (0..8).map(move |y| (WorldCoord::new(x, y, 0), STONE)))
+            .collect();
+        let mut world = world_with_drone_at([2.0, 2.0, 1.0]);
+        load_blocks(&mut world, &floor);
+        let shaft = WorldCoord::new(2, 2, 0);
+
+        crate::order::validate_dig_target(world.chunk_cache(), world.drones()[0].tile(), shaft)
+            .unwrap();
+        world
+            .issue_order(0, Order::Dig(DigOrder::new(shaft)))
+            .unwrap();
+        step_seconds(&mut world, 5.0);
+        assert_eq!(world.block_at(shaft), Some(AIR));
+
+        let drone = world.drones()[0].clone();
+        let order = MoveOrder::plan(world.chunk_cache_mut(), &drone, shaft)
             .unwrap()
+            .unwrap();
+        world.issue_order(0, Order::Move(order)).unwrap();
+        step_seconds(&mut world, 1.0);
+
+        assert_eq!(world.drones()[0].position, [2.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn mine_job_clears_box_across_levels() {
+        let tile_box = TileBox3::new(WorldCoord::new(2, 2, 0), WorldCoord::new(4, 2, 1)).unwrap();
+        let stone: Vec<(WorldCoord, BlockId)> =
+            tile_box.tiles().map(|coord| (coord, STONE)).collect();
+        let mut world = world_with_drone_at([0.0, 0.0, 1.0]);
+        load_blocks(&mut world, &stone);
+
+        let job_id = world.add_mine_job(tile_box);
+        world.assign_job(0, job_id).unwrap();
+        step_seconds(&mut world, 45.0);
+
+        assert!(
+            tile_box
+                .tiles()
+                .all(|coord| world.block_at(coord) == Some(AIR))
+        );
+        let job = world.job(job_id).unwrap();
+        assert!(job.is_finished());
+        assert_eq!(job.progress().dug, 6);
+        assert_eq!(world.drone_job(0), None);
+        assert_eq!(world.inventory(0).unwrap()[0].count, 6);
+    }
+
+    #[test]
+    fn mine_job_survives_full_inventory() {
+        let tile_box = TileBox3::new(WorldCoord::new(2, 2, 0), WorldCoord::new(3, 2, 0)).unwrap();
+        let stone: Vec<(WorldCoord, BlockId)> =
+            tile_box.tiles().map(|coord| (coord, STONE)).collect();
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &stone);
+        while world.add_item_to_inventory(0, ITEM_IRON_ORE) {}
+
+        let job_id = world.add_mine_job(tile_box);
+        world.assign_job(0, job_id).unwrap();
+        step_seconds(&mut world, 15.0);
+
+        let job = world.job(job_id).unwrap();
+        assert!(job.is_finished());
+        assert_eq!(job.dropped_blocks(), 2);
+        assert!(
+            tile_box
+                .tiles()
+                .all(|coord| world.block_at(coord) == Some(AIR))
+        );
+        let mut left: Vec<(WorldCoord, ItemId, u32)> = world
+            .drops()
             .iter()
-            .map(|slot| slot.count)
-            .sum();
+            .flat_map(|drop| {
+                drop.items
+                    .iter()
+                    .map(move |stack| (drop.tile, stack.item, stack.count))
+            })
+            .collect();
+        left.sort_by_key(|&(coord, ..)| (coord.x, coord.y, coord.z));
+        assert_eq!(
+            left,
+            tile_box
+                .tiles()
+                .map(|coord| (coord, ITEM_STONE, 1))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn manual_order_takes_drone_off_job() {
+        let tile = WorldCoord::new(2, 1, 0);
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[(tile, STONE)]);
+        let job_id = world.add_mine_job(TileBox3::new(tile, tile).unwrap());
+        world.assign_job(0, job_id).unwrap();
+        world.step();
+        assert_eq!(world.job(job_id).unwrap().tile_assigned_to(0), Some(tile));
+
+        let order = MoveOrder::for_target(world.drones()[0].position, WorldCoord::new(1, 3, 0));
+        world.issue_order(0, Order::Move(order.unwrap())).unwrap();
+
+        assert_eq!(world.drone_job(0), None);
+        assert_eq!(world.job(job_id).unwrap().progress().pending, 1);
+    }
+
+    #[test]
+    fn moves_stop_at_unloaded_terrain() {
+        let mut world = world_with_drone_at([1.0, 1.0, 0.0]);
+        load_blocks(&mut world, &[]);
+        let order = MoveOrder::for_target(world.drones()[0].position, WorldCoord::new(1, -2, 0));
+        world.issue_order(0, Order::Move(order.unwrap())).unwrap();
+
+        step_seconds(&mut world, 5.0);
+
+        assert!(world.drones()[0].position[1] > -0.5);
+        assert_eq!(
+            world.drain_order_events(),
+            vec![OrderEvent::Failed {
+                drone: 0,
+                target: WorldCoord::new(1, -2, 0),
+                error: OrderError::new(OrderErrorReason::PathBlocked),
+            }]
+        );
+    }
 
-        assert_eq!(total_units, MAX_INVENTORY_UNITS);
+    #[test]
+    fn orders_for_unknown_drone_are_rejected() {
+        let mut world = World::new();
+        let err = world
+            .issue_order(0, Order::Dig(DigOrder::new(WorldCoord::new(0, 0, 0))))
+            .unwrap_err();
+        assert_eq!(err.reason, OrderErrorReason::UnknownDrone);
+    }
+
+    #[test]
+    fn placing_for_unknown_drone_is_rejected() {
+        let mut world = World::new();
+        let mut tool = ToolController::new();
+        let err = world
+            .place_selected_block(&mut tool, 0, WorldCoord::new(1, 0, 0))
+            .unwrap_err();
+        assert_eq!(err.reason, PlacementErrorReason::UnknownDrone);
     }
 }
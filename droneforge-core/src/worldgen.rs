@@ -1,5 +1,4 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::fmt;
 
 use fastrand::Rng;
 
@@ -10,6 +9,80 @@ use crate::coordinates::{ChunkPosition, LocalBlockCoord, WorldCoord};
 pub const HORIZONTAL_LIMIT: i32 = 1024;
 pub const VERTICAL_LIMIT: i32 = 65;
 
+/// Top solid layer of the spawn plateau; drones start standing in a pocket carved into it.
+/// The plateau is bare rock so the first digs yield stone.
+const SURFACE_LEVEL: i32 = 0;
+const DIRT_DEPTH: i32 = 3;
+const HILL_HEIGHT: f32 = 10.0;
+const HILL_SCALE: f32 = 48.0;
+/// Terrain is flat and cave-free within this many blocks of the origin, then blends into the
+/// hills over the next `SPAWN_BLEND` blocks.
+const SPAWN_RADIUS: f32 = 16.0;
+const SPAWN_BLEND: f32 = 24.0;
+const CAVE_SCALE: f32 = 12.0;
+const CAVE_THRESHOLD: f32 = 0.68;
+/// Caves stay this far below the surface so hills are not hollow shells.
+const CAVE_ROOF: i32 = 4;
+const VEIN_SCALE: f32 = 5.0;
+/// Vein noise needed for iron just under the dirt, and how much it drops at the bottom of the
+/// map: ore gets more common the deeper a drone digs.
+const SHALLOW_VEIN_THRESHOLD: f32 = 0.85;
+const DEEP_VEIN_BONUS: f32 = 0.1;
+
+const HILL_SALT: u64 = 1;
+const CAVE_SALT: u64 = 2;
+const VEIN_SALT: u64 = 3;
+
+/// Produces the terrain of a world. Implementations must be deterministic: the same
+/// coordinate always yields the same block, so chunks can be regenerated on demand.
+pub trait WorldGenerator: fmt::Debug + Send + Sync {
+    fn block_at(&self, coord: WorldCoord) -> BlockId;
+
+    fn populate_chunk(&self, chunk: &mut Chunk, position: ChunkPosition) {
+        chunk.position = position;
+        for z in 0..CHUNK_HEIGHT {
+            for y in 0..CHUNK_DEPTH {
+                for x in 0..CHUNK_WIDTH {
+                    let local = LocalBlockCoord::new(x, y, z);
+                    let block = self.block_at(offset(chunk_origin(position), x, y, z));
+                    chunk
+                        .set_block(local, block)
+                        .expect("Local coordinate must be in chunk bounds");
+                }
+            }
+        }
+    }
+
+    fn chunk_for_position(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(position, AIR);
+        self.populate_chunk(&mut chunk, position);
+        chunk
+    }
+}
+
+/// A level world: `block` up to and including `surface`, air above. Handy for tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlatGenerator {
+    pub surface: i32,
+    pub block: BlockId,
+}
+
+impl FlatGenerator {
+    pub fn new(surface: i32, block: BlockId) -> Self {
+        Self { surface, block }
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn block_at(&self, coord: WorldCoord) -> BlockId {
+        if coord.z <= self.surface {
+            self.block
+        } else {
+            AIR
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DeterministicMap {
     seed: u64,
@@ -20,76 +93,138 @@ impl DeterministicMap {
         Self { seed }
     }
 
-    pub fn block_at(&self, coord: WorldCoord) -> BlockId {
-        let shifted = WorldCoord::new(coord.x, coord.y, coord.z.saturating_add(1));
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 
-        if !self.within_bounds(shifted) {
-            return AIR;
-        }
+    /// Highest solid `z` of the column at `(x, y)`.
+    pub fn surface_height(&self, x: i32, y: i32) -> i32 {
+        let hills = self.fractal_noise_2d(HILL_SALT, x as f32 / HILL_SCALE, y as f32 / HILL_SCALE);
+        let offset = (hills * 2.0 - 1.0) * HILL_HEIGHT * spawn_falloff(x, y);
+        SURFACE_LEVEL + offset.round() as i32
+    }
 
+    fn block_in_column(&self, coord: WorldCoord, surface: i32) -> BlockId {
         // Ensure (1,0,0) and (2,0,0) are always air
-        if (coord.x == 1 && coord.y == 0 && coord.z == 0)
-            || (coord.x == 2 && coord.y == 0 && coord.z == 0)
-        {
+        if (coord.x == 1 || coord.x == 2) && coord.y == 0 && coord.z == 0 {
             return AIR;
         }
 
-        if shifted.z == VERTICAL_LIMIT {
-            return AIR;
+        if coord.z == -VERTICAL_LIMIT - 1 {
+            return BEDROCK;
         }
 
-        if shifted.z == -VERTICAL_LIMIT {
-            return BEDROCK;
+        if coord.z > surface || coord.z >= VERTICAL_LIMIT - 1 {
+            return AIR;
         }
 
-        if shifted.x <= 0 {
-            if (-4..=0).contains(&shifted.z) {
-                return DIRT;
-            }
+        let depth = surface - coord.z;
+        let falloff = spawn_falloff(coord.x, coord.y);
+        if depth < (DIRT_DEPTH as f32 * falloff).round() as i32 {
+            return DIRT;
+        }
 
-            if (-64..=-5).contains(&shifted.z) {
-                let mut rng = self.rng_for_coord(shifted);
-                if rng.u32(0..100) < 5 {
-                    return IRON;
-                }
-                return STONE;
+        if depth >= CAVE_ROOF && falloff >= 1.0 {
+            let cave = self.fractal_noise_3d(CAVE_SALT, coord, CAVE_SCALE);
+            if cave > CAVE_THRESHOLD {
+                return AIR;
             }
-
-            return AIR;
         }
 
-        if (shifted.x > 0 && (-64..=0).contains(&shifted.z))
-            || (shifted.x - shifted.z + 1 > 0 && shifted.z > 0)
-        {
-            let mut rng = self.rng_for_coord(shifted);
-            if rng.u32(0..100) < 5 {
-                return IRON;
-            }
-            return STONE;
+        let depth_ratio = (SURFACE_LEVEL - coord.z).max(0) as f32 / VERTICAL_LIMIT as f32;
+        let vein_threshold = SHALLOW_VEIN_THRESHOLD - DEEP_VEIN_BONUS * depth_ratio.min(1.0);
+        if self.value_noise_3d(VEIN_SALT, coord, VEIN_SCALE) > vein_threshold {
+            return IRON;
         }
 
-        AIR
+        STONE
     }
 
-    pub fn chunk_for_position(&self, position: ChunkPosition) -> Chunk {
-        let mut chunk = Chunk::new(position, AIR);
-        self.populate_chunk(&mut chunk, position);
-        chunk
+    /// Two octaves of value noise, in `0.0..1.0`.
+    fn fractal_noise_2d(&self, salt: u64, x: f32, y: f32) -> f32 {
+        let coarse = self.value_noise_2d(salt, x, y);
+        let fine = self.value_noise_2d(salt.wrapping_add(0x100), x * 2.0, y * 2.0);
+        (coarse * 2.0 + fine) / 3.0
     }
 
-    pub fn populate_chunk(&self, chunk: &mut Chunk, position: ChunkPosition) {
-        chunk.position = position;
-        let base_x = position.x * CHUNK_WIDTH as i32;
-        let base_y = position.y * CHUNK_DEPTH as i32;
-        let base_z = position.z * CHUNK_HEIGHT as i32;
+    fn fractal_noise_3d(&self, salt: u64, coord: WorldCoord, scale: f32) -> f32 {
+        let coarse = self.value_noise_3d(salt, coord, scale);
+        let fine = self.value_noise_3d(salt.wrapping_add(0x100), coord, scale / 2.0);
+        (coarse * 2.0 + fine) / 3.0
+    }
 
-        for z in 0..CHUNK_HEIGHT {
-            for y in 0..CHUNK_DEPTH {
-                for x in 0..CHUNK_WIDTH {
-                    let world_coord =
-                        WorldCoord::new(base_x + x as i32, base_y + y as i32, base_z + z as i32);
+    fn value_noise_2d(&self, salt: u64, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (smoothstep(x - x0), smoothstep(y - y0));
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let corner = |dx: i32, dy: i32| self.lattice_value(salt, x0 + dx, y0 + dy, 0);
+
+        let bottom = lerp(corner(0, 0), corner(1, 0), tx);
+        let top = lerp(corner(0, 1), corner(1, 1), tx);
+        lerp(bottom, top, ty)
+    }
+
+    fn value_noise_3d(&self, salt: u64, coord: WorldCoord, scale: f32) -> f32 {
+        let (x, y, z) = (
+            coord.x as f32 / scale,
+            coord.y as f32 / scale,
+            coord.z as f32 / scale,
+        );
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (tx, ty, tz) = (smoothstep(x - x0), smoothstep(y - y0), smoothstep(z - z0));
+        let (x0, y0, z0) = (x0 as i32, y0 as i32, z0 as i32);
+        let corner =
+            |dx: i32, dy: i32, dz: i32| self.lattice_value(salt, x0 + dx, y0 + dy, z0 + dz);
+
+        let face = |dz: i32| {
+            let front = lerp(corner(0, 0, dz), corner(1, 0, dz), tx);
+            let back = lerp(corner(0, 1, dz), corner(1, 1, dz), tx);
+            lerp(front, back, ty)
+        };
+        lerp(face(0), face(1), tz)
+    }
+
+    /// A value in `0.0..1.0` fixed by the seed, the noise layer, and the lattice point.
+    fn lattice_value(&self, salt: u64, x: i32, y: i32, z: i32) -> f32 {
+        let mut hash = mix(self.seed ^ salt.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        for axis in [x, y, z] {
+            hash = mix(hash ^ axis as u32 as u64);
+        }
+        Rng::with_seed(hash).f32()
+    }
+
+    fn within_bounds(&self, coord: WorldCoord) -> bool {
+        (-HORIZONTAL_LIMIT..=HORIZONTAL_LIMIT).contains(&coord.x)
+            && (-HORIZONTAL_LIMIT..=HORIZONTAL_LIMIT).contains(&coord.y)
+            && (-VERTICAL_LIMIT - 1..VERTICAL_LIMIT).contains(&coord.z)
+    }
+}
+
+impl WorldGenerator for DeterministicMap {
+    fn block_at(&self, coord: WorldCoord) -> BlockId {
+        if !self.within_bounds(coord) {
+            return AIR;
+        }
+        self.block_in_column(coord, self.surface_height(coord.x, coord.y))
+    }
 
-                    let block = self.block_at(world_coord);
+    fn populate_chunk(&self, chunk: &mut Chunk, position: ChunkPosition) {
+        chunk.position = position;
+        let origin = chunk_origin(position);
+
+        // Columns share a surface height, so work out each one once.
+        for y in 0..CHUNK_DEPTH {
+            for x in 0..CHUNK_WIDTH {
+                let column = offset(origin, x, y, 0);
+                let surface = self.surface_height(column.x, column.y);
+
+                for z in 0..CHUNK_HEIGHT {
+                    let world_coord = offset(column, 0, 0, z);
+                    let block = if self.within_bounds(world_coord) {
+                        self.block_in_column(world_coord, surface)
+                    } else {
+                        AIR
+                    };
                     let local = LocalBlockCoord::new(x, y, z);
                     chunk
                         .set_block(local, block)
@@ -98,20 +233,43 @@ impl DeterministicMap {
             }
         }
     }
+}
 
-    fn rng_for_coord(&self, coord: WorldCoord) -> Rng {
-        let mut hasher = DefaultHasher::new();
-        self.seed.hash(&mut hasher);
-        coord.hash(&mut hasher);
-        let seed = hasher.finish();
-        Rng::with_seed(seed)
-    }
+fn chunk_origin(position: ChunkPosition) -> WorldCoord {
+    WorldCoord::new(
+        position.x * CHUNK_WIDTH as i32,
+        position.y * CHUNK_DEPTH as i32,
+        position.z * CHUNK_HEIGHT as i32,
+    )
+}
 
-    fn within_bounds(&self, coord: WorldCoord) -> bool {
-        (-HORIZONTAL_LIMIT..=HORIZONTAL_LIMIT).contains(&coord.x)
-            && (-HORIZONTAL_LIMIT..=HORIZONTAL_LIMIT).contains(&coord.y)
-            && (-VERTICAL_LIMIT..=VERTICAL_LIMIT).contains(&coord.z)
-    }
+fn offset(origin: WorldCoord, x: usize, y: usize, z: usize) -> WorldCoord {
+    WorldCoord::new(
+        origin.x + x as i32,
+        origin.y + y as i32,
+        origin.z + z as i32,
+    )
+}
+
+/// 0.0 on the spawn plateau, rising to 1.0 where the full terrain begins.
+fn spawn_falloff(x: i32, y: i32) -> f32 {
+    let distance = ((x as f32).powi(2) + (y as f32).powi(2)).sqrt();
+    ((distance - SPAWN_RADIUS) / SPAWN_BLEND).clamp(0.0, 1.0)
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// SplitMix64 finaliser.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
 }
 
 #[cfg(test)]
@@ -122,6 +280,24 @@ mod tests {
         DeterministicMap::new(42)
     }
 
+    fn count_blocks(
+        generator: &DeterministicMap,
+        block: BlockId,
+        zs: std::ops::Range<i32>,
+    ) -> usize {
+        let mut count = 0;
+        for x in 100..160 {
+            for y in 100..160 {
+                for z in zs.clone() {
+                    if generator.block_at(WorldCoord::new(x, y, z)) == block {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
     #[test]
     fn enforces_bounds() {
         let generator = map();
@@ -135,73 +311,150 @@ mod tests {
         let generator = map();
         assert_eq!(generator.block_at(WorldCoord::new(0, 0, 64)), AIR);
         assert_eq!(generator.block_at(WorldCoord::new(0, 0, -66)), BEDROCK);
+        assert_eq!(generator.block_at(WorldCoord::new(300, -200, -66)), BEDROCK);
     }
 
     #[test]
-    fn places_left_side_layers() {
+    fn spawn_is_a_flat_solid_plateau() {
         let generator = map();
-        assert_eq!(generator.block_at(WorldCoord::new(-3, 0, -1)), DIRT);
-        assert_eq!(generator.block_at(WorldCoord::new(-3, 0, -6)), STONE);
-        assert_eq!(generator.block_at(WorldCoord::new(-3, 0, -65)), STONE);
+        for x in -10..=10 {
+            for y in -10..=10 {
+                assert_eq!(generator.surface_height(x, y), SURFACE_LEVEL);
+            }
+        }
+        assert_eq!(generator.block_at(WorldCoord::new(0, 0, 1)), AIR);
+        assert_eq!(generator.block_at(WorldCoord::new(3, 0, 0)), STONE);
+        assert_eq!(generator.block_at(WorldCoord::new(-3, 0, -1)), STONE);
+        for z in -20..-3 {
+            assert_ne!(generator.block_at(WorldCoord::new(4, 4, z)), AIR);
+        }
     }
 
     #[test]
-    fn places_right_side_ores_deterministically() {
+    fn hills_vary_away_from_spawn() {
         let generator = map();
-        let mut iron_coord = None;
+        let heights: Vec<i32> = (0..64)
+            .map(|step| generator.surface_height(200 + step * 4, 300))
+            .collect();
+        let lowest = *heights.iter().min().unwrap();
+        let highest = *heights.iter().max().unwrap();
+
+        assert!(highest - lowest >= 4);
+        assert!(highest <= SURFACE_LEVEL + HILL_HEIGHT as i32);
+        // Neighbouring columns differ by at most a step, so hills are climbable.
+        for window in heights.windows(2) {
+            assert!((window[0] - window[1]).abs() <= 3);
+        }
+    }
 
-        for x in 1..100 {
-            let candidate = WorldCoord::new(x, 0, -11);
-            if generator.block_at(candidate) == IRON {
-                iron_coord = Some(candidate);
-                break;
+    #[test]
+    fn carves_caves_below_the_surface() {
+        let generator = map();
+        assert!(count_blocks(&generator, AIR, -40..-20) > 0);
+        for x in 100..160 {
+            let surface = generator.surface_height(x, 120);
+            for z in surface - CAVE_ROOF + 1..=surface {
+                assert_ne!(generator.block_at(WorldCoord::new(x, 120, z)), AIR);
             }
         }
+    }
 
-        let iron_coord = iron_coord.expect("expected at least one iron vein along the right side");
-        assert_eq!(generator.block_at(iron_coord), IRON);
-
-        let different_seed = DeterministicMap::new(7);
-        let diverges = (1..20).any(|x| {
-            let coord = WorldCoord::new(x, 0, -11);
-            generator.block_at(coord) != different_seed.block_at(coord)
-        });
+    #[test]
+    fn iron_clusters_into_veins() {
+        let generator = map();
+        let mut iron = 0;
+        let mut touching = 0;
+        for x in 100..160 {
+            for y in 100..160 {
+                let coord = WorldCoord::new(x, y, -30);
+                if generator.block_at(coord) != IRON {
+                    continue;
+                }
+                iron += 1;
+                let neighbours = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+                if neighbours.iter().any(|(dx, dy)| {
+                    generator.block_at(WorldCoord::new(x + dx, y + dy, -30)) == IRON
+                }) {
+                    touching += 1;
+                }
+            }
+        }
 
-        assert!(diverges);
+        assert!(iron > 0);
+        assert!(touching * 10 >= iron * 9);
     }
 
     #[test]
-    fn stone_above_ground_follows_diagonal_rule() {
+    fn ore_gets_richer_with_depth() {
         let generator = map();
-        // For z > 0 the area where x - z + 1 > 0 should contain stone, otherwise air.
-        assert_eq!(generator.block_at(WorldCoord::new(4, 0, 1)), STONE);
-        assert_eq!(generator.block_at(WorldCoord::new(1, 0, 2)), AIR);
-        assert_eq!(generator.block_at(WorldCoord::new(-1, 0, 2)), AIR);
+        let shallow = count_blocks(&generator, IRON, -12..-4);
+        let deep = count_blocks(&generator, IRON, -64..-56);
+
+        assert!(deep > shallow);
     }
 
     #[test]
-    fn chunk_generation_matches_block_at() {
+    fn is_deterministic_per_seed() {
         let generator = map();
-        let position = ChunkPosition::new(0, 0, 0);
-        let chunk = generator.chunk_for_position(position);
-
-        let local = LocalBlockCoord::new(13, 0, 0);
-        assert_eq!(
-            chunk.get_block(local).unwrap(),
-            generator.block_at(WorldCoord::new(13, 0, 0))
+        let coords: Vec<WorldCoord> = (0..200)
+            .map(|i| WorldCoord::new(60 + i * 3, -40 + i, -(i % 60)))
+            .collect();
+
+        let again = map();
+        assert!(
+            coords
+                .iter()
+                .all(|&coord| generator.block_at(coord) == again.block_at(coord))
         );
 
-        let local_ore = LocalBlockCoord::new(10, 0, 0);
-        assert_eq!(
-            chunk.get_block(local_ore).unwrap(),
-            generator.block_at(WorldCoord::new(10, 0, 0))
+        let different_seed = DeterministicMap::new(7);
+        assert!(
+            coords
+                .iter()
+                .any(|&coord| generator.block_at(coord) != different_seed.block_at(coord))
         );
     }
 
+    #[test]
+    fn chunk_generation_matches_block_at() {
+        let generator = map();
+        for position in [
+            ChunkPosition::new(0, 0, 0),
+            ChunkPosition::new(3, -2, -5),
+            ChunkPosition::new(-4, 6, 1),
+        ] {
+            let chunk = generator.chunk_for_position(position);
+            for z in 0..CHUNK_HEIGHT {
+                for y in 0..CHUNK_DEPTH {
+                    for x in 0..CHUNK_WIDTH {
+                        let world = WorldCoord::new(
+                            position.x * CHUNK_WIDTH as i32 + x as i32,
+                            position.y * CHUNK_DEPTH as i32 + y as i32,
+                            position.z * CHUNK_HEIGHT as i32 + z as i32,
+                        );
+                        assert_eq!(
+                            chunk.get_block(LocalBlockCoord::new(x, y, z)).unwrap(),
+                            generator.block_at(world)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn ensures_specific_coordinates_are_air() {
         let generator = map();
         assert_eq!(generator.block_at(WorldCoord::new(1, 0, 0)), AIR);
         assert_eq!(generator.block_at(WorldCoord::new(2, 0, 0)), AIR);
     }
+
+    #[test]
+    fn dirt_covers_terrain_away_from_spawn() {
+        let generator = map();
+        let surface = generator.surface_height(200, 200);
+        for z in surface - DIRT_DEPTH + 1..=surface {
+            assert_eq!(generator.block_at(WorldCoord::new(200, 200, z)), DIRT);
+        }
+    }
 }
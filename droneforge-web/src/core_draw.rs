@@ -1,4 +1,4 @@
-use macroquad::prelude::{draw_poly, Color, Vec2};
+use macroquad::prelude::{Color, Vec2, draw_poly};
 use std::f32::consts::FRAC_PI_4;
 
 pub const CORE_COLOR: Color = Color::from_rgba(0, 53, 146, 255);
@@ -10,4 +10,3 @@ pub fn draw_core_at_screen(center: Vec2, tile_size: f32) {
     let radius = (half - margin).max(1.0);
     draw_poly(center.x, center.y, 4, radius, FRAC_PI_4, CORE_COLOR);
 }
-
@@ -1,4 +1,4 @@
-use droneforge_core::DronePose;
+use droneforge_core::{DronePose, DroneStatus};
 use macroquad::prelude::*;
 
 #[derive(Debug, Clone)]
@@ -96,3 +96,29 @@ pub fn draw_drone(
         config.stroke_color,
     );
 }
+
+/// Small dot at the drone's upper right showing its scheduler status.
+pub fn draw_status_pip(
+    status: DroneStatus,
+    center_screen: Vec2,
+    effective_block_size: f32,
+    config: &DroneDrawConfig,
+) {
+    let color = match status {
+        DroneStatus::Idle => GRAY,
+        DroneStatus::Thinking => YELLOW,
+        DroneStatus::Working => SKYBLUE,
+        DroneStatus::Finished => GREEN,
+    };
+    let radius_px = config.radius_tiles * effective_block_size;
+    let pip_radius_px = (radius_px * 0.3).max(2.0);
+    let offset = radius_px * 0.9;
+    let pip_center = center_screen + vec2(offset, -offset);
+    draw_circle(
+        pip_center.x,
+        pip_center.y,
+        pip_radius_px + 1.0,
+        config.stroke_color,
+    );
+    draw_circle(pip_center.x, pip_center.y, pip_radius_px, color);
+}
@@ -0,0 +1,19 @@
+use droneforge_core::{Enemy, EnemyKind};
+use macroquad::prelude::{Color, Vec2, draw_poly, vec2};
+
+pub const CRAWLER_COLOR: Color = Color::from_rgba(196, 40, 40, 255);
+pub const BRUTE_COLOR: Color = Color::from_rgba(120, 16, 24, 255);
+
+pub fn enemy_world_center(enemy: &Enemy) -> Vec2 {
+    vec2(enemy.position[0] + 0.5, enemy.position[1] + 0.5)
+}
+
+/// Draw an enemy as a triangle; brutes are larger and darker than crawlers.
+pub fn draw_enemy_at_screen(enemy: &Enemy, center: Vec2, tile_size: f32) {
+    let (scale, color) = match enemy.kind {
+        EnemyKind::Crawler => (0.3, CRAWLER_COLOR),
+        EnemyKind::Brute => (0.45, BRUTE_COLOR),
+    };
+    let radius = (tile_size * scale).max(1.0);
+    draw_poly(center.x, center.y, 3, radius, -90.0, color);
+}
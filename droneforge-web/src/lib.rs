@@ -1,10 +1,17 @@
 use d_gen_tileset::layout::{self, MASK_EAST, MASK_NORTH, MASK_SOUTH, MASK_WEST};
+#[cfg(not(target_arch = "wasm32"))]
+use droneforge_core::ChunkWorkers;
 use droneforge_core::chunk::CHUNK_HEIGHT;
-use droneforge_core::inventory::add_block_to_slots;
-use droneforge_core::worldgen::{DeterministicMap, HORIZONTAL_LIMIT, VERTICAL_LIMIT};
+use droneforge_core::order::validate_dig_target;
+use droneforge_core::worldgen::{
+    DeterministicMap, HORIZONTAL_LIMIT, VERTICAL_LIMIT, WorldGenerator,
+};
 use droneforge_core::{
-    is_placable_block, AIR, BEDROCK, BlockId, ChunkCache, ChunkPosition, DronePose, INVENTORY_SLOTS,
-    IRON, PlacementError, PlacementErrorReason, STONE, ToolController, World, WorldCoord, CORE,
+    AIR, BlockId, BlockRegistry, CORE, ChunkCache, ChunkPosition, DigOrder, DroneId, DronePose,
+    DroneStatus, EnemyEvent, GamePhase, INVENTORY_SLOTS, ITEM_CORE, ITEM_IRON_ORE, ITEM_STONE,
+    ItemId, ItemRegistry, MoveOrder, Order, OrderEvent, OrderOutcome, PathErrorReason, RECIPE_CORE,
+    RecipeId, SECONDS_PER_TICK, StructureEvent, TileBox3, ToolController, WARRIOR_COST_ITEM,
+    WARRIOR_IRON_COST, WARRIOR_SPOT, WaveSchedule, Waves, World, WorldCoord, is_placable_block,
 };
 #[cfg(target_arch = "wasm32")]
 use macroquad::miniquad;
@@ -15,7 +22,13 @@ use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 use std::sync::{Mutex, OnceLock};
 
 use crate::core_draw::draw_core_at_screen;
-use crate::drone::{draw_drone, drone_world_center, is_visible_at_view, DroneDrawConfig};
+use crate::drone::{
+    DroneDrawConfig, draw_drone, draw_status_pip, drone_world_center, is_visible_at_view,
+};
+use crate::enemy_draw::{draw_enemy_at_screen, enemy_world_center};
+use crate::warrior_draw::{
+    draw_drop_at_screen, draw_warrior_at_screen, draw_warrior_spot_at_screen,
+};
 const VIEW_MIN_X: i32 = -100;
 const VIEW_MAX_X: i32 = 100;
 const VIEW_MIN_Y: i32 = -60;
@@ -33,15 +46,22 @@ const BASE_ZOOM_AT_POWER_ZERO: f32 = 1f32;
 const MIN_ZOOM_POWER: i32 = -48;
 const MAX_ZOOM_POWER: i32 = 15;
 const ZOOM_FACTOR: f32 = 1.1;
-const DRONE_MOVE_SPEED: f32 = 4.3;
-const FIXED_STEP_SECONDS: f32 = 1.0 / 60.0;
+const FIXED_STEP_SECONDS: f32 = SECONDS_PER_TICK;
 
 mod core_draw;
 mod drone;
+mod enemy_draw;
+mod warrior_draw;
 
 const RENDER_CHUNK_SIZE: i32 = 32;
 const PRELOAD_Z_RADIUS: i32 = 5;
 const CHUNK_CACHE_CHUNKS_PER_FRAME: usize = 256;
+const CHUNK_CACHE_BUDGET: usize = 4096;
+const WORLD_SEED: u64 = 42;
+/// Enemies enter on the border of this square around the spawn, on the drones' level.
+const WAVE_ARENA_RADIUS: i32 = 24;
+/// Warrior spots marked out around the spawn pocket, on the drones' level.
+const WARRIOR_SPOTS: [(i32, i32); 4] = [(3, 1), (3, -1), (-2, 2), (-2, -2)];
 const LOAD_METRIC_INTERVAL_SECS: f64 = 5.0;
 
 static PENDING_Z_DELTA: AtomicI32 = AtomicI32::new(0);
@@ -52,8 +72,18 @@ static PENDING_USE_TOGGLE: AtomicBool = AtomicBool::new(false);
 static USE_MODE_ACTIVE: AtomicBool = AtomicBool::new(false);
 static PENDING_TOOL_SLOT: AtomicI32 = AtomicI32::new(-1);
 static PENDING_TOOL_REQUEST: AtomicBool = AtomicBool::new(false);
-static PENDING_CORE_SLOT: AtomicI32 = AtomicI32::new(-1);
-static PENDING_CORE_REQUEST: AtomicBool = AtomicBool::new(false);
+static PENDING_CRAFT_RECIPE: AtomicI32 = AtomicI32::new(-1);
+static PENDING_CRAFT_REQUEST: AtomicBool = AtomicBool::new(false);
+static PENDING_WARRIOR_TOGGLE: AtomicBool = AtomicBool::new(false);
+static WARRIOR_MODE_ACTIVE: AtomicBool = AtomicBool::new(false);
+static PENDING_DEPOSIT_REQUEST: AtomicBool = AtomicBool::new(false);
+static PENDING_PICK_UP_REQUEST: AtomicBool = AtomicBool::new(false);
+
+/// HP of the core shown in the HUD, or -1 while no core stands.
+static HUD_CORE_HP: AtomicI32 = AtomicI32::new(-1);
+static HUD_CORE_MAX_HP: AtomicI32 = AtomicI32::new(0);
+static GAME_PHASE: AtomicU32 = AtomicU32::new(0);
+static IRON_AMOUNT: AtomicU32 = AtomicU32::new(0);
 
 static INITIAL_CHUNK_TOTAL: AtomicU32 = AtomicU32::new(0);
 static INITIAL_CHUNK_LOADED: AtomicU32 = AtomicU32::new(0);
@@ -64,10 +94,11 @@ struct SelectedDroneUi {
     name: String,
     health: i32,
     max_health: i32,
+    state: &'static str,
     status: String,
     progress_percent: u32,
     progress_visible: bool,
-    inventory_blocks: [BlockId; INVENTORY_SLOTS],
+    inventory_items: [ItemId; INVENTORY_SLOTS],
     inventory_counts: [u32; INVENTORY_SLOTS],
 }
 
@@ -78,7 +109,7 @@ fn selected_drone_ui() -> &'static Mutex<SelectedDroneUi> {
 
 #[derive(Default)]
 struct SelectedToolUi {
-    block: BlockId,
+    item: ItemId,
     count: u32,
 }
 
@@ -184,6 +215,22 @@ pub extern "C" fn selected_drone_status_len() -> usize {
     if ui.present { ui.status.len() } else { 0 }
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn selected_drone_state_ptr() -> *const u8 {
+    let ui = selected_drone_ui().lock().unwrap();
+    if ui.present {
+        ui.state.as_ptr()
+    } else {
+        ptr::null()
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn selected_drone_state_len() -> usize {
+    let ui = selected_drone_ui().lock().unwrap();
+    if ui.present { ui.state.len() } else { 0 }
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn selected_drone_health() -> i32 {
     let ui = selected_drone_ui().lock().unwrap();
@@ -217,16 +264,16 @@ pub extern "C" fn selected_drone_progress_percent() -> u32 {
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn selected_drone_inventory_slot_block(slot_index: u32) -> BlockId {
+pub extern "C" fn selected_drone_inventory_slot_item(slot_index: u32) -> ItemId {
     let ui = selected_drone_ui().lock().unwrap();
     if !ui.present {
-        return AIR;
+        return 0;
     }
 
-    ui.inventory_blocks
+    ui.inventory_items
         .get(slot_index as usize)
         .copied()
-        .unwrap_or(AIR)
+        .unwrap_or(0)
 }
 
 #[unsafe(no_mangle)]
@@ -243,9 +290,9 @@ pub extern "C" fn selected_drone_inventory_slot_count(slot_index: u32) -> u32 {
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn selected_tool_block() -> BlockId {
+pub extern "C" fn selected_tool_item() -> ItemId {
     let ui = selected_tool_ui().lock().unwrap();
-    ui.block
+    ui.item
 }
 
 #[unsafe(no_mangle)]
@@ -278,14 +325,96 @@ pub extern "C" fn block_tile_size() -> u32 {
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn core_block_id() -> BlockId {
-    CORE
+pub extern "C" fn item_name_ptr(item: ItemId) -> *const u8 {
+    ItemRegistry::global().name(item).as_ptr()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn item_name_len(item: ItemId) -> usize {
+    ItemRegistry::global().name(item).len()
+}
+
+/// The block whose tile stands in for `item` in the UI, or air when it has none.
+#[unsafe(no_mangle)]
+pub extern "C" fn item_icon_block(item: ItemId) -> BlockId {
+    ItemRegistry::global().icon_block(item).unwrap_or(AIR)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn core_item_id() -> ItemId {
+    ITEM_CORE
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn stone_item_id() -> ItemId {
+    ITEM_STONE
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn core_recipe_id() -> RecipeId {
+    RECIPE_CORE
 }
 
+/// Asks the selected drone to craft `recipe_id`.
 #[unsafe(no_mangle)]
-pub extern "C" fn inventory_create_core(slot_index: u32) {
-    log_ui_action("inventory action: create core");
-    queue_core_request(slot_index as i32);
+pub extern "C" fn craft_recipe(recipe_id: u32) {
+    log_ui_action("inventory action: craft recipe");
+    queue_craft_request(recipe_id as i32);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn core_hp() -> i32 {
+    HUD_CORE_HP.load(Ordering::SeqCst)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn core_max_hp() -> i32 {
+    HUD_CORE_MAX_HP.load(Ordering::SeqCst)
+}
+
+/// 0 while running, 1 once defeated, 2 on victory.
+#[unsafe(no_mangle)]
+pub extern "C" fn game_phase() -> u32 {
+    GAME_PHASE.load(Ordering::SeqCst)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn iron_amount() -> u32 {
+    IRON_AMOUNT.load(Ordering::SeqCst)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn warrior_iron_cost() -> u32 {
+    WARRIOR_IRON_COST
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn build_warrior_mode() {
+    log_ui_action("tool action: build warrior");
+    PENDING_WARRIOR_TOGGLE.store(true, Ordering::SeqCst);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn warrior_mode_active() -> i32 {
+    if WARRIOR_MODE_ACTIVE.load(Ordering::SeqCst) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Hands the selected drone's iron ore to a core it stands next to.
+#[unsafe(no_mangle)]
+pub extern "C" fn deposit_selected_iron() {
+    log_ui_action("inventory action: deposit iron");
+    PENDING_DEPOSIT_REQUEST.store(true, Ordering::SeqCst);
+}
+
+/// Has the selected drone collect items lying on or next to its tile.
+#[unsafe(no_mangle)]
+pub extern "C" fn pick_up_drops() {
+    log_ui_action("inventory action: pick up");
+    PENDING_PICK_UP_REQUEST.store(true, Ordering::SeqCst);
 }
 
 #[unsafe(no_mangle)]
@@ -357,6 +486,22 @@ fn set_move_mode_active(active: bool) {
     MOVE_MODE_ACTIVE.store(active, Ordering::SeqCst);
 }
 
+fn take_pending_warrior_toggle() -> bool {
+    PENDING_WARRIOR_TOGGLE.swap(false, Ordering::SeqCst)
+}
+
+fn set_warrior_mode_active(active: bool) {
+    WARRIOR_MODE_ACTIVE.store(active, Ordering::SeqCst);
+}
+
+fn take_pending_deposit_request() -> bool {
+    PENDING_DEPOSIT_REQUEST.swap(false, Ordering::SeqCst)
+}
+
+fn take_pending_pick_up_request() -> bool {
+    PENDING_PICK_UP_REQUEST.swap(false, Ordering::SeqCst)
+}
+
 fn take_pending_use_toggle() -> bool {
     PENDING_USE_TOGGLE.swap(false, Ordering::SeqCst)
 }
@@ -384,22 +529,18 @@ fn take_pending_tool_slot() -> Option<Option<usize>> {
     }
 }
 
-fn queue_core_request(slot_index: i32) {
-    PENDING_CORE_SLOT.store(slot_index, Ordering::SeqCst);
-    PENDING_CORE_REQUEST.store(true, Ordering::SeqCst);
+fn queue_craft_request(recipe_id: i32) {
+    PENDING_CRAFT_RECIPE.store(recipe_id, Ordering::SeqCst);
+    PENDING_CRAFT_REQUEST.store(true, Ordering::SeqCst);
 }
 
-fn take_pending_core_request() -> Option<usize> {
-    if !PENDING_CORE_REQUEST.swap(false, Ordering::SeqCst) {
+fn take_pending_craft_request() -> Option<RecipeId> {
+    if !PENDING_CRAFT_REQUEST.swap(false, Ordering::SeqCst) {
         return None;
     }
 
-    let slot = PENDING_CORE_SLOT.load(Ordering::SeqCst);
-    if slot < 0 {
-        None
-    } else {
-        Some(slot as usize)
-    }
+    let recipe = PENDING_CRAFT_RECIPE.load(Ordering::SeqCst);
+    RecipeId::try_from(recipe).ok()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -437,15 +578,8 @@ struct BlockPalette;
 
 impl BlockPalette {
     fn color_for(&self, block: BlockId) -> Color {
-        match block {
-            AIR => Color::from_rgba(0, 0, 0, 0),
-            droneforge_core::DIRT => Color::from_rgba(143, 99, 63, 255),
-            droneforge_core::STONE => Color::from_rgba(120, 120, 120, 255),
-            droneforge_core::IRON => Color::from_rgba(194, 133, 74, 255),
-            BEDROCK => Color::from_rgba(45, 45, 45, 255),
-            CORE => Color::from_rgba(0, 53, 146, 255),
-            _ => MAGENTA,
-        }
+        let [r, g, b, a] = BlockRegistry::global().color(block);
+        Color::from_rgba(r, g, b, a)
     }
 }
 
@@ -461,132 +595,13 @@ struct TileSet {
     wall_tiles: HashMap<WallTileKey, TileRegion>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum SelectionMode {
+    #[default]
     Inspect,
     MoveTarget,
     UseTarget,
-}
-
-impl Default for SelectionMode {
-    fn default() -> Self {
-        SelectionMode::Inspect
-    }
-}
-
-#[derive(Debug, Clone)]
-struct MoveOrder {
-    target_tile: (i32, i32, i32),
-    target_position: Vec3,
-    direction: Vec3,
-    delta_per_sec: Vec3,
-}
-
-impl MoveOrder {
-    fn for_target(current: Vec3, target_tile: (i32, i32, i32)) -> Option<Self> {
-        let target_position = vec3(
-            target_tile.0 as f32,
-            target_tile.1 as f32,
-            target_tile.2 as f32,
-        );
-        let offset = target_position - current;
-        let distance = offset.length();
-        if distance <= f32::EPSILON {
-            return None;
-        }
-
-        let direction = offset / distance;
-        let delta_per_sec = direction * DRONE_MOVE_SPEED;
-
-        Some(Self {
-            target_tile,
-            target_position,
-            direction,
-            delta_per_sec,
-        })
-    }
-
-    fn status_text(&self) -> String {
-        format!(
-            "moving to tile {}, {}, {} (d/s {:.2}, {:.2}, {:.2})",
-            self.target_tile.0,
-            self.target_tile.1,
-            self.target_tile.2,
-            self.delta_per_sec.x,
-            self.delta_per_sec.y,
-            self.delta_per_sec.z
-        )
-    }
-}
-
-#[derive(Debug, Clone)]
-enum Order {
-    Move(MoveOrder),
-    Dig(DigOrder),
-}
-
-impl Order {
-    fn status_text(&self) -> String {
-        match self {
-            Order::Move(order) => order.status_text(),
-            Order::Dig(order) => order.status_text(),
-        }
-    }
-
-    fn progress_percent(&self) -> Option<u32> {
-        match self {
-            Order::Move(_) => None,
-            Order::Dig(order) => Some(order.progress_percent()),
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-struct DigOrder {
-    target_tile: (i32, i32, i32),
-    elapsed: f32,
-    duration: f32,
-}
-
-impl DigOrder {
-    fn new(target_tile: (i32, i32, i32)) -> Self {
-        Self {
-            target_tile,
-            elapsed: 0.0,
-            duration: 5.0,
-        }
-    }
-
-    fn progress_fraction(&self) -> f32 {
-        (self.elapsed / self.duration).clamp(0.0, 1.0)
-    }
-
-    fn progress_percent(&self) -> u32 {
-        if self.elapsed >= self.duration {
-            return 100;
-        }
-        let stepped = ((self.progress_fraction() * 100.0) / 5.0).floor() as u32 * 5;
-        stepped.min(100)
-    }
-
-    fn status_text(&self) -> String {
-        format!(
-            "digging wall at {}, {}, {} ({}%)",
-            self.target_tile.0,
-            self.target_tile.1,
-            self.target_tile.2,
-            self.progress_percent()
-        )
-    }
-
-    fn advance(&mut self, delta_seconds: f32) -> (bool, bool) {
-        let before = self.progress_percent();
-        self.elapsed = (self.elapsed + delta_seconds).min(self.duration);
-        let after = self.progress_percent();
-        let progressed = after != before;
-        let completed = self.elapsed >= self.duration - f32::EPSILON;
-        (completed, progressed)
-    }
+    BuildWarrior,
 }
 
 impl TileSet {
@@ -601,7 +616,7 @@ impl TileSet {
         let mut floor_tiles = HashMap::new();
         let mut wall_tiles = HashMap::new();
 
-        for &block in layout::SOLID_BLOCKS.iter() {
+        for &block in layout::TILED_BLOCKS.iter() {
             if let Some(position) = layout::floor_tile_position(block) {
                 floor_tiles.insert(block, TileRegion::from_tile_position(position));
             }
@@ -721,8 +736,6 @@ impl DroneSpriteAtlas {
 
 pub struct GameState {
     world: World,
-    chunk_cache: ChunkCache,
-    generator: DeterministicMap,
     tiles: TileSet,
     drone_sprites: DroneSpriteAtlas,
     drone_draw: DroneDrawConfig,
@@ -740,18 +753,20 @@ pub struct GameState {
     last_two_finger_center: Option<Vec2>,
     last_right_drag_pos: Option<Vec2>,
     selection_mode: SelectionMode,
-    selected_drone: Option<usize>,
+    selected_drone: Option<DroneId>,
     selected_order: Option<String>,
+    selected_status: Option<DroneStatus>,
+    /// Outcome of the last warrior placement, shown in the overlay.
+    build_status: Option<String>,
     tool_controller: ToolController,
-    active_orders: Vec<Option<Order>>,
     render_chunk_xs: Vec<i32>,
     render_chunk_ys: Vec<i32>,
-    world_chunk_xs: Vec<i32>,
-    world_chunk_ys: Vec<i32>,
-    world_chunk_zs: Vec<i32>,
     world_chunk_z_set: HashSet<i32>,
     chunk_cache_queue: VecDeque<ChunkPosition>,
+    /// Positions waiting in the queue or, on native builds, being generated by the workers.
     chunk_cache_queued: HashSet<ChunkPosition>,
+    #[cfg(not(target_arch = "wasm32"))]
+    chunk_workers: Option<ChunkWorkers>,
     chunk_cache_frame_time_total_ms: f64,
     chunk_cache_frame_time_count: u64,
     chunk_cache_last_avg_update_time: f64,
@@ -764,50 +779,58 @@ pub struct GameState {
 
 impl GameState {
     pub async fn new() -> Self {
-        let tiles = TileSet::load_from_assets().await;
-        let drone_sprites = DroneSpriteAtlas::load_from_assets().await;
-        let generator = DeterministicMap::new(42);
-        let cache_capacity =
-            ChunkCache::chunk_count_for_limits(HORIZONTAL_LIMIT, VERTICAL_LIMIT).max(1);
-        let chunk_cache = ChunkCache::with_capacity(cache_capacity);
-        Self::build(generator, chunk_cache, tiles, drone_sprites)
+        Self::with_generator(DeterministicMap::new(WORLD_SEED)).await
     }
 
-    pub async fn new_with_cache(generator: DeterministicMap, chunk_cache: ChunkCache) -> Self {
+    pub async fn with_generator(generator: impl WorldGenerator + 'static) -> Self {
+        Self::new_with_cache(ChunkCache::streaming(generator, CHUNK_CACHE_BUDGET)).await
+    }
+
+    /// Starts a game on `chunk_cache`, which should carry the generator for unloaded terrain.
+    pub async fn new_with_cache(chunk_cache: ChunkCache) -> Self {
         let tiles = TileSet::load_from_assets().await;
         let drone_sprites = DroneSpriteAtlas::load_from_assets().await;
-        Self::build(generator, chunk_cache, tiles, drone_sprites)
+        Self::build(chunk_cache, tiles, drone_sprites)
     }
 
-    fn build(
-        generator: DeterministicMap,
-        chunk_cache: ChunkCache,
-        tiles: TileSet,
-        drone_sprites: DroneSpriteAtlas,
-    ) -> Self {
+    fn build(chunk_cache: ChunkCache, tiles: TileSet, drone_sprites: DroneSpriteAtlas) -> Self {
         let initial_zoom_power = 0;
         let (render_chunk_xs, render_chunk_ys) =
             render_chunk_ranges(VIEW_MIN_X, VIEW_MAX_X, VIEW_MIN_Y, VIEW_MAX_Y);
-        let (world_chunk_xs, world_chunk_ys, world_chunk_zs) =
+        let (_, _, world_chunk_zs) =
             ChunkCache::chunk_ranges_for_limits(HORIZONTAL_LIMIT, VERTICAL_LIMIT);
         let world_chunk_z_set: HashSet<i32> = world_chunk_zs.iter().copied().collect();
         let (chunk_width_px, chunk_depth_px) = render_chunk_pixel_dimensions();
         let scratch_image =
             Image::gen_image_color(chunk_width_px, chunk_depth_px, Color::from_rgba(0, 0, 0, 0));
         sync_tile_atlas_ui(&tiles);
-        let mut world = World::new();
-        world.set_drones(vec![DronePose::new(
-            [0.0, 0.0, 0.0],
-            [1.0, 0.0],
-            "d1",
-            10,
-            10,
-        )]);
-        let drone_count = world.drones().len();
+        #[cfg(not(target_arch = "wasm32"))]
+        let chunk_workers = chunk_cache.generator().cloned().map(ChunkWorkers::new);
+        let mut world = World::with_chunk_cache(chunk_cache);
+        world.chunk_cache_mut().set_clock(get_time);
+        world.set_drones(vec![
+            DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([-1.0, 1.0, 0.0], [1.0, 0.0], "d2", 10, 10),
+            DronePose::new([-1.0, -1.0, 0.0], [1.0, 0.0], "d3", 10, 10),
+        ]);
+        let arena = TileBox3::new(
+            WorldCoord::new(-WAVE_ARENA_RADIUS, -WAVE_ARENA_RADIUS, DEFAULT_VIEW_Z),
+            WorldCoord::new(WAVE_ARENA_RADIUS, WAVE_ARENA_RADIUS, DEFAULT_VIEW_Z),
+        )
+        .expect("wave arena corners are ordered");
+        world.set_waves(Waves::new(
+            &WaveSchedule::builtin(),
+            WORLD_SEED,
+            arena,
+            world.tick,
+        ));
+        for (x, y) in WARRIOR_SPOTS {
+            world
+                .set_block(WorldCoord::new(x, y, DEFAULT_VIEW_Z), WARRIOR_SPOT)
+                .expect("warrior spots lie inside the world");
+        }
         let mut game = Self {
             world,
-            chunk_cache,
-            generator,
             tiles,
             drone_sprites,
             drone_draw: DroneDrawConfig::default(),
@@ -827,16 +850,16 @@ impl GameState {
             selection_mode: SelectionMode::Inspect,
             selected_drone: None,
             selected_order: None,
+            selected_status: None,
+            build_status: None,
             tool_controller: ToolController::new(),
-            active_orders: vec![None; drone_count],
             render_chunk_xs,
             render_chunk_ys,
-            world_chunk_xs,
-            world_chunk_ys,
-            world_chunk_zs,
             world_chunk_z_set,
             chunk_cache_queue: VecDeque::new(),
             chunk_cache_queued: HashSet::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            chunk_workers,
             chunk_cache_frame_time_total_ms: 0.0,
             chunk_cache_frame_time_count: 0,
             chunk_cache_last_avg_update_time: 0.0,
@@ -911,7 +934,7 @@ impl GameState {
         let base = ChunkPosition::new(key.chunk_x, key.chunk_y, floor_chunk_z);
         let wall = ChunkPosition::new(key.chunk_x, key.chunk_y, wall_chunk_z);
 
-        self.chunk_cache.has_chunk(&base) && self.chunk_cache.has_chunk(&wall)
+        self.world.chunk_cache().has_chunk(&base) && self.world.chunk_cache().has_chunk(&wall)
     }
 
     fn prime_chunk_cache_queue(&mut self) {
@@ -957,21 +980,15 @@ impl GameState {
             }
         }
 
-        for &chunk_z in &self.world_chunk_zs {
-            if seen_chunk_zs.contains(&chunk_z) {
-                continue;
-            }
-            ordered_chunk_zs.push(chunk_z);
-        }
-
         for chunk_z in ordered_chunk_zs {
-            self.queue_full_chunk_plane(chunk_z);
+            self.queue_view_chunk_plane(chunk_z);
         }
     }
 
-    fn queue_full_chunk_plane(&mut self, chunk_z: i32) {
-        let chunk_ys = self.world_chunk_ys.clone();
-        let chunk_xs = self.world_chunk_xs.clone();
+    /// Queues the chunks under the rendered view; everything else is generated when touched.
+    fn queue_view_chunk_plane(&mut self, chunk_z: i32) {
+        let chunk_ys = self.render_chunk_ys.clone();
+        let chunk_xs = self.render_chunk_xs.clone();
 
         for chunk_y in chunk_ys.into_iter() {
             for chunk_x in chunk_xs.iter().copied() {
@@ -982,7 +999,9 @@ impl GameState {
     }
 
     fn queue_chunk_cache_position(&mut self, position: ChunkPosition) {
-        if self.chunk_cache.has_chunk(&position) || self.chunk_cache_queued.contains(&position) {
+        if self.world.chunk_cache().has_chunk(&position)
+            || self.chunk_cache_queued.contains(&position)
+        {
             return;
         }
 
@@ -997,26 +1016,56 @@ impl GameState {
         }
 
         let frame_start = get_time();
+        let processed = self.generate_queued_chunks();
+
+        if processed > 0 {
+            let duration_ms = (get_time() - frame_start) * 1000.0;
+            self.record_chunk_cache_frame_time(duration_ms);
+        }
+    }
+
+    /// Native builds keep a frame's budget of queued chunks with the worker pool and insert
+    /// whatever has finished, up to the same budget.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn generate_queued_chunks(&mut self) -> usize {
+        let Some(workers) = self.chunk_workers.as_mut() else {
+            return 0;
+        };
+        while workers.in_flight() < CHUNK_CACHE_CHUNKS_PER_FRAME
+            && let Some(position) = self.chunk_cache_queue.pop_front()
+        {
+            if self.world.chunk_cache().has_chunk(&position) {
+                self.chunk_cache_queued.remove(&position);
+            } else {
+                workers.request(position);
+            }
+        }
+
+        let inserted =
+            workers.insert_finished(self.world.chunk_cache_mut(), CHUNK_CACHE_CHUNKS_PER_FRAME);
+        for position in &inserted {
+            self.chunk_cache_queued.remove(position);
+        }
+        inserted.len()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn generate_queued_chunks(&mut self) -> usize {
         let mut processed = 0usize;
         while processed < CHUNK_CACHE_CHUNKS_PER_FRAME {
             if let Some(position) = self.chunk_cache_queue.pop_front() {
                 self.chunk_cache_queued.remove(&position);
-                if self.chunk_cache.has_chunk(&position) {
+                if self.world.chunk_cache().has_chunk(&position) {
                     continue;
                 }
 
-                self.chunk_cache
-                    .populate_chunk_at(&self.generator, position);
+                self.world.chunk_cache_mut().touch_chunk(position);
                 processed += 1;
             } else {
                 break;
             }
         }
-
-        if processed > 0 {
-            let duration_ms = (get_time() - frame_start) * 1000.0;
-            self.record_chunk_cache_frame_time(duration_ms);
-        }
+        processed
     }
 
     fn record_chunk_cache_frame_time(&mut self, duration_ms: f64) {
@@ -1044,18 +1093,18 @@ impl GameState {
         let wall_chunk_z = div_floor(self.view_z, CHUNK_HEIGHT as i32);
 
         let plane_chunks = self
-            .world_chunk_xs
+            .render_chunk_xs
             .len()
-            .saturating_mul(self.world_chunk_ys.len());
+            .saturating_mul(self.render_chunk_ys.len());
         let planes = if chunk_z == wall_chunk_z { 1 } else { 2 };
         let theoretical = plane_chunks.saturating_mul(planes);
 
         let mut pending = 0usize;
-        for position in &self.chunk_cache_queue {
-            if position.z == chunk_z || position.z == wall_chunk_z {
-                if !self.chunk_cache.has_chunk(position) {
-                    pending = pending.saturating_add(1);
-                }
+        for position in &self.chunk_cache_queued {
+            if (position.z == chunk_z || position.z == wall_chunk_z)
+                && !self.world.chunk_cache().has_chunk(position)
+            {
+                pending = pending.saturating_add(1);
             }
         }
 
@@ -1105,8 +1154,9 @@ impl GameState {
                     for x in 0..RENDER_CHUNK_SIZE as usize {
                         let world_x = base_x + x as i32;
                         let world_y = base_y + y as i32;
-                        let block = block_at(&self.chunk_cache, world_x, world_y, floor_z);
-                        let wall_block = block_at(&self.chunk_cache, world_x, world_y, wall_z);
+                        let block = block_at(self.world.chunk_cache(), world_x, world_y, floor_z);
+                        let wall_block =
+                            block_at(self.world.chunk_cache(), world_x, world_y, wall_z);
 
                         let Some(block) = block else {
                             continue;
@@ -1117,8 +1167,12 @@ impl GameState {
 
                         if let Some(wall_block) = wall_block {
                             if is_wall_block(wall_block) {
-                                let mask =
-                                    wall_edge_mask(&self.chunk_cache, world_x, world_y, wall_z);
+                                let mask = wall_edge_mask(
+                                    self.world.chunk_cache(),
+                                    world_x,
+                                    world_y,
+                                    wall_z,
+                                );
 
                                 if let Some(tile) = self.tiles.wall_region(wall_block, mask) {
                                     blit_tile_region(
@@ -1217,150 +1271,119 @@ impl GameState {
 
     fn fixed_update(&mut self) {
         self.world.step();
-        self.advance_drone_orders(FIXED_STEP_SECONDS);
-    }
+        let events = self.world.drain_order_events();
+        self.apply_order_events(&events);
 
-    fn advance_drone_orders(&mut self, delta_seconds: f32) {
-        self.ensure_order_capacity();
-        let drone_count = self.world.drones().len();
-        let mut ui_dirty = false;
-
-        for index in 0..drone_count {
-            let Some(order) = self
-                .active_orders
-                .get_mut(index)
-                .and_then(|slot| slot.take())
-            else {
-                continue;
-            };
-
-            let mut keep_order: Option<Order> = None;
-
-            match order {
-                Order::Move(move_order) => {
-                    let arrived = if let Some(drone) = self.world.drones_mut().get_mut(index) {
-                        Self::advance_single_drone(drone, &move_order, delta_seconds)
-                    } else {
-                        false
-                    };
-
-                    if arrived {
-                        if self.selected_drone == Some(index) {
-                            self.selected_order = Some(format!(
-                                "arrived at {}, {}, {}",
-                                move_order.target_tile.0,
-                                move_order.target_tile.1,
-                                move_order.target_tile.2
-                            ));
-                            ui_dirty = true;
-                        }
-                    } else {
-                        keep_order = Some(Order::Move(move_order));
-                    }
-                }
-                Order::Dig(mut dig_order) => {
-                    let (completed, progressed) = dig_order.advance(delta_seconds);
-                    if progressed && self.selected_drone == Some(index) {
-                        self.selected_order = Some(dig_order.status_text());
-                        ui_dirty = true;
-                    }
-
-                    if completed {
-                        let target = dig_order.target_tile;
-                        let inventory_changed = self.finish_dig(index, target);
-                        if self.selected_drone == Some(index) {
-                            self.selected_order = Some(format!(
-                                "finished digging at {}, {}, {}",
-                                target.0, target.1, target.2
-                            ));
-                            ui_dirty = true;
-                            if inventory_changed {
-                                ui_dirty = true;
-                            }
-                        }
-                    } else {
-                        keep_order = Some(Order::Dig(dig_order));
-                    }
-                }
-            }
-
-            if let Some(order) = keep_order {
-                if let Some(slot) = self.active_orders.get_mut(index) {
-                    *slot = Some(order);
-                }
-            }
+        // The overlay reads wave progress straight from the world.
+        self.world.drain_wave_events();
+        let enemy_events = self.world.drain_enemy_events();
+        if enemy_events
+            .iter()
+            .any(|event| matches!(event, EnemyEvent::BrokeBlock { .. }))
+        {
+            self.rendered_level_dirty = true;
         }
-
-        if ui_dirty {
-            self.sync_selected_ui();
+        if self
+            .world
+            .drain_structure_events()
+            .iter()
+            .any(|event| matches!(event, StructureEvent::Destroyed { .. }))
+        {
+            self.rendered_level_dirty = true;
         }
+        // Warriors are drawn from world state each frame.
+        self.world.drain_warrior_events();
+        // Fights show through world state; the log is only kept for tests and tools.
+        self.world.drain_combat_events();
+        self.forget_dead_drone();
+        self.sync_hud_ui();
     }
 
-    fn advance_single_drone(drone: &mut DronePose, order: &MoveOrder, delta_seconds: f32) -> bool {
-        let current = Vec3::new(drone.position[0], drone.position[1], drone.position[2]);
-        let remaining_vec = order.target_position - current;
-        let remaining_distance = remaining_vec.length();
+    /// Where the selected drone sits in the world's drone list right now.
+    fn selected_index(&self) -> Option<usize> {
+        self.selected_drone
+            .and_then(|id| self.world.drone_index(id))
+    }
 
-        if remaining_distance <= f32::EPSILON {
-            drone.position = [
-                order.target_position.x,
-                order.target_position.y,
-                order.target_position.z,
-            ];
-            Self::apply_heading_from_direction(drone, order.direction);
-            return true;
+    /// Drops the selection once the selected drone has left the world.
+    fn forget_dead_drone(&mut self) {
+        if forget_dead_selection(&self.world, &mut self.selected_drone) {
+            self.selected_order = None;
+            self.tool_controller.clear_selection();
+            self.sync_selected_ui();
         }
+    }
 
-        let step = order.delta_per_sec * delta_seconds;
-        let step_distance = step.length();
-
-        if step_distance >= remaining_distance {
-            drone.position = [
-                order.target_position.x,
-                order.target_position.y,
-                order.target_position.z,
-            ];
-            Self::apply_heading_from_direction(drone, order.direction);
-            true
-        } else {
-            let next = current + step;
-            drone.position = [next.x, next.y, next.z];
-            Self::apply_heading_from_direction(drone, order.direction);
-            false
-        }
+    fn sync_hud_ui(&self) {
+        let (hp, max_hp) = self
+            .world
+            .primary_core()
+            .map_or((-1, 0), |core| (core.hp, core.max_hp));
+        HUD_CORE_HP.store(hp, Ordering::SeqCst);
+        HUD_CORE_MAX_HP.store(max_hp, Ordering::SeqCst);
+        let phase = match self.world.phase() {
+            GamePhase::Running => 0,
+            GamePhase::Defeated => 1,
+            GamePhase::Victory => 2,
+        };
+        GAME_PHASE.store(phase, Ordering::SeqCst);
+        IRON_AMOUNT.store(
+            self.world.resources().amount(WARRIOR_COST_ITEM),
+            Ordering::SeqCst,
+        );
     }
 
-    fn finish_dig(&mut self, drone_index: usize, target: (i32, i32, i32)) -> bool {
-        let mined_block = block_at(&self.chunk_cache, target.0, target.1, target.2);
-        let start = get_time();
-        let coord = WorldCoord::new(target.0, target.1, target.2);
-        if let Err(err) = self.chunk_cache.set_block(coord, AIR) {
-            #[cfg(not(target_arch = "wasm32"))]
-            eprintln!("failed to save dug block: {:?}", err);
-            self.selected_order = Some("failed to save dug block".to_string());
-            return false;
-        }
-        let elapsed_ms = (get_time() - start) * 1000.0;
-        self.chunk_cache.record_save_time_ms(elapsed_ms);
-        self.rendered_level_dirty = true;
+    fn apply_order_events(&mut self, events: &[OrderEvent]) {
+        let mut ui_dirty = false;
 
-        let mut inventory_changed = false;
-        if let Some(block) = mined_block {
-            if block == STONE || block == IRON || block == CORE {
-                inventory_changed = self.world.add_block_to_inventory(drone_index, block);
+        for event in events {
+            if let OrderEvent::Completed {
+                outcome: OrderOutcome::Dug { collected, .. },
+                ..
+            } = event
+            {
+                self.rendered_level_dirty = true;
+                if *collected && self.selected_drone == Some(event.drone()) {
+                    self.refresh_tool_selection();
+                    self.sync_tool_ui();
+                }
             }
-        }
 
-        if inventory_changed && self.selected_drone == Some(drone_index) {
-            if let Some(slots) = self.world.inventory(drone_index) {
-                self.tool_controller.refresh_from_inventory(slots);
-            } else {
-                self.tool_controller.clear_selection();
+            if let OrderEvent::Completed {
+                outcome: OrderOutcome::Crafted { .. },
+                ..
+            } = event
+                && self.selected_drone == Some(event.drone())
+            {
+                self.refresh_tool_selection();
+                self.sync_tool_ui();
             }
-            self.sync_tool_ui();
+
+            if self.selected_drone != Some(event.drone()) {
+                continue;
+            }
+            if let Some(status) = event.status_text() {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let OrderEvent::Failed { error, .. } = event {
+                    eprintln!("drone order failed: {}", error);
+                }
+                self.selected_order = Some(status);
+            }
+            ui_dirty = true;
         }
 
-        inventory_changed
+        // Scheduler hand-offs change the status without emitting an order event.
+        let status = self
+            .selected_index()
+            .map(|index| self.world.drone_status(index));
+        if status != self.selected_status {
+            self.selected_status = status;
+            ui_dirty = true;
+        }
+
+        if ui_dirty {
+            self.sync_selected_ui();
+        }
     }
 
     fn render(&mut self) {
@@ -1377,35 +1400,36 @@ impl GameState {
             self.rendered_level_dirty = false;
         }
 
-        if let Some(rendered_level) = &self.rendered_level {
-            if rendered_level.z == self.view_z {
-                let world_origin_x = rendered_level.origin_chunk_x * RENDER_CHUNK_SIZE;
-                let world_origin_y = rendered_level.origin_chunk_y * RENDER_CHUNK_SIZE;
-
-                let origin_screen =
-                    self.world_to_screen(world_origin_x, world_origin_y, effective_block_size);
-
-                let blocks_w = rendered_level.chunks_x as f32
-                    * RENDER_CHUNK_SIZE as f32
-                    * effective_block_size;
-                let blocks_h = rendered_level.chunks_y as f32
-                    * RENDER_CHUNK_SIZE as f32
-                    * effective_block_size;
-
-                draw_texture_ex(
-                    &rendered_level.texture,
-                    origin_screen.x,
-                    origin_screen.y,
-                    WHITE,
-                    DrawTextureParams {
-                        dest_size: Some(vec2(blocks_w, blocks_h)),
-                        ..Default::default()
-                    },
-                );
-            }
+        if let Some(rendered_level) = &self.rendered_level
+            && rendered_level.z == self.view_z
+        {
+            let world_origin_x = rendered_level.origin_chunk_x * RENDER_CHUNK_SIZE;
+            let world_origin_y = rendered_level.origin_chunk_y * RENDER_CHUNK_SIZE;
+
+            let origin_screen =
+                self.world_to_screen(world_origin_x, world_origin_y, effective_block_size);
+
+            let blocks_w =
+                rendered_level.chunks_x as f32 * RENDER_CHUNK_SIZE as f32 * effective_block_size;
+            let blocks_h =
+                rendered_level.chunks_y as f32 * RENDER_CHUNK_SIZE as f32 * effective_block_size;
+
+            draw_texture_ex(
+                &rendered_level.texture,
+                origin_screen.x,
+                origin_screen.y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(blocks_w, blocks_h)),
+                    ..Default::default()
+                },
+            );
         }
 
-        self.render_cores(effective_block_size);
+        self.render_structures(effective_block_size);
+        self.render_warriors(effective_block_size);
+        self.render_drops(effective_block_size);
+        self.render_enemies(effective_block_size);
         self.render_drones(effective_block_size);
 
         draw_text(
@@ -1445,7 +1469,7 @@ impl GameState {
         };
         draw_text(&cache_avg_text, 20.0, 112.0, 24.0, WHITE);
 
-        let override_text = if let Some(ms) = self.chunk_cache.last_save_ms() {
+        let override_text = if let Some(ms) = self.world.chunk_cache().last_save_ms() {
             format!("chunk save: {:.3} ms", ms)
         } else {
             "chunk save: -- ms".to_string()
@@ -1505,10 +1529,29 @@ impl GameState {
         );
 
         draw_text(&format!("fps: {:.1}", self.fps), 20.0, 280.0, 24.0, WHITE);
+
+        if let Some(wave_status) = self.world.wave_status_text() {
+            draw_text(&wave_status, 20.0, 304.0, 24.0, WHITE);
+        }
+
+        draw_text(
+            &format!(
+                "iron: {} (warrior: {})",
+                self.world.resources().amount(WARRIOR_COST_ITEM),
+                WARRIOR_IRON_COST
+            ),
+            20.0,
+            328.0,
+            24.0,
+            WHITE,
+        );
+        if let Some(build_status) = &self.build_status {
+            draw_text(build_status, 20.0, 352.0, 24.0, WHITE);
+        }
     }
 
     fn render_drones(&self, effective_block_size: f32) {
-        for drone in self.world.drones() {
+        for (index, drone) in self.world.drones().iter().enumerate() {
             if !is_visible_at_view(drone, self.view_z) {
                 continue;
             }
@@ -1516,6 +1559,23 @@ impl GameState {
             let center_world = drone_world_center(drone);
             let center_screen = self.world_to_screen_f(center_world, effective_block_size);
             draw_drone(drone, center_screen, effective_block_size, &self.drone_draw);
+            draw_status_pip(
+                self.world.drone_status(index),
+                center_screen,
+                effective_block_size,
+                &self.drone_draw,
+            );
+        }
+    }
+
+    fn render_enemies(&self, effective_block_size: f32) {
+        for enemy in self.world.enemies() {
+            if !enemy.is_alive() || enemy.tile().z != self.view_z {
+                continue;
+            }
+            let center_screen =
+                self.world_to_screen_f(enemy_world_center(enemy), effective_block_size);
+            draw_enemy_at_screen(enemy, center_screen, effective_block_size);
         }
     }
 
@@ -1540,19 +1600,48 @@ impl GameState {
         draw_core_at_screen(center_screen, effective_block_size);
     }
 
-    fn render_cores(&self, effective_block_size: f32) {
+    fn render_structures(&self, effective_block_size: f32) {
         let (min_x, max_x, min_y, max_y) = self.visible_world_bounds(effective_block_size);
         let z = self.view_z;
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
-                if block_at(&self.chunk_cache, x, y, z) == Some(CORE) {
-                    self.render_core_at((x, y), effective_block_size);
+                match block_at(self.world.chunk_cache(), x, y, z) {
+                    Some(CORE) => self.render_core_at((x, y), effective_block_size),
+                    Some(WARRIOR_SPOT) => {
+                        let center_world = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                        let center_screen =
+                            self.world_to_screen_f(center_world, effective_block_size);
+                        draw_warrior_spot_at_screen(center_screen, effective_block_size);
+                    }
+                    _ => {}
                 }
             }
         }
     }
 
+    fn render_drops(&self, effective_block_size: f32) {
+        for drop in self.world.drops() {
+            if drop.tile.z != self.view_z {
+                continue;
+            }
+            let center_world = vec2(drop.tile.x as f32 + 0.5, drop.tile.y as f32 + 0.5);
+            let center_screen = self.world_to_screen_f(center_world, effective_block_size);
+            draw_drop_at_screen(center_screen, effective_block_size);
+        }
+    }
+
+    fn render_warriors(&self, effective_block_size: f32) {
+        for warrior in self.world.warriors() {
+            if warrior.tile.z != self.view_z {
+                continue;
+            }
+            let center_world = vec2(warrior.tile.x as f32 + 0.5, warrior.tile.y as f32 + 0.5);
+            let center_screen = self.world_to_screen_f(center_world, effective_block_size);
+            draw_warrior_at_screen(center_screen, effective_block_size);
+        }
+    }
+
     fn apply_zoom_power_at_screen_pos(&mut self, next_zoom_power: i32, focus_screen_pos: Vec2) {
         let clamped_power = clamp_zoom_power(next_zoom_power);
         if clamped_power == self.zoom_power {
@@ -1622,31 +1711,31 @@ impl GameState {
         }
         self.last_two_finger_center = Some(focus);
 
-        if let Some(previous_distance) = self.last_pinch_distance {
-            if previous_distance > 0.0 {
-                let ratio = current_distance / previous_distance;
-                if ratio > 0.0 {
-                    let delta_power = ratio.ln() / ZOOM_FACTOR.ln();
-                    self.pinch_zoom_accumulator += delta_power;
-
-                    let mut applied_steps = 0;
-                    while self.pinch_zoom_accumulator >= 1.0 {
-                        applied_steps += 1;
-                        self.pinch_zoom_accumulator -= 1.0;
-                    }
-                    while self.pinch_zoom_accumulator <= -1.0 {
-                        applied_steps -= 1;
-                        self.pinch_zoom_accumulator += 1.0;
-                    }
+        if let Some(previous_distance) = self.last_pinch_distance
+            && previous_distance > 0.0
+        {
+            let ratio = current_distance / previous_distance;
+            if ratio > 0.0 {
+                let delta_power = ratio.ln() / ZOOM_FACTOR.ln();
+                self.pinch_zoom_accumulator += delta_power;
+
+                let mut applied_steps = 0;
+                while self.pinch_zoom_accumulator >= 1.0 {
+                    applied_steps += 1;
+                    self.pinch_zoom_accumulator -= 1.0;
+                }
+                while self.pinch_zoom_accumulator <= -1.0 {
+                    applied_steps -= 1;
+                    self.pinch_zoom_accumulator += 1.0;
+                }
 
-                    if applied_steps != 0 {
-                        let target_power = self.zoom_power + applied_steps;
-                        self.apply_zoom_power_at_screen_pos(target_power, focus);
+                if applied_steps != 0 {
+                    let target_power = self.zoom_power + applied_steps;
+                    self.apply_zoom_power_at_screen_pos(target_power, focus);
 
-                        // If we hit a limit, zero out the accumulator so we do not keep pushing.
-                        if self.zoom_power == MIN_ZOOM_POWER || self.zoom_power == MAX_ZOOM_POWER {
-                            self.pinch_zoom_accumulator = 0.0;
-                        }
+                    // If we hit a limit, zero out the accumulator so we do not keep pushing.
+                    if self.zoom_power == MIN_ZOOM_POWER || self.zoom_power == MAX_ZOOM_POWER {
+                        self.pinch_zoom_accumulator = 0.0;
                     }
                 }
             }
@@ -1659,8 +1748,9 @@ impl GameState {
         if take_pending_move_toggle() {
             if self.selection_mode == SelectionMode::MoveTarget {
                 self.exit_move_mode();
-            } else if self.selected_drone.is_some() {
+            } else if self.selected_index().is_some() {
                 self.exit_use_mode();
+                self.exit_warrior_mode();
                 self.selection_mode = SelectionMode::MoveTarget;
                 set_move_mode_active(true);
             }
@@ -1669,23 +1759,43 @@ impl GameState {
         if take_pending_use_toggle() {
             if self.selection_mode == SelectionMode::UseTarget {
                 self.exit_use_mode();
-            } else if self.selected_drone.is_some() {
+            } else if self.selected_index().is_some() {
                 self.exit_move_mode();
+                self.exit_warrior_mode();
                 self.selection_mode = SelectionMode::UseTarget;
                 set_use_mode_active(true);
             }
         }
 
-        if self.selection_mode == SelectionMode::MoveTarget && self.selected_drone.is_none() {
+        if take_pending_warrior_toggle() {
+            if self.selection_mode == SelectionMode::BuildWarrior {
+                self.exit_warrior_mode();
+            } else {
+                self.exit_move_mode();
+                self.exit_use_mode();
+                self.selection_mode = SelectionMode::BuildWarrior;
+                set_warrior_mode_active(true);
+            }
+        }
+
+        if take_pending_deposit_request() {
+            self.try_deposit_iron();
+        }
+
+        if take_pending_pick_up_request() {
+            self.try_pick_up_drops();
+        }
+
+        if self.selection_mode == SelectionMode::MoveTarget && self.selected_index().is_none() {
             self.exit_move_mode();
         }
 
-        if self.selection_mode == SelectionMode::UseTarget && self.selected_drone.is_none() {
+        if self.selection_mode == SelectionMode::UseTarget && self.selected_index().is_none() {
             self.exit_use_mode();
         }
 
         if let Some(request) = take_pending_tool_slot() {
-            if let Some(selected_index) = self.selected_drone {
+            if let Some(selected_index) = self.selected_index() {
                 if let Some(slots) = self.world.inventory(selected_index) {
                     match request {
                         Some(slot_index) => {
@@ -1704,8 +1814,8 @@ impl GameState {
             self.sync_tool_ui();
         }
 
-        if let Some(slot_index) = take_pending_core_request() {
-            self.try_create_core_from_slot(slot_index);
+        if let Some(recipe) = take_pending_craft_request() {
+            self.try_craft(recipe);
         }
     }
 
@@ -1735,35 +1845,27 @@ impl GameState {
             SelectionMode::Inspect => self.apply_selection_click(),
             SelectionMode::MoveTarget => self.handle_move_target_click(),
             SelectionMode::UseTarget => self.handle_use_target_click(),
-        }
-    }
-
-    fn ensure_order_capacity(&mut self) {
-        let drone_count = self.world.drones().len();
-        if self.active_orders.len() < drone_count {
-            self.active_orders.resize(drone_count, None);
+            SelectionMode::BuildWarrior => self.handle_build_warrior_click(),
         }
     }
 
     fn order_status_for(&self, index: usize) -> Option<String> {
-        self.active_orders
-            .get(index)
-            .and_then(|order| order.as_ref())
-            .map(|order| order.status_text())
+        self.world.current_order(index).map(Order::status_text)
     }
 
     fn order_progress_percent(&self, index: usize) -> Option<u32> {
-        self.active_orders
-            .get(index)
-            .and_then(|order| order.as_ref())
-            .and_then(|order| order.progress_percent())
+        self.world
+            .current_order(index)
+            .and_then(Order::progress_percent)
     }
 
     fn apply_selection_click(&mut self) {
         let (mouse_x, mouse_y) = mouse_position();
         let screen_pos = vec2(mouse_x, mouse_y);
         let effective_block_size = BLOCK_PIXEL_SIZE as f32 * self.zoom;
-        let next_selection = self.find_drone_at_screen(screen_pos, effective_block_size);
+        let next_selection = self
+            .find_drone_at_screen(screen_pos, effective_block_size)
+            .map(|index| self.world.drones()[index].id);
 
         let previous_selection = self.selected_drone;
         self.selected_drone = next_selection;
@@ -1771,8 +1873,7 @@ impl GameState {
             self.tool_controller.clear_selection();
             self.sync_tool_ui();
         }
-        if let Some(index) = self.selected_drone {
-            self.ensure_order_capacity();
+        if let Some(index) = self.selected_index() {
             self.selected_order = self.order_status_for(index);
         } else {
             self.selected_order = None;
@@ -1780,14 +1881,6 @@ impl GameState {
         self.sync_selected_ui();
     }
 
-    fn apply_heading_from_direction(drone: &mut DronePose, direction: Vec3) {
-        let heading = vec2(direction.x, direction.y);
-        if heading.length_squared() > f32::EPSILON {
-            let normalized = heading.normalize();
-            drone.heading = [normalized.x, normalized.y];
-        }
-    }
-
     fn tile_coords_from_world(target_world: Vec3) -> (i32, i32, i32) {
         (
             target_world.x.floor() as i32,
@@ -1796,26 +1889,8 @@ impl GameState {
         )
     }
 
-    fn tile_blocked(chunk_cache: &ChunkCache, coords: (i32, i32, i32)) -> bool {
-        let (target_tile_x, target_tile_y, target_tile_z) = coords;
-        is_solid_opt(block_at(
-            chunk_cache,
-            target_tile_x,
-            target_tile_y,
-            target_tile_z,
-        ))
-    }
-
-    fn wall_adjacent_to_air(&self, target_tile: (i32, i32, i32)) -> bool {
-        let (x, y, z) = target_tile;
-        let neighbours = [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)];
-        neighbours
-            .iter()
-            .any(|(nx, ny)| !is_solid_opt(block_at(&self.chunk_cache, *nx, *ny, z)))
-    }
-
     fn handle_move_target_click(&mut self) {
-        let Some(selected_index) = self.selected_drone else {
+        let Some(selected_index) = self.selected_index() else {
             self.exit_move_mode();
             return;
         };
@@ -1825,56 +1900,43 @@ impl GameState {
         let effective_block_size = BLOCK_PIXEL_SIZE as f32 * self.zoom;
         let target_world = self.screen_to_world(screen_pos, effective_block_size);
 
-        self.ensure_order_capacity();
-
         let target_tile = Self::tile_coords_from_world(target_world);
-        let current_position = if let Some(drone) = self.world.drones().get(selected_index) {
-            Vec3::new(drone.position[0], drone.position[1], drone.position[2])
-        } else {
+        let Some(drone) = self.world.drones().get(selected_index).cloned() else {
             self.exit_move_mode();
             return;
         };
-        let start_tile = Self::tile_coords_from_world(current_position);
-
-        let blocked_tile = if Self::tile_blocked(&self.chunk_cache, target_tile) {
-            Some(target_tile)
-        } else {
-            let start_coord = WorldCoord::new(start_tile.0, start_tile.1, start_tile.2);
-            let end_coord = WorldCoord::new(target_tile.0, target_tile.1, target_tile.2);
-            self.chunk_cache
-                .first_solid_on_line(start_coord, end_coord)
-                .map(|coord| (coord.x, coord.y, coord.z))
-        };
 
-        if let Some((blocked_x, blocked_y, blocked_z)) = blocked_tile {
-            let mut status = format!(
-                "blocked by wall at {}, {}, {}",
-                blocked_x, blocked_y, blocked_z
-            );
-            if let Some(existing) = self.order_status_for(selected_index) {
-                status.push_str("; still ");
-                status.push_str(&existing);
+        let target_coord = WorldCoord::new(target_tile.0, target_tile.1, target_tile.2);
+        let planned = match MoveOrder::plan(self.world.chunk_cache_mut(), &drone, target_coord) {
+            Ok(planned) => planned,
+            Err(err) => {
+                let mut status = match err.reason {
+                    PathErrorReason::Unreachable | PathErrorReason::SearchLimit => format!(
+                        "{}: {}, {}, {}",
+                        err, target_tile.0, target_tile.1, target_tile.2
+                    ),
+                    _ => err.to_string(),
+                };
+                if let Some(existing) = self.order_status_for(selected_index) {
+                    status.push_str("; still ");
+                    status.push_str(&existing);
+                }
+                self.selected_order = Some(status);
+                self.exit_move_mode();
+                self.sync_selected_ui();
+                return;
             }
-            self.selected_order = Some(status);
-            self.exit_move_mode();
-            self.sync_selected_ui();
-            return;
-        }
+        };
 
-        match MoveOrder::for_target(current_position, target_tile) {
+        match planned {
             Some(order) => {
-                if let Some(slot) = self.active_orders.get_mut(selected_index) {
-                    *slot = Some(Order::Move(order.clone()));
-                }
-                if let Some(drone) = self.world.drones_mut().get_mut(selected_index) {
-                    Self::apply_heading_from_direction(drone, order.direction);
-                }
                 self.selected_order = Some(order.status_text());
+                if let Err(err) = self.world.issue_order(selected_index, Order::Move(order)) {
+                    self.selected_order = Some(err.to_string());
+                }
             }
             None => {
-                if let Some(slot) = self.active_orders.get_mut(selected_index) {
-                    *slot = None;
-                }
+                self.world.clear_orders(selected_index);
                 self.selected_order = Some(format!(
                     "already at {}, {}, {}",
                     target_tile.0, target_tile.1, target_tile.2
@@ -1886,7 +1948,7 @@ impl GameState {
     }
 
     fn handle_use_target_click(&mut self) {
-        let Some(selected_index) = self.selected_drone else {
+        let Some(selected_index) = self.selected_index() else {
             self.exit_use_mode();
             return;
         };
@@ -1896,36 +1958,21 @@ impl GameState {
         let effective_block_size = BLOCK_PIXEL_SIZE as f32 * self.zoom;
         let target_world = self.screen_to_world(screen_pos, effective_block_size);
 
-        self.ensure_order_capacity();
-
         let target_tile = Self::tile_coords_from_world(target_world);
-        let current_position = if let Some(drone) = self.world.drones().get(selected_index) {
-            Vec3::new(drone.position[0], drone.position[1], drone.position[2])
+        let drone_tile = if let Some(drone) = self.world.drones().get(selected_index) {
+            Self::tile_coords_from_world(Vec3::from_array(drone.position))
         } else {
             self.exit_use_mode();
             return;
         };
-        let drone_tile = Self::tile_coords_from_world(current_position);
 
         if self.tool_controller.selection().is_some() {
             let target_coord = WorldCoord::new(target_tile.0, target_tile.1, target_tile.2);
-            let drone_coord = WorldCoord::new(drone_tile.0, drone_tile.1, drone_tile.2);
-            let placement_result = if let Some(slots) = self.world.inventory_mut(selected_index) {
-                let start = get_time();
-                let result = self.tool_controller.place_selected_block(
-                    slots,
-                    &mut self.chunk_cache,
-                    drone_coord,
-                    target_coord,
-                );
-                if result.is_ok() {
-                    let elapsed_ms = (get_time() - start) * 1000.0;
-                    self.chunk_cache.record_save_time_ms(elapsed_ms);
-                }
-                result
-            } else {
-                Err(PlacementError::new(PlacementErrorReason::SlotEmpty))
-            };
+            let placement_result = self.world.place_selected_block(
+                &mut self.tool_controller,
+                selected_index,
+                target_coord,
+            );
 
             match placement_result {
                 Ok(_) => {
@@ -1946,106 +1993,75 @@ impl GameState {
             return;
         }
 
-        let mut invalid_status: Option<String> = None;
+        let target_coord = WorldCoord::new(target_tile.0, target_tile.1, target_tile.2);
+        let drone_coord = WorldCoord::new(drone_tile.0, drone_tile.1, drone_tile.2);
+        let issued = validate_dig_target(self.world.chunk_cache(), drone_coord, target_coord)
+            .and_then(|_| {
+                let order = Order::Dig(DigOrder::new(target_coord));
+                self.world.issue_order(selected_index, order)
+            });
 
-        if target_tile.2 != drone_tile.2 {
-            invalid_status = Some("can only dig on the current level".to_string());
-        } else {
-            let dx = target_tile.0 - drone_tile.0;
-            let dy = target_tile.1 - drone_tile.1;
-            let adjacent = dx.abs() <= 1 && dy.abs() <= 1 && !(dx == 0 && dy == 0);
-            if !adjacent {
-                invalid_status = Some("target must be adjacent to the drone".to_string());
-            } else {
-                let target_block = block_at(
-                    &self.chunk_cache,
-                    target_tile.0,
-                    target_tile.1,
-                    target_tile.2,
-                );
-                if !is_solid_opt(target_block) {
-                    invalid_status = Some("no wall to dig here".to_string());
-                } else if target_block == Some(BEDROCK) {
-                    invalid_status = Some("bedrock cannot be dug".to_string());
-                } else if dx != 0 && dy != 0 && !self.wall_adjacent_to_air(target_tile) {
-                    invalid_status =
-                        Some("diagonal walls must touch air to dig safely".to_string());
-                }
+        self.selected_order = match issued {
+            Ok(()) => self.order_status_for(selected_index),
+            Err(err) => Some(err.to_string()),
+        };
+        self.exit_use_mode();
+        self.sync_selected_ui();
+    }
+
+    fn handle_build_warrior_click(&mut self) {
+        let (mouse_x, mouse_y) = mouse_position();
+        let effective_block_size = BLOCK_PIXEL_SIZE as f32 * self.zoom;
+        let target_world = self.screen_to_world(vec2(mouse_x, mouse_y), effective_block_size);
+        let (x, y, z) = Self::tile_coords_from_world(target_world);
+
+        self.build_status = Some(match self.world.place_warrior(WorldCoord::new(x, y, z)) {
+            Ok(_) => format!("warrior placed at {}, {}, {}", x, y, z),
+            Err(err) => {
+                log_ui_action(&format!("warrior placement failed: {}", err));
+                err.to_string()
             }
-        }
+        });
+        self.sync_hud_ui();
+    }
 
-        if let Some(status) = invalid_status {
-            self.selected_order = Some(status);
-            self.exit_use_mode();
-            self.sync_selected_ui();
+    fn try_deposit_iron(&mut self) {
+        let Some(selected_index) = self.selected_index() else {
             return;
-        }
-
-        let direction = Vec3::new(
-            (target_tile.0 - drone_tile.0) as f32,
-            (target_tile.1 - drone_tile.1) as f32,
-            0.0,
-        );
-        let order = DigOrder::new(target_tile);
-
-        if let Some(slot) = self.active_orders.get_mut(selected_index) {
-            *slot = Some(Order::Dig(order.clone()));
-        }
-        if let Some(drone) = self.world.drones_mut().get_mut(selected_index) {
-            Self::apply_heading_from_direction(drone, direction);
-        }
-        self.selected_order = Some(order.status_text());
-        self.exit_use_mode();
+        };
+        self.selected_order =
+            match self
+                .world
+                .deposit_items(selected_index, ITEM_IRON_ORE, u32::MAX)
+            {
+                Ok(count) => Some(format!("deposited {} iron", count)),
+                Err(err) => Some(err.to_string()),
+            };
+        self.sync_hud_ui();
         self.sync_selected_ui();
     }
 
-    fn try_create_core_from_slot(&mut self, slot_index: usize) {
-        let Some(selected_index) = self.selected_drone else {
+    fn try_pick_up_drops(&mut self) {
+        let Some(selected_index) = self.selected_index() else {
             return;
         };
-        let Some(slots) = self.world.inventory_mut(selected_index) else {
-            return;
+        self.selected_order = match self.world.pick_up_drops(selected_index) {
+            Ok(0) => Some("nothing to pick up".to_string()),
+            Ok(count) => Some(format!("picked up {} items", count)),
+            Err(err) => Some(err.to_string()),
         };
-        if slot_index >= slots.len() {
-            self.selected_order = Some("invalid inventory slot".to_string());
-            self.sync_selected_ui();
-            return;
-        }
-
-        {
-            let slot = &mut slots[slot_index];
-            if slot.block != Some(STONE) || slot.count == 0 {
-                self.selected_order = Some("needs stone to create core".to_string());
-                self.sync_selected_ui();
-                return;
-            }
-
-            // Consume one stone.
-            slot.count = slot.count.saturating_sub(1);
-            if slot.count == 0 {
-                slot.block = None;
-            }
-        }
+        self.refresh_tool_selection();
+        self.sync_selected_ui();
+    }
 
-        let added = add_block_to_slots(slots, CORE);
-        if !added {
-            if let Some(slot) = slots.get_mut(slot_index) {
-                // Restore the stone if we could not place the core.
-                if slot.block.is_none() {
-                    slot.block = Some(STONE);
-                    slot.count = 1;
-                } else {
-                    slot.count = slot.count.saturating_add(1);
-                }
-            }
-            self.selected_order = Some("inventory full; core not created".to_string());
-            self.sync_selected_ui();
+    fn try_craft(&mut self, recipe: RecipeId) {
+        let Some(selected_index) = self.selected_index() else {
             return;
-        }
-
-        self.selected_order = Some("created core from stone".to_string());
-
-        self.tool_controller.refresh_from_inventory(slots);
+        };
+        self.selected_order = match self.world.craft(selected_index, recipe) {
+            Ok(()) => self.order_status_for(selected_index),
+            Err(err) => Some(err.to_string()),
+        };
         self.sync_selected_ui();
     }
 
@@ -2066,7 +2082,7 @@ impl GameState {
             if distance <= selection_radius {
                 let replace = closest
                     .as_ref()
-                    .map_or(true, |(_, best_distance)| distance < *best_distance);
+                    .is_none_or(|(_, best_distance)| distance < *best_distance);
                 if replace {
                     closest = Some((index, distance));
                 }
@@ -2077,79 +2093,79 @@ impl GameState {
     }
 
     fn refresh_tool_selection(&mut self) {
-        if let Some(selected_index) = self.selected_drone {
-            if let Some(slots) = self.world.inventory(selected_index) {
-                self.tool_controller.refresh_from_inventory(slots);
-                return;
-            }
+        if let Some(selected_index) = self.selected_index()
+            && let Some(slots) = self.world.inventory(selected_index)
+        {
+            self.tool_controller.refresh_from_inventory(slots);
+            return;
         }
         self.tool_controller.clear_selection();
     }
 
     fn sync_tool_ui(&self) {
         let mut ui = selected_tool_ui().lock().unwrap();
-        ui.block = AIR;
+        ui.item = 0;
         ui.count = 0;
-        if let Some(selected_index) = self.selected_drone {
-            if let Some(selection) = self.tool_controller.selection() {
-                if let Some(slots) = self.world.inventory(selected_index) {
-                    if let Some(slot) = slots.get(selection.slot_index) {
-                        if slot.block == Some(selection.block) && slot.count > 0 {
-                            ui.block = selection.block;
-                            ui.count = slot.count;
-                        }
-                    }
-                }
-            }
+        if let Some(selected_index) = self.selected_index()
+            && let Some(selection) = self.tool_controller.selection()
+            && let Some(slots) = self.world.inventory(selected_index)
+            && let Some(slot) = slots.get(selection.slot_index)
+            && slot.item == Some(selection.item)
+            && slot.count > 0
+        {
+            ui.item = selection.item;
+            ui.count = slot.count;
         }
     }
 
     fn sync_selected_ui(&self) {
         let mut ui = selected_drone_ui().lock().unwrap();
-        if let Some(selected_index) = self.selected_drone {
-            if let Some(drone) = self.world.drones().get(selected_index) {
-                ui.present = true;
-                ui.name.clear();
-                ui.name.push_str(&drone.name);
-                ui.health = drone.health;
-                ui.max_health = drone.max_health;
-                ui.status.clear();
-                if let Some(order_status) = self.order_status_for(selected_index) {
-                    ui.status.push_str(&order_status);
-                } else if let Some(order) = &self.selected_order {
-                    ui.status.push_str(order);
-                }
-                if let Some(progress) = self.order_progress_percent(selected_index) {
-                    ui.progress_visible = true;
-                    ui.progress_percent = progress;
-                } else {
-                    ui.progress_visible = false;
-                    ui.progress_percent = 0;
-                }
-                ui.inventory_blocks = [AIR; INVENTORY_SLOTS];
-                ui.inventory_counts = [0; INVENTORY_SLOTS];
-                if let Some(slots) = self.world.inventory(selected_index) {
-                    for (slot_idx, slot) in slots.iter().enumerate() {
-                        if slot_idx >= INVENTORY_SLOTS {
-                            break;
-                        }
-                        ui.inventory_blocks[slot_idx] = slot.block.unwrap_or(AIR);
-                        ui.inventory_counts[slot_idx] = slot.count;
+        if let Some(selected_index) = self.selected_index()
+            && let Some(drone) = self.world.drones().get(selected_index)
+        {
+            ui.present = true;
+            ui.name.clear();
+            ui.name.push_str(&drone.name);
+            ui.health = drone.health;
+            ui.max_health = drone.max_health;
+            ui.state = self.world.drone_status(selected_index).label();
+            ui.status.clear();
+            if let Some(order_status) = self.order_status_for(selected_index) {
+                ui.status.push_str(&order_status);
+            } else if let Some(order) = &self.selected_order {
+                ui.status.push_str(order);
+            }
+            if let Some(progress) = self.order_progress_percent(selected_index) {
+                ui.progress_visible = true;
+                ui.progress_percent = progress;
+            } else {
+                ui.progress_visible = false;
+                ui.progress_percent = 0;
+            }
+            ui.inventory_items = [0; INVENTORY_SLOTS];
+            ui.inventory_counts = [0; INVENTORY_SLOTS];
+            if let Some(slots) = self.world.inventory(selected_index) {
+                for (slot_idx, slot) in slots.iter().enumerate() {
+                    if slot_idx >= INVENTORY_SLOTS {
+                        break;
                     }
+                    ui.inventory_items[slot_idx] = slot.item.unwrap_or(0);
+                    ui.inventory_counts[slot_idx] = slot.count;
                 }
-                self.sync_tool_ui();
-                return;
             }
+            self.sync_tool_ui();
+            return;
         }
 
         ui.present = false;
         ui.name.clear();
         ui.health = 0;
         ui.max_health = 0;
+        ui.state = "";
         ui.status.clear();
         ui.progress_visible = false;
         ui.progress_percent = 0;
-        ui.inventory_blocks = [AIR; INVENTORY_SLOTS];
+        ui.inventory_items = [0; INVENTORY_SLOTS];
         ui.inventory_counts = [0; INVENTORY_SLOTS];
         self.sync_tool_ui();
     }
@@ -2163,50 +2179,10 @@ impl GameState {
         self.selection_mode = SelectionMode::Inspect;
         set_use_mode_active(false);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn tile_blocked_detects_walls_and_air() {
-        let generator = DeterministicMap::new(42);
-        let mut chunk_cache = ChunkCache::new();
-
-        // Populate the chunks that cover the target test coordinates.
-        chunk_cache.populate_chunk_at(&generator, ChunkPosition::new(0, 0, 0));
-        chunk_cache.populate_chunk_at(&generator, ChunkPosition::new(0, 0, 2));
-
-        assert!(GameState::tile_blocked(&chunk_cache, (3, 0, 0))); // stone/iron terrain
-        assert!(!GameState::tile_blocked(&chunk_cache, (0, 0, 10))); // generated air
-    }
-
-    #[test]
-    fn dig_order_reports_progress_in_steps() {
-        let mut order = DigOrder::new((0, 0, 0));
 
-        assert_eq!(order.progress_percent(), 0);
-        let (completed_first, progressed_first) = order.advance(0.2);
-        assert!(!completed_first);
-        assert!(!progressed_first);
-        assert_eq!(order.progress_percent(), 0);
-
-        let (completed_second, progressed_second) = order.advance(0.05);
-        assert!(!completed_second);
-        assert!(progressed_second);
-        assert_eq!(order.progress_percent(), 5);
-
-        let (completed_final, progressed_final) = order.advance(5.0);
-        assert!(completed_final);
-        assert!(progressed_final);
-        assert_eq!(order.progress_percent(), 100);
-    }
-
-    #[test]
-    fn core_is_solid_but_not_a_wall_block() {
-        assert!(is_solid(CORE));
-        assert!(!is_wall_block(CORE));
+    fn exit_warrior_mode(&mut self) {
+        self.selection_mode = SelectionMode::Inspect;
+        set_warrior_mode_active(false);
     }
 }
 
@@ -2270,19 +2246,15 @@ fn block_at(cache: &ChunkCache, x: i32, y: i32, z: i32) -> Option<BlockId> {
 }
 
 fn is_solid(block: BlockId) -> bool {
-    block != AIR
+    BlockRegistry::global().is_solid(block)
 }
 
 fn is_wall_block(block: BlockId) -> bool {
     is_solid(block) && !is_placable_block(block)
 }
 
-fn is_solid_opt(block: Option<BlockId>) -> bool {
-    block.map_or(false, is_solid)
-}
-
 fn is_wall_opt(block: Option<BlockId>) -> bool {
-    block.map_or(false, is_wall_block)
+    block.is_some_and(is_wall_block)
 }
 
 fn wall_edge_mask(cache: &ChunkCache, x: i32, y: i32, z: i32) -> u8 {
@@ -2306,11 +2278,7 @@ fn wall_edge_mask(cache: &ChunkCache, x: i32, y: i32, z: i32) -> u8 {
 
 pub async fn run() {
     install_panic_hook();
-    let generator = DeterministicMap::new(42);
-    let cache_capacity =
-        ChunkCache::chunk_count_for_limits(HORIZONTAL_LIMIT, VERTICAL_LIMIT).max(1);
-    let chunk_cache = ChunkCache::with_capacity(cache_capacity);
-    let mut game = GameState::new_with_cache(generator, chunk_cache).await;
+    let mut game = GameState::new().await;
     game.initialize_camera_center();
     let mut accumulator = 0.0_f32;
 
@@ -2356,3 +2324,62 @@ fn install_panic_hook() {
 
 #[cfg(not(target_arch = "wasm32"))]
 fn install_panic_hook() {}
+
+/// Clears `selected` once its drone has left `world`. Returns whether it did.
+fn forget_dead_selection(world: &World, selected: &mut Option<DroneId>) -> bool {
+    let gone = selected.is_some_and(|id| world.drone_index(id).is_none());
+    if gone {
+        *selected = None;
+    }
+    gone
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_planning_detects_walls_and_air() {
+        let generator = DeterministicMap::new(42);
+        let mut chunk_cache = ChunkCache::new();
+
+        // Populate the chunks that cover the target test coordinates.
+        chunk_cache.populate_chunk_at(&generator, ChunkPosition::new(0, 0, 0));
+        chunk_cache.populate_chunk_at(&generator, ChunkPosition::new(0, 0, 2));
+
+        let underground = DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10);
+        let err =
+            MoveOrder::plan(&mut chunk_cache, &underground, WorldCoord::new(3, 0, 0)).unwrap_err();
+        assert!(matches!(err.reason, PathErrorReason::TargetBlocked(_))); // stone/iron terrain
+
+        let airborne = DronePose::new([0.0, 0.0, 10.0], [1.0, 0.0], "d1", 10, 10);
+        let order =
+            MoveOrder::plan(&mut chunk_cache, &airborne, WorldCoord::new(3, 0, 10)).unwrap();
+        assert!(order.is_some()); // generated air
+    }
+
+    #[test]
+    fn selection_is_dropped_when_the_selected_drone_dies() {
+        let mut world = World::new();
+        world.set_drones(vec![
+            DronePose::new([0.0, 0.0, 0.0], [1.0, 0.0], "d1", 10, 10),
+            DronePose::new([1.0, 0.0, 0.0], [1.0, 0.0], "d2", 10, 10),
+        ]);
+        let mut selected = Some(world.drones()[1].id);
+        let mut survivor = Some(world.drones()[0].id);
+
+        world.drones_mut()[1].health = 0;
+        world.step();
+
+        assert!(forget_dead_selection(&world, &mut selected));
+        assert_eq!(selected, None);
+        assert!(!forget_dead_selection(&world, &mut survivor));
+        assert_eq!(survivor, Some(0));
+    }
+
+    #[test]
+    fn core_is_solid_but_not_a_wall_block() {
+        assert!(is_solid(CORE));
+        assert!(!is_wall_block(CORE));
+    }
+}
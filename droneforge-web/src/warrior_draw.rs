@@ -0,0 +1,37 @@
+use macroquad::prelude::{Color, Vec2, draw_circle, draw_rectangle, draw_rectangle_lines};
+
+pub const WARRIOR_COLOR: Color = Color::from_rgba(232, 196, 72, 255);
+pub const WARRIOR_SPOT_COLOR: Color = Color::from_rgba(168, 140, 60, 255);
+pub const DROP_COLOR: Color = Color::from_rgba(214, 214, 214, 255);
+
+/// Outline an empty warrior spot so it reads as a build target rather than floor.
+pub fn draw_warrior_spot_at_screen(center: Vec2, tile_size: f32) {
+    let inset = tile_size * 0.15;
+    let side = tile_size - inset * 2.0;
+    let thickness = (tile_size * 0.08).max(1.0);
+    draw_rectangle_lines(
+        center.x - side * 0.5,
+        center.y - side * 0.5,
+        side,
+        side,
+        thickness,
+        WARRIOR_SPOT_COLOR,
+    );
+}
+
+pub fn draw_warrior_at_screen(center: Vec2, tile_size: f32) {
+    let radius = (tile_size * 0.3).max(1.0);
+    draw_circle(center.x, center.y, radius, WARRIOR_COLOR);
+}
+
+/// A small crate marking items a dead drone left behind.
+pub fn draw_drop_at_screen(center: Vec2, tile_size: f32) {
+    let side = (tile_size * 0.3).max(1.0);
+    draw_rectangle(
+        center.x - side * 0.5,
+        center.y - side * 0.5,
+        side,
+        side,
+        DROP_COLOR,
+    );
+}
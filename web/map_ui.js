@@ -16,39 +16,38 @@ tilesetImage.onload = () => {
     tilesetReady = true;
 };
 
-const BLOCK_NAME_BY_ID = {
-    1: "dirt",
-    2: "stone",
-    3: "iron",
-    4: "bedrock",
+const FALLBACK_STONE_ITEM_ID = 1;
+const FALLBACK_CORE_ITEM_ID = 5;
+const FALLBACK_CORE_RECIPE_ID = 1;
+
+const itemIdFromWasm = (exportName, fallback) => {
+    const fn = wasm_exports?.[exportName];
+    return typeof fn === "function" ? fn() : fallback;
 };
 
-const STONE_BLOCK_ID = 2;
-let cachedCoreBlockId = null;
+const stoneItemId = () => itemIdFromWasm("stone_item_id", FALLBACK_STONE_ITEM_ID);
+const coreItemId = () => itemIdFromWasm("core_item_id", FALLBACK_CORE_ITEM_ID);
+const coreRecipeId = () => itemIdFromWasm("core_recipe_id", FALLBACK_CORE_RECIPE_ID);
 
-const resolveCoreBlockId = () => {
-    const fn = wasm_exports?.core_block_id;
-    if (typeof fn === "function") {
-        cachedCoreBlockId = fn();
-    } else if (cachedCoreBlockId === null) {
-        cachedCoreBlockId = 5;
+const itemName = (itemId) => {
+    const ptrFn = wasm_exports?.item_name_ptr;
+    const lenFn = wasm_exports?.item_name_len;
+    if (typeof ptrFn !== "function" || typeof lenFn !== "function") {
+        return "";
     }
-    return cachedCoreBlockId;
+    return readWasmString(ptrFn(itemId), lenFn(itemId)).toLowerCase();
 };
 
-const ensureCoreNameRegistered = () => {
-    const coreId = resolveCoreBlockId();
-    if (coreId && !BLOCK_NAME_BY_ID[coreId]) {
-        BLOCK_NAME_BY_ID[coreId] = "core";
-    }
+const itemIconBlock = (itemId) => {
+    const fn = wasm_exports?.item_icon_block;
+    return typeof fn === "function" ? fn(itemId) : itemId;
 };
 
-const describeInventorySlot = (blockId, count) => {
-    ensureCoreNameRegistered();
-    if (!blockId || !count) {
+const describeInventorySlot = (itemId, count) => {
+    if (!itemId || !count) {
         return "Empty slot";
     }
-    const name = BLOCK_NAME_BY_ID[blockId] || `block ${blockId}`;
+    const name = itemName(itemId) || `item ${itemId}`;
     return count > 1 ? `${name} x${count}` : name;
 };
 
@@ -72,6 +71,10 @@ window.addEventListener("load", () => {
     const selectionMove = document.getElementById("selection-move");
     const selectionUse = document.getElementById("selection-use");
     const selectionTool = document.getElementById("selection-tool");
+    const selectionDeposit = document.getElementById("selection-deposit");
+    const selectionPickUp = document.getElementById("selection-pick-up");
+    const buildWarrior = document.getElementById("build-warrior");
+    const coreHp = document.getElementById("core-hp");
     const inventoryPanel = document.getElementById("inventory-panel");
     const inventoryGrid = document.getElementById("inventory-grid");
     const inventorySelection = document.getElementById("inventory-selection");
@@ -85,13 +88,13 @@ window.addEventListener("load", () => {
     const inventoryCanvases = [];
     const inventoryCounts = [];
     const inventorySlots = [];
-    const inventorySlotBlocks = Array(INVENTORY_SLOTS).fill(0);
+    const inventorySlotItems = Array(INVENTORY_SLOTS).fill(0);
     const inventorySlotCounts = Array(INVENTORY_SLOTS).fill(0);
     let lastSelectedSlot = null;
     let inventoryVisible = false;
     let toolPreviewCanvas = null;
     let toolPreviewCtx = null;
-    let lastToolBlock = 0;
+    let lastToolItem = 0;
     let lastToolCount = 0;
     let inventoryActionMenu = null;
     let inventoryActionSlot = null;
@@ -137,13 +140,12 @@ window.addEventListener("load", () => {
             inventorySelection.textContent = "";
             return;
         }
-        const block = inventorySlotBlocks[lastSelectedSlot] ?? 0;
+        const item = inventorySlotItems[lastSelectedSlot] ?? 0;
         const count = inventorySlotCounts[lastSelectedSlot] ?? 0;
-        inventorySelection.textContent = describeInventorySlot(block, count);
+        inventorySelection.textContent = describeInventorySlot(item, count);
     };
 
     const selectToolSlot = (slotIndex) => {
-        const block = inventorySlotBlocks[slotIndex] ?? 0;
         const count = inventorySlotCounts[slotIndex] ?? 0;
         if (count > 0) {
             const fn = wasm_exports?.tool_select_slot;
@@ -255,9 +257,9 @@ window.addEventListener("load", () => {
         actionBtn.textContent = "create core";
         actionBtn.addEventListener("click", () => {
             if (inventoryActionSlot !== null) {
-                const fn = wasm_exports?.inventory_create_core;
+                const fn = wasm_exports?.craft_recipe;
                 if (typeof fn === "function") {
-                    fn(inventoryActionSlot);
+                    fn(coreRecipeId());
                 }
                 closeInventoryActionMenu();
                 renderInventorySlots();
@@ -300,9 +302,9 @@ window.addEventListener("load", () => {
 
     const openInventoryActionMenu = (slotIndex, anchor) => {
         createInventoryActionMenu();
-        const block = inventorySlotBlocks[slotIndex] ?? 0;
+        const item = inventorySlotItems[slotIndex] ?? 0;
         const count = inventorySlotCounts[slotIndex] ?? 0;
-        if (block !== STONE_BLOCK_ID || count <= 0) {
+        if (item !== stoneItemId() || count <= 0) {
             closeInventoryActionMenu();
             return;
         }
@@ -330,20 +332,19 @@ window.addEventListener("load", () => {
             return;
         }
 
-        const blockFn = wasm_exports?.selected_tool_block;
+        const itemFn = wasm_exports?.selected_tool_item;
         const countFn = wasm_exports?.selected_tool_count;
         const tileXFn = wasm_exports?.block_tile_pixel_x;
         const tileYFn = wasm_exports?.block_tile_pixel_y;
         const tileSize = tileSizeFromWasm();
 
-        const block = typeof blockFn === "function" ? blockFn() : 0;
+        const item = typeof itemFn === "function" ? itemFn() : 0;
         const count = typeof countFn === "function" ? countFn() : 0;
-        const isCoreBlock = block === resolveCoreBlockId();
 
-        if (block === lastToolBlock && count === lastToolCount) {
+        if (item === lastToolItem && count === lastToolCount) {
             return;
         }
-        lastToolBlock = block;
+        lastToolItem = item;
         lastToolCount = count;
 
         toolPreviewCtx.clearRect(
@@ -352,13 +353,13 @@ window.addEventListener("load", () => {
             toolPreviewCanvas.width,
             toolPreviewCanvas.height
         );
-        selectionTool?.classList.toggle("has-tool", block !== 0 && count > 0);
+        selectionTool?.classList.toggle("has-tool", item !== 0 && count > 0);
 
-        if (block === 0 || count === 0) {
+        if (item === 0 || count === 0) {
             return;
         }
 
-        if (isCoreBlock) {
+        if (item === coreItemId()) {
             drawCoreIcon(
                 toolPreviewCtx,
                 Math.min(toolPreviewCanvas.width, toolPreviewCanvas.height)
@@ -370,6 +371,7 @@ window.addEventListener("load", () => {
             return;
         }
 
+        const block = itemIconBlock(item);
         const tileX =
             typeof tileXFn === "function" ? tileXFn(block) : -1;
         const tileY =
@@ -394,24 +396,23 @@ window.addEventListener("load", () => {
     };
 
     const renderInventorySlots = () => {
-        ensureCoreNameRegistered();
-        const blockFn = wasm_exports?.selected_drone_inventory_slot_block;
+        const itemFn = wasm_exports?.selected_drone_inventory_slot_item;
         const countFn = wasm_exports?.selected_drone_inventory_slot_count;
         const tileXFn = wasm_exports?.block_tile_pixel_x;
         const tileYFn = wasm_exports?.block_tile_pixel_y;
         const tileSize = tileSizeFromWasm();
-        const coreBlockId = resolveCoreBlockId();
+        const coreItem = coreItemId();
 
         for (let i = 0; i < INVENTORY_SLOTS; i += 1) {
-            const block =
-                typeof blockFn === "function" ? blockFn(i) : 0;
+            const item =
+                typeof itemFn === "function" ? itemFn(i) : 0;
             const count =
                 typeof countFn === "function" ? countFn(i) : 0;
             const canvas = inventoryCanvases[i];
             const countEl = inventoryCounts[i];
             const slotEl = inventorySlots[i];
 
-            inventorySlotBlocks[i] = block;
+            inventorySlotItems[i] = item;
             inventorySlotCounts[i] = count;
 
             if (countEl) {
@@ -419,7 +420,7 @@ window.addEventListener("load", () => {
             }
 
             if (slotEl) {
-                slotEl.setAttribute("aria-label", describeInventorySlot(block, count));
+                slotEl.setAttribute("aria-label", describeInventorySlot(item, count));
             }
 
             if (!canvas) continue;
@@ -427,11 +428,11 @@ window.addEventListener("load", () => {
             if (!ctx) continue;
 
             ctx.clearRect(0, 0, canvas.width, canvas.height);
-            if (block === 0 || count === 0) {
+            if (item === 0 || count === 0) {
                 continue;
             }
 
-            if (block === coreBlockId) {
+            if (item === coreItem) {
                 drawCoreIcon(ctx, Math.min(canvas.width, canvas.height));
                 continue;
             }
@@ -440,6 +441,7 @@ window.addEventListener("load", () => {
                 continue;
             }
 
+            const block = itemIconBlock(item);
             const tileX =
                 typeof tileXFn === "function" ? tileXFn(block) : -1;
             const tileY =
@@ -467,14 +469,37 @@ window.addEventListener("load", () => {
         renderToolPreview();
 
         if (inventoryActionSlot !== null) {
-            const activeBlock = inventorySlotBlocks[inventoryActionSlot] ?? 0;
+            const activeItem = inventorySlotItems[inventoryActionSlot] ?? 0;
             const activeCount = inventorySlotCounts[inventoryActionSlot] ?? 0;
-            if (activeBlock !== STONE_BLOCK_ID || activeCount === 0) {
+            if (activeItem !== stoneItemId() || activeCount === 0) {
                 closeInventoryActionMenu();
             }
         }
     };
 
+    const updateCoreHp = () => {
+        const hpFn = wasm_exports?.core_hp;
+        const maxHpFn = wasm_exports?.core_max_hp;
+        const phaseFn = wasm_exports?.game_phase;
+        if (!coreHp || typeof hpFn !== "function" || typeof maxHpFn !== "function") {
+            return;
+        }
+        const hp = hpFn();
+        const maxHp = maxHpFn();
+        const phase = typeof phaseFn === "function" ? phaseFn() : 0;
+        let text = "";
+        if (phase === 1) {
+            text = "Core destroyed - defeat";
+        } else if (phase === 2) {
+            text = "All waves cleared - victory";
+        } else if (hp >= 0) {
+            text = `Core HP ${hp}/${maxHp}`;
+        }
+        coreHp.textContent = text;
+        coreHp.style.display = text ? "block" : "none";
+        coreHp.classList.toggle("is-low", hp >= 0 && hp * 4 <= maxHp);
+    };
+
     const updateInventoryVisibility = (isPresent) => {
         const shouldShow = inventoryVisible && isPresent;
         if (inventoryPanel) {
@@ -510,6 +535,8 @@ window.addEventListener("load", () => {
             const healthMaxFn = wasm_exports?.selected_drone_health_max;
             const statusPtrFn = wasm_exports?.selected_drone_status_ptr;
             const statusLenFn = wasm_exports?.selected_drone_status_len;
+            const statePtrFn = wasm_exports?.selected_drone_state_ptr;
+            const stateLenFn = wasm_exports?.selected_drone_state_len;
 
             const namePtr = typeof namePtrFn === "function" ? namePtrFn() : 0;
             const nameLen = typeof nameLenFn === "function" ? nameLenFn() : 0;
@@ -520,11 +547,15 @@ window.addEventListener("load", () => {
             const statusLen = typeof statusLenFn === "function" ? statusLenFn() : 0;
             const status =
                 statusLen > 0 ? readWasmString(statusPtr, statusLen) : "";
+            const statePtr = typeof statePtrFn === "function" ? statePtrFn() : 0;
+            const stateLen = typeof stateLenFn === "function" ? stateLenFn() : 0;
+            const state = stateLen > 0 ? readWasmString(statePtr, stateLen) : "";
 
             if (selectionText) {
                 const safeName = name || "???";
                 const maxDisplay = hpMax > 0 ? hpMax : 0;
-                const headerText = `drone ${safeName} hp ${hp}/${maxDisplay}`;
+                const stateText = state ? ` [${state}]` : "";
+                const headerText = `drone ${safeName} hp ${hp}/${maxDisplay}${stateText}`;
                 selectionText.textContent = status
                     ? `${headerText}\n${status}`
                     : headerText;
@@ -601,6 +632,18 @@ window.addEventListener("load", () => {
             );
         }
         renderToolPreview();
+        updateCoreHp();
+
+        const warriorActiveFn = wasm_exports?.warrior_mode_active;
+        const warriorIsActive =
+            typeof warriorActiveFn === "function" && warriorActiveFn() === 1;
+        if (buildWarrior) {
+            buildWarrior.classList.toggle("is-active", warriorIsActive);
+            buildWarrior.setAttribute(
+                "aria-pressed",
+                warriorIsActive ? "true" : "false"
+            );
+        }
         requestAnimationFrame(pumpSelectionUi);
     };
 
@@ -626,6 +669,44 @@ window.addEventListener("load", () => {
         });
     }
 
+    if (selectionDeposit) {
+        selectionDeposit.addEventListener("click", () => {
+            const fn = wasm_exports?.deposit_selected_iron;
+            if (typeof fn === "function") {
+                fn();
+            }
+        });
+    }
+
+    if (selectionPickUp) {
+        selectionPickUp.addEventListener("click", () => {
+            const fn = wasm_exports?.pick_up_drops;
+            if (typeof fn === "function") {
+                fn();
+            }
+        });
+    }
+
+    const toggleBuildWarrior = () => {
+        const fn = wasm_exports?.build_warrior_mode;
+        if (typeof fn === "function") {
+            fn();
+        }
+    };
+
+    if (buildWarrior) {
+        buildWarrior.addEventListener("click", toggleBuildWarrior);
+    }
+
+    window.addEventListener("keydown", (event) => {
+        if (event.repeat || event.ctrlKey || event.metaKey || event.altKey) {
+            return;
+        }
+        if (event.key === "w" || event.key === "W") {
+            toggleBuildWarrior();
+        }
+    });
+
     if (selectionTool) {
         selectionTool.disabled = false;
         selectionTool.addEventListener("click", () => {